@@ -0,0 +1,120 @@
+//! A tree form of the command list, with loops as nodes holding their bodies instead of
+//! jumps with baked-in targets.
+
+use crate::parser::{Cmd, Op, Span};
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum Node {
+    /// Any command other than a jump.
+    Cmd(Cmd),
+    /// A `[...]` loop around its body.
+    Loop(Vec<Node>),
+}
+
+/// A [`Node`] that also knows where each of its commands and brackets starts in the
+/// source, so passes over the tree can keep track of it.
+#[derive(PartialEq, Debug, Clone)]
+pub(crate) enum SpannedNode {
+    Cmd(Cmd, Span),
+    Loop {
+        open: Span,
+        body: Vec<SpannedNode>,
+        close: Span,
+    },
+}
+
+impl From<SpannedNode> for Node {
+    fn from(node: SpannedNode) -> Self {
+        match node {
+            SpannedNode::Cmd(cmd, _) => Node::Cmd(cmd),
+            SpannedNode::Loop { body, .. } => {
+                Node::Loop(body.into_iter().map(Node::from).collect())
+            }
+        }
+    }
+}
+
+impl From<&Node> for SpannedNode {
+    fn from(node: &Node) -> Self {
+        match node {
+            Node::Cmd(cmd) => SpannedNode::Cmd(*cmd, Span::default()),
+            Node::Loop(body) => SpannedNode::Loop {
+                open: Span::default(),
+                body: body.iter().map(SpannedNode::from).collect(),
+                close: Span::default(),
+            },
+        }
+    }
+}
+
+/// Turns a flat command list into a tree, following the jump targets. `cmds` must have
+/// balanced jumps, as [`Parser::parse_all`](crate::Parser::parse_all) guarantees.
+pub fn lift(cmds: &[Cmd]) -> Vec<Node> {
+    let spanned = cmds.iter().map(|&cmd| (cmd, Span::default()));
+    lift_spanned(spanned).into_iter().map(Node::from).collect()
+}
+
+/// [`lift`], keeping the span that goes with each command.
+pub(crate) fn lift_spanned(cmds: impl IntoIterator<Item = (Cmd, Span)>) -> Vec<SpannedNode> {
+    // Each open loop along with the span of its `[`
+    let mut stack = vec![(Span::default(), Vec::new())];
+
+    for (cmd, span) in cmds {
+        match cmd.operator {
+            Op::JmpZero => stack.push((span, Vec::new())),
+            Op::JmpNonZero => {
+                let (open, body) = stack.pop().expect("jumps are balanced");
+                stack
+                    .last_mut()
+                    .expect("jumps are balanced")
+                    .1
+                    .push(SpannedNode::Loop {
+                        open,
+                        body,
+                        close: span,
+                    });
+            }
+            _ => stack
+                .last_mut()
+                .unwrap()
+                .1
+                .push(SpannedNode::Cmd(cmd, span)),
+        }
+    }
+
+    let (_, nodes) = stack.pop().unwrap();
+    assert!(stack.is_empty(), "jumps are balanced");
+    nodes
+}
+
+/// Turns a tree back into a flat command list, with every jump pointing past its
+/// matching bracket.
+pub fn lower(nodes: &[Node]) -> Vec<Cmd> {
+    let spanned: Vec<_> = nodes.iter().map(SpannedNode::from).collect();
+    lower_spanned(&spanned)
+        .into_iter()
+        .map(|(cmd, _)| cmd)
+        .collect()
+}
+
+/// [`lower`], keeping the span that goes with each command.
+pub(crate) fn lower_spanned(nodes: &[SpannedNode]) -> Vec<(Cmd, Span)> {
+    let mut cmds = Vec::new();
+    lower_into(nodes, &mut cmds);
+    cmds
+}
+
+fn lower_into(nodes: &[SpannedNode], cmds: &mut Vec<(Cmd, Span)>) {
+    for node in nodes {
+        match node {
+            SpannedNode::Cmd(cmd, span) => cmds.push((*cmd, *span)),
+            SpannedNode::Loop { open, body, close } => {
+                let start = cmds.len();
+                cmds.push((Cmd::new(Op::JmpZero, 0), *open));
+                lower_into(body, cmds);
+                cmds.push((Cmd::new(Op::JmpNonZero, start as isize + 1), *close));
+                cmds[start].0.operand = cmds.len() as isize;
+            }
+        }
+    }
+}
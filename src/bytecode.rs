@@ -0,0 +1,423 @@
+//! A compact binary format for optimized command lists, so programs don't have to be
+//! parsed and optimized again every time they run.
+//!
+//! A file is laid out as:
+//!
+//! ```text
+//! magic        b"BFBC"
+//! version      1 byte, currently 1
+//! cell width   1 byte, in bits
+//! tape policy  1 byte: 0 wrap, 1 error, 2 grow
+//! tape length  varint
+//! count        varint, the number of commands
+//! commands     count times:
+//!                1 byte operator, with the top bit set if an offset follows
+//!                signed varint operand, except for jumps and `zero`
+//!                signed varint offset, if flagged
+//! checksum     CRC-32 of everything before it, 4 bytes little-endian
+//! ```
+//!
+//! Varints are LEB128, with signed values zigzag-encoded first. Jump targets are left out
+//! and worked out from the brackets when reading.
+//!
+//! Files may come from anywhere, so [`decode`] rejects tape lengths, moves and offsets
+//! beyond [`MAX_TAPE_LEN`], as well as repeat counts and scan steps that could never
+//! have come out of the parser.
+
+use std::fmt;
+
+use crate::{
+    config::{CellWidth, Config, TapePolicy},
+    optimizer::link_jumps,
+    parser::{Cmd, Op},
+};
+
+pub const MAGIC: &[u8; 4] = b"BFBC";
+/// The format version written by [`encode`], the only one [`decode`] reads.
+pub const VERSION: u8 = 1;
+
+/// The longest tape, and the furthest move or offset, a file may ask for. Each cell takes
+/// up to 8 bytes, so this keeps a tape within 8 GiB.
+pub const MAX_TAPE_LEN: usize = 1 << 30;
+
+/// Marks an operator byte as followed by an offset.
+const HAS_OFFSET: u8 = 0x80;
+
+/// Operators in the order of their codes.
+const OPS: [Op; 10] = [
+    Op::Add,
+    Op::Move,
+    Op::Out,
+    Op::In,
+    Op::JmpZero,
+    Op::JmpNonZero,
+    Op::SetZero,
+    Op::ScanLeft,
+    Op::ScanRight,
+    Op::MulAdd,
+];
+
+/// A program read back by [`decode`], with the settings it was compiled for.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Program {
+    pub cmds: Vec<Cmd>,
+    pub cell_width: CellWidth,
+    pub tape_len: usize,
+    pub tape_policy: TapePolicy,
+}
+
+impl Program {
+    /// `config` with the program's cell width and tape settings.
+    pub fn config(&self, config: Config) -> Config {
+        Config {
+            cell_width: self.cell_width,
+            tape_len: self.tape_len,
+            tape_policy: self.tape_policy,
+            ..config
+        }
+    }
+}
+
+/// Encodes `cmds` along with the cell width and tape settings of `config`. The other
+/// settings only affect how the program talks to the outside world, and are left to
+/// whoever runs it.
+pub fn encode(cmds: &[Cmd], config: &Config) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(VERSION);
+    bytes.push(config.cell_width.bits() as u8);
+    bytes.push(match config.tape_policy {
+        TapePolicy::Wrap => 0,
+        TapePolicy::Error => 1,
+        TapePolicy::Grow => 2,
+    });
+    write_varint(&mut bytes, config.tape_len as u64);
+    write_varint(&mut bytes, cmds.len() as u64);
+
+    for cmd in cmds {
+        let code = OPS.iter().position(|&op| op == cmd.operator).unwrap() as u8;
+        match cmd.offset {
+            0 => bytes.push(code),
+            _ => bytes.push(code | HAS_OFFSET),
+        }
+        if has_operand(cmd.operator) {
+            write_varint(&mut bytes, zigzag(cmd.operand));
+        }
+        if cmd.offset != 0 {
+            write_varint(&mut bytes, zigzag(cmd.offset));
+        }
+    }
+
+    let checksum = crc32(&bytes);
+    bytes.extend(checksum.to_le_bytes());
+    bytes
+}
+
+/// Reads a program written by [`encode`].
+pub fn decode(bytes: &[u8]) -> Result<Program, BytecodeError> {
+    let mut reader = Reader { bytes, pos: 0 };
+
+    if !bytes.starts_with(MAGIC) {
+        return Err(reader.error(BytecodeErrorKind::NotBytecode));
+    }
+    reader.pos = MAGIC.len();
+    let version = reader.byte()?;
+    if version != VERSION {
+        return Err(reader.error_before(BytecodeErrorKind::UnsupportedVersion(version)));
+    }
+
+    let Some(body_len) = bytes.len().checked_sub(4).filter(|&len| len >= reader.pos) else {
+        return Err(reader.error(BytecodeErrorKind::Truncated));
+    };
+    let (body, checksum) = bytes.split_at(body_len);
+    if crc32(body) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+        return Err(BytecodeError {
+            kind: BytecodeErrorKind::ChecksumMismatch,
+            offset: body_len,
+        });
+    }
+    reader.bytes = body;
+
+    let cell_width = match reader.byte()? {
+        8 => CellWidth::U8,
+        16 => CellWidth::U16,
+        32 => CellWidth::U32,
+        64 => CellWidth::U64,
+        bits => return Err(reader.error_before(BytecodeErrorKind::UnknownCellWidth(bits))),
+    };
+    let tape_policy = match reader.byte()? {
+        0 => TapePolicy::Wrap,
+        1 => TapePolicy::Error,
+        2 => TapePolicy::Grow,
+        code => return Err(reader.error_before(BytecodeErrorKind::UnknownTapePolicy(code))),
+    };
+    let start = reader.pos;
+    let tape_len = reader.size()?;
+    if !(1..=MAX_TAPE_LEN).contains(&tape_len) {
+        return Err(BytecodeError {
+            kind: BytecodeErrorKind::InvalidTapeLength(tape_len),
+            offset: start,
+        });
+    }
+    let count = reader.size()?;
+
+    // Every command takes at least a byte, which bounds what a bad count can allocate
+    let mut cmds = Vec::with_capacity(count.min(body.len()));
+    let mut open = Vec::new();
+    for _ in 0..count {
+        let code = reader.byte()?;
+        let operator = *OPS
+            .get((code & !HAS_OFFSET) as usize)
+            .ok_or_else(|| reader.error_before(BytecodeErrorKind::UnknownOperator(code)))?;
+
+        match operator {
+            Op::JmpZero => open.push(reader.pos - 1),
+            Op::JmpNonZero if open.pop().is_none() => {
+                return Err(reader.error_before(BytecodeErrorKind::UnmatchedJump));
+            }
+            _ => {}
+        }
+
+        let start = reader.pos;
+        let operand = match has_operand(operator) {
+            true => reader.signed()?,
+            false => 0,
+        };
+        if !operand_in_range(operator, operand) {
+            return Err(BytecodeError {
+                kind: BytecodeErrorKind::InvalidOperand(operator, operand),
+                offset: start,
+            });
+        }
+
+        let start = reader.pos;
+        let offset = match code & HAS_OFFSET {
+            0 => 0,
+            _ => reader.signed()?,
+        };
+        if !offset_in_range(offset) {
+            return Err(BytecodeError {
+                kind: BytecodeErrorKind::InvalidOffset(offset),
+                offset: start,
+            });
+        }
+        cmds.push(Cmd {
+            operator,
+            operand,
+            offset,
+        });
+    }
+
+    if let Some(&offset) = open.first() {
+        return Err(BytecodeError {
+            kind: BytecodeErrorKind::UnmatchedJump,
+            offset,
+        });
+    }
+    if reader.pos != body.len() {
+        return Err(reader.error(BytecodeErrorKind::TrailingBytes));
+    }
+    link_jumps(&mut cmds);
+
+    Ok(Program {
+        cmds,
+        cell_width,
+        tape_len,
+        tape_policy,
+    })
+}
+
+/// Whether `op` is stored with its operand. Jump targets are recomputed instead, and
+/// `zero` has none.
+fn has_operand(op: Op) -> bool {
+    !matches!(op, Op::JmpZero | Op::JmpNonZero | Op::SetZero)
+}
+
+/// Whether `operand` is something the parser and optimizer could have produced for `op`.
+/// Repeat counts are positive, scans must move, and moves stay within [`MAX_TAPE_LEN`].
+/// Adds and multiplications wrap, so any value goes.
+pub(crate) fn operand_in_range(op: Op, operand: isize) -> bool {
+    let max = MAX_TAPE_LEN as isize;
+
+    match op {
+        Op::Out | Op::In => (1..=max).contains(&operand),
+        Op::ScanLeft | Op::ScanRight => operand != 0 && operand.unsigned_abs() <= MAX_TAPE_LEN,
+        Op::Move => operand.unsigned_abs() <= MAX_TAPE_LEN,
+        Op::Add | Op::MulAdd | Op::JmpZero | Op::JmpNonZero | Op::SetZero => true,
+    }
+}
+
+/// Whether `offset` stays within [`MAX_TAPE_LEN`] of the memory pointer.
+pub(crate) fn offset_in_range(offset: isize) -> bool {
+    offset.unsigned_abs() <= MAX_TAPE_LEN
+}
+
+fn zigzag(value: isize) -> u64 {
+    ((value << 1) ^ (value >> (isize::BITS - 1))) as u64
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// The CRC-32 used by zlib and PNG.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn error(&self, kind: BytecodeErrorKind) -> BytecodeError {
+        BytecodeError {
+            kind,
+            offset: self.pos,
+        }
+    }
+
+    /// An error about the byte just read.
+    fn error_before(&self, kind: BytecodeErrorKind) -> BytecodeError {
+        BytecodeError {
+            kind,
+            offset: self.pos - 1,
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, BytecodeError> {
+        let byte = *self
+            .bytes
+            .get(self.pos)
+            .ok_or_else(|| self.error(BytecodeErrorKind::Truncated))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> Result<u64, BytecodeError> {
+        let start = self.pos;
+        let mut value = 0u64;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            let bits = u64::from(byte & 0x7F);
+            if bits << shift >> shift != bits {
+                break;
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(BytecodeError {
+            kind: BytecodeErrorKind::NumberTooLarge,
+            offset: start,
+        })
+    }
+
+    fn size(&mut self) -> Result<usize, BytecodeError> {
+        let start = self.pos;
+        let value = self.varint()?;
+        usize::try_from(value).map_err(|_| BytecodeError {
+            kind: BytecodeErrorKind::NumberTooLarge,
+            offset: start,
+        })
+    }
+
+    fn signed(&mut self) -> Result<isize, BytecodeError> {
+        let start = self.pos;
+        let value = self.varint()?;
+        let value = (value >> 1) as i64 ^ -((value & 1) as i64);
+        isize::try_from(value).map_err(|_| BytecodeError {
+            kind: BytecodeErrorKind::NumberTooLarge,
+            offset: start,
+        })
+    }
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum BytecodeErrorKind {
+    /// The file doesn't start with [`MAGIC`].
+    NotBytecode,
+    /// The file was written in a format version other than [`VERSION`].
+    UnsupportedVersion(u8),
+    /// The file ends in the middle of a field.
+    Truncated,
+    /// The checksum doesn't match the contents, which have been corrupted.
+    ChecksumMismatch,
+    UnknownCellWidth(u8),
+    UnknownTapePolicy(u8),
+    UnknownOperator(u8),
+    /// The tape length is zero or more than [`MAX_TAPE_LEN`].
+    InvalidTapeLength(usize),
+    /// An operand the operator can't take, like a negative repeat count.
+    InvalidOperand(Op, isize),
+    /// An offset further away than [`MAX_TAPE_LEN`].
+    InvalidOffset(isize),
+    /// A varint doesn't fit the field it encodes.
+    NumberTooLarge,
+    /// A `jz` without a `jnz` or the other way around.
+    UnmatchedJump,
+    /// There is more data between the last command and the checksum.
+    TrailingBytes,
+}
+
+/// An error in a bytecode file, along with the offset of the byte it was found at.
+#[derive(PartialEq, Eq, Debug)]
+pub struct BytecodeError {
+    pub kind: BytecodeErrorKind,
+    pub offset: usize,
+}
+
+impl fmt::Display for BytecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bytecode error at byte {}: ", self.offset)?;
+
+        match &self.kind {
+            BytecodeErrorKind::NotBytecode => write!(f, "not a bytecode file"),
+            BytecodeErrorKind::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "unsupported format version {version}, expected {VERSION}"
+                )
+            }
+            BytecodeErrorKind::Truncated => write!(f, "unexpected end of file"),
+            BytecodeErrorKind::ChecksumMismatch => {
+                write!(f, "checksum doesn't match, the file is corrupted")
+            }
+            BytecodeErrorKind::UnknownCellWidth(bits) => write!(f, "unknown cell width {bits}"),
+            BytecodeErrorKind::UnknownTapePolicy(code) => {
+                write!(f, "unknown tape policy {code}")
+            }
+            BytecodeErrorKind::UnknownOperator(code) => {
+                write!(f, "unknown operator {code:#04X}")
+            }
+            BytecodeErrorKind::InvalidTapeLength(len) => {
+                write!(f, "tape length {len} is out of range")
+            }
+            BytecodeErrorKind::InvalidOperand(op, operand) => {
+                write!(f, "operand {operand} is out of range for `{op}`")
+            }
+            BytecodeErrorKind::InvalidOffset(offset) => {
+                write!(f, "offset {offset} is out of range")
+            }
+            BytecodeErrorKind::NumberTooLarge => write!(f, "number too large"),
+            BytecodeErrorKind::UnmatchedJump => write!(f, "jump without a matching jump"),
+            BytecodeErrorKind::TrailingBytes => write!(f, "unexpected data after the commands"),
+        }
+    }
+}
+
+impl std::error::Error for BytecodeError {}
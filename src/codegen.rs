@@ -0,0 +1,10 @@
+//! Ahead-of-time backends that translate commands into source code for other toolchains,
+//! or straight into executables.
+
+pub mod asm;
+pub mod c;
+pub mod elf;
+mod linux;
+pub mod llvm;
+pub mod rust;
+pub mod wasm;
@@ -0,0 +1,316 @@
+//! Translates commands into GNU assembler source for x86-64 Linux, in AT&T or Intel
+//! syntax.
+//!
+//! The program is the one [`elf`](super::elf) encodes directly, built once by
+//! [`linux`](super::linux): the tape and output buffer live in `.bss` and the program
+//! talks to the kernel through raw system calls, so it needs no C library to link:
+//!
+//! ```text
+//! as prog.s -o prog.o && ld prog.o -o prog
+//! ```
+
+use std::{fmt::Write, str::FromStr};
+
+use super::linux::{self, Alu, Cond, Inst, Symbol, OUT_BUF};
+use crate::{
+    config::{CellWidth, Config},
+    parser::{Cmd, Span},
+    x86::Reg,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Syntax {
+    /// `addb $1, (%rbx,%r12,1)`, the assembler's default.
+    #[default]
+    Att,
+    /// `add BYTE PTR [rbx+r12], 1`, after `.intel_syntax noprefix`.
+    Intel,
+}
+
+impl FromStr for Syntax {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "att" => Ok(Syntax::Att),
+            "intel" => Ok(Syntax::Intel),
+            _ => Err(format!(
+                "unknown assembly syntax `{s}`, expected att or intel"
+            )),
+        }
+    }
+}
+
+/// Returns assembler source for a program that behaves like `cmds` run with `config`.
+///
+/// Each command's instructions are preceded by a comment with its index and, if `spans`
+/// is given, where it starts in the source. `spans` has to line up with `cmds`, as the
+/// spans of a [`Runner`] built from source do at every optimization level.
+///
+/// Configuration carries over as it does for [`elf::emit`]: [`TapePolicy::Grow`] stops
+/// the program like [`TapePolicy::Error`] does, output is always written as raw bytes and
+/// [`Config::step_limit`] is ignored.
+///
+/// [`Runner`]: crate::Runner::spans
+/// [`elf::emit`]: super::elf::emit
+/// [`TapePolicy::Grow`]: crate::TapePolicy::Grow
+/// [`TapePolicy::Error`]: crate::TapePolicy::Error
+pub fn emit(cmds: &[Cmd], spans: Option<&[Span]>, config: &Config, syntax: Syntax) -> String {
+    let program = linux::program(cmds, spans, config);
+    let mut writer = Writer {
+        text: String::new(),
+        config,
+        syntax,
+        labels: &program.labels,
+    };
+
+    if syntax == Syntax::Intel {
+        writer.text.push_str("    .intel_syntax noprefix\n");
+    }
+    writer
+        .text
+        .push_str("    .text\n    .globl _start\n_start:\n");
+    for inst in &program.insts {
+        writer.inst(inst);
+    }
+
+    let tape_bytes = config.tape_len * config.cell_width.bytes();
+    writeln!(
+        writer.text,
+        "
+    .section .rodata
+off_tape:
+    .ascii {:?}
+past_eof:
+    .ascii {:?}
+
+    .bss
+    .balign 16
+outbuf:
+    .zero {OUT_BUF}
+tape:
+    .zero {tape_bytes}",
+        Symbol::OffTape.message().unwrap(),
+        Symbol::PastEof.message().unwrap(),
+    )
+    .unwrap();
+
+    writer.text
+}
+
+#[derive(Clone, Copy)]
+enum Operand<'a> {
+    /// A register, by name.
+    Reg(&'a str),
+    Imm(i64),
+    /// `size` bytes at `[base + index * scale]`.
+    Mem {
+        base: &'a str,
+        index: Option<&'a str>,
+        scale: usize,
+        size: usize,
+    },
+    /// The address of a symbol, relative to `rip`.
+    Rip(&'a str),
+    Label(&'a str),
+}
+
+use Operand::*;
+
+struct Writer<'a> {
+    text: String,
+    config: &'a Config,
+    syntax: Syntax,
+    /// The name of each label of the program
+    labels: &'a [String],
+}
+
+impl Writer<'_> {
+    fn inst(&mut self, inst: &Inst) {
+        let labels = self.labels;
+        let label = |label: linux::Label| Label(&labels[label.0]);
+        let reg = |reg: Reg| Reg(reg.name(8));
+
+        match *inst {
+            Inst::Bind(target) => {
+                let name = &labels[target.0];
+                // Routines stand apart from the code before them
+                if !name.starts_with('.') {
+                    self.text.push('\n');
+                }
+                writeln!(self.text, "{name}:").unwrap();
+            }
+            Inst::Comment(ref text) => writeln!(self.text, "    # {text}").unwrap(),
+            Inst::Jmp(target) => self.op("jmp", &[label(target)]),
+            Inst::Jcc(cond, target) => {
+                let mnemonic = match cond {
+                    Cond::Below => "jb",
+                    Cond::Equal => "je",
+                    Cond::NotEqual => "jne",
+                    Cond::Sign => "js",
+                    Cond::NotSign => "jns",
+                    Cond::LessOrEqual => "jle",
+                };
+                self.op(mnemonic, &[label(target)]);
+            }
+            Inst::Call(target) => self.op("call", &[label(target)]),
+            Inst::Ret => self.op("ret", &[]),
+            Inst::Syscall => self.op("syscall", &[]),
+            Inst::Cqo => self.op("cqo", &[]),
+            Inst::MovImm(dst, value) => match i32::try_from(value) {
+                Ok(_) => self.op("mov", &[reg(dst), Imm(value)]),
+                Err(_) => self.op("movabs", &[reg(dst), Imm(value)]),
+            },
+            Inst::Alu(op, dst, src) => self.op(mnemonic(op), &[reg(dst), reg(src)]),
+            Inst::AluImm(op, dst, value) => {
+                self.op(mnemonic(op), &[reg(dst), Imm(value.into())]);
+            }
+            Inst::Idiv(src) => self.op("idiv", &[reg(src)]),
+            Inst::Inc(dst) => self.op("inc", &[reg(dst)]),
+            Inst::Push(src) => self.op("push", &[reg(src)]),
+            Inst::Pop(dst) => self.op("pop", &[reg(dst)]),
+            Inst::Imul(dst, src) => self.op("imul", &[reg(dst), reg(src)]),
+            Inst::ImulImm(dst, src, value) => {
+                self.op("imul", &[reg(dst), reg(src), Imm(value.into())]);
+            }
+            Inst::Lea(dst, symbol) => {
+                let symbol = match symbol {
+                    Symbol::OutBuf => "outbuf",
+                    Symbol::Tape => "tape",
+                    Symbol::OffTape => "off_tape",
+                    Symbol::PastEof => "past_eof",
+                };
+                self.op("lea", &[reg(dst), Rip(symbol)]);
+            }
+            Inst::CellAlu(op, index, src) => {
+                let src = Reg(src.name(self.config.cell_width.bytes()));
+                self.op(mnemonic(op), &[self.cell(index), src]);
+            }
+            Inst::CellCmpZero(index) => self.op("cmp", &[self.cell(index), Imm(0)]),
+            Inst::LoadCell(index) => {
+                let cell = self.cell(index);
+                match self.config.cell_width {
+                    CellWidth::U8 | CellWidth::U16 => self.op("movzx", &[Reg("esi"), cell]),
+                    CellWidth::U32 => self.op("mov", &[Reg("esi"), cell]),
+                    CellWidth::U64 => self.op("mov", &[Reg("rsi"), cell]),
+                }
+            }
+            Inst::StoreByte { base, index, src } => {
+                let slot = Mem {
+                    base: base.name(8),
+                    index: Some(index.name(8)),
+                    scale: 1,
+                    size: 1,
+                };
+                self.op("mov", &[slot, Reg(src.name(1))]);
+            }
+            Inst::LoadByte { dst, base } => {
+                let slot = Mem {
+                    base: base.name(8),
+                    index: None,
+                    scale: 1,
+                    size: 1,
+                };
+                self.op("movzx", &[Reg(dst.name(4)), slot]);
+            }
+            Inst::CmpByte(src, value) => self.op("cmp", &[Reg(src.name(1)), Imm(value.into())]),
+        }
+    }
+
+    /// Writes an instruction, with its operands in Intel order.
+    fn op(&mut self, mnemonic: &str, operands: &[Operand]) {
+        let size = operands.iter().find_map(|operand| match operand {
+            Mem { size, .. } => Some(*size),
+            _ => None,
+        });
+
+        let line = match self.syntax {
+            Syntax::Intel => {
+                let operands: Vec<_> = operands.iter().map(intel).collect();
+                format!("{mnemonic} {}", operands.join(", "))
+            }
+            Syntax::Att => {
+                let suffix = |size| match size {
+                    1 => 'b',
+                    2 => 'w',
+                    4 => 'l',
+                    _ => 'q',
+                };
+                let mnemonic = match (mnemonic, size) {
+                    // movzx takes the size of its source, and always widens to 32 bits here
+                    ("movzx", Some(size)) => format!("movz{}l", suffix(size)),
+                    ("cqo", _) => "cqto".to_string(),
+                    (mnemonic, Some(size)) => format!("{mnemonic}{}", suffix(size)),
+                    (mnemonic, None) => mnemonic.to_string(),
+                };
+                let operands: Vec<_> = operands.iter().rev().map(att).collect();
+                format!("{mnemonic} {}", operands.join(", "))
+            }
+        };
+        writeln!(self.text, "    {}", line.trim_end()).unwrap();
+    }
+
+    /// The cell `index` points at.
+    fn cell(&self, index: Reg) -> Operand<'static> {
+        let width = self.config.cell_width.bytes();
+        Mem {
+            base: "rbx",
+            index: Some(index.name(8)),
+            scale: width,
+            size: width,
+        }
+    }
+}
+
+fn mnemonic(op: Alu) -> &'static str {
+    match op {
+        Alu::Mov => "mov",
+        Alu::Add => "add",
+        Alu::Sub => "sub",
+        Alu::Cmp => "cmp",
+        Alu::Test => "test",
+        Alu::Xor => "xor",
+    }
+}
+
+fn intel(operand: &Operand) -> String {
+    match *operand {
+        Reg(reg) => reg.to_string(),
+        Imm(value) => value.to_string(),
+        Mem {
+            base,
+            index,
+            scale,
+            size,
+        } => {
+            let size = match size {
+                1 => "BYTE PTR ",
+                2 => "WORD PTR ",
+                4 => "DWORD PTR ",
+                _ => "QWORD PTR ",
+            };
+            match (index, scale) {
+                (None, _) => format!("{size}[{base}]"),
+                (Some(index), 1) => format!("{size}[{base}+{index}]"),
+                (Some(index), scale) => format!("{size}[{base}+{index}*{scale}]"),
+            }
+        }
+        Rip(symbol) => format!("[rip+{symbol}]"),
+        Label(label) => label.to_string(),
+    }
+}
+
+fn att(operand: &Operand) -> String {
+    match *operand {
+        Reg(reg) => format!("%{reg}"),
+        Imm(value) => format!("${value}"),
+        Mem {
+            base, index, scale, ..
+        } => match index {
+            Some(index) => format!("(%{base},%{index},{scale})"),
+            None => format!("(%{base})"),
+        },
+        Rip(symbol) => format!("{symbol}(%rip)"),
+        Label(label) => label.to_string(),
+    }
+}
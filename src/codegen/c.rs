@@ -0,0 +1,205 @@
+//! Translates commands into a standalone C99 program.
+
+use std::fmt::Write;
+
+use crate::{
+    config::{CellWidth, Config, EofPolicy, FlushPolicy, TapePolicy},
+    parser::{Cmd, Op},
+};
+
+/// Returns a C program that behaves like `cmds` run with `config`.
+///
+/// The cell type, tape length and tape, EOF and flush policies all carry over. Output is
+/// always written as raw bytes whatever [`Config::output`] says, and
+/// [`Config::step_limit`] is ignored.
+pub fn emit(cmds: &[Cmd], config: &Config) -> String {
+    let mut c = String::new();
+
+    prelude(&mut c, config);
+
+    let mut depth = 1;
+    for cmd in cmds {
+        if cmd.operator == Op::JmpNonZero {
+            depth -= 1;
+        }
+        c.push_str(&"    ".repeat(depth));
+
+        let cell = cell(cmd.offset);
+        match cmd.operator {
+            Op::Add if cmd.operand < 0 => writeln!(c, "{cell} -= {};", cmd.operand.unsigned_abs()),
+            Op::Add => writeln!(c, "{cell} += {};", cmd.operand),
+            Op::Move => writeln!(c, "p = seek(p, {});", cmd.operand),
+            Op::Out => writeln!(c, "out({cell}, {});", cmd.operand),
+            Op::In => writeln!(c, "in(&{cell}, {});", cmd.operand),
+            Op::JmpZero => writeln!(c, "while (tape[p]) {{"),
+            Op::JmpNonZero => writeln!(c, "}}"),
+            Op::SetZero => writeln!(c, "{cell} = 0;"),
+            Op::ScanLeft | Op::ScanRight => {
+                let delta = match cmd.operator {
+                    Op::ScanLeft => -cmd.operand,
+                    _ => cmd.operand,
+                };
+                writeln!(c, "while (tape[p]) p = seek(p, {delta});")
+            }
+            // `at` may move the tape, so it has to run before `tape[p]` is read
+            Op::MulAdd => writeln!(
+                c,
+                "if (tape[p]) {{ cell *c = at({}); \
+                 *c += (uint64_t)tape[p] * (uint64_t){}LL; }}",
+                cmd.offset, cmd.operand
+            ),
+        }
+        .unwrap();
+
+        if cmd.operator == Op::JmpZero {
+            depth += 1;
+        }
+    }
+
+    c.push_str("    return 0;\n}\n");
+    c
+}
+
+/// The C expression for the cell `offset` away from the memory pointer.
+fn cell(offset: isize) -> String {
+    match offset {
+        0 => "tape[p]".to_string(),
+        _ => format!("*at({offset})"),
+    }
+}
+
+/// Writes the includes, the tape and the helpers the program body calls.
+fn prelude(c: &mut String, config: &Config) {
+    let cell_type = match config.cell_width {
+        CellWidth::U8 => "uint8_t",
+        CellWidth::U16 => "uint16_t",
+        CellWidth::U32 => "uint32_t",
+        CellWidth::U64 => "uint64_t",
+    };
+
+    writeln!(
+        c,
+        "#include <stdint.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef {cell_type} cell;
+
+static cell *tape;
+static long len = {len};
+static long p;
+",
+        len = config.tape_len,
+    )
+    .unwrap();
+
+    let off_tape = match config.tape_policy {
+        TapePolicy::Wrap => "    to %= len;\n    return to < 0 ? to + len : to;",
+        TapePolicy::Error => {
+            "    fputs(\"memory pointer moved off the tape\\n\", stderr);
+    exit(1);"
+        }
+        TapePolicy::Grow => {
+            "    long extra = to >= len ? (to + 1 > 2 * len ? to + 1 : 2 * len) - len
+                           : (-to > len ? -to : len);
+    cell *grown = realloc(tape, (len + extra) * sizeof(cell));
+    if (!grown) {
+        fputs(\"out of memory\\n\", stderr);
+        exit(1);
+    }
+    tape = grown;
+    if (to >= len) {
+        memset(tape + len, 0, extra * sizeof(cell));
+    } else {
+        memmove(tape + extra, tape, len * sizeof(cell));
+        memset(tape, 0, extra * sizeof(cell));
+        to += extra;
+    }
+    len += extra;
+    return to;"
+        }
+    };
+
+    writeln!(
+        c,
+        "/* Moves `delta` cells away from `from`, returning the new position. */
+static inline long seek(long from, long delta) {{
+    long to = from + delta;
+    if (to >= 0 && to < len) {{
+        return to;
+    }}
+{off_tape}
+}}
+
+/* The cell `offset` cells away from the memory pointer. */
+static inline cell *at(long offset) {{
+    long to = p + offset;
+    if (to < 0 || to >= len) {{
+        to = seek(p, offset);
+        p = seek(to, -offset);
+    }}
+    return &tape[to];
+}}
+"
+    )
+    .unwrap();
+
+    let flush = match config.flush {
+        FlushPolicy::Always => "\n    fflush(stdout);",
+        _ => "",
+    };
+    writeln!(
+        c,
+        "static inline void out(cell value, long count) {{
+    while (count--) {{
+        putchar((unsigned char)value);
+    }}{flush}
+}}
+"
+    )
+    .unwrap();
+
+    let before_read = match config.flush {
+        FlushPolicy::BeforeRead => "    fflush(stdout);\n",
+        _ => "",
+    };
+    let eof = match config.eof {
+        EofPolicy::Zero => "*c = 0;",
+        EofPolicy::Max => "*c = (cell)-1;",
+        EofPolicy::Unchanged => "/* leave the cell as it was */",
+        EofPolicy::Error => {
+            "fputs(\"tried to read past the end of input\\n\", stderr);
+            exit(1);"
+        }
+    };
+    writeln!(
+        c,
+        "static inline void in(cell *c, long count) {{
+{before_read}    while (count--) {{
+        int byte = getchar();
+        if (byte == EOF) {{
+            {eof}
+            return;
+        }}
+        *c = (cell)byte;
+    }}
+}}
+"
+    )
+    .unwrap();
+
+    c.push_str(
+        "int main(void) {
+    tape = calloc(len, sizeof(cell));
+    if (!tape) {
+        fputs(\"out of memory\\n\", stderr);
+        return 1;
+    }
+",
+    );
+    if config.flush == FlushPolicy::Newline {
+        c.push_str("    setvbuf(stdout, NULL, _IOLBF, BUFSIZ);\n");
+    }
+    c.push('\n');
+}
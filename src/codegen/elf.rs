@@ -0,0 +1,246 @@
+//! Translates commands into a static x86-64 Linux executable, without an assembler or
+//! linker.
+//!
+//! The file is an ELF header, a read-only executable segment holding the machine code
+//! and a zero-filled segment holding the output buffer and the tape. The code is the
+//! program [`linux`](super::linux) builds, which [`asm`](super::asm) prints instead.
+
+use crate::{config::Config, parser::Cmd, x86::Assembler};
+
+use super::linux::{self, Alu, Cond, Inst, Label, Symbol, OUT_BUF};
+
+/// Where the executable segment is loaded.
+const BASE: u64 = 0x40_0000;
+const PAGE: u64 = 0x1000;
+/// Size of the ELF header followed by the two program headers.
+const HEADERS: usize = 64 + 2 * 56;
+
+/// Returns the bytes of an executable that behaves like `cmds` run with `config`.
+///
+/// The cell width, tape length and EOF and flush policies carry over, and the program
+/// exits with status 1 after an error. The tape can't grow, so [`TapePolicy::Grow`]
+/// stops the program like [`TapePolicy::Error`] does. Output is always written as raw
+/// bytes whatever [`Config::output`] says, and [`Config::step_limit`] is ignored.
+///
+/// [`TapePolicy::Grow`]: crate::TapePolicy::Grow
+/// [`TapePolicy::Error`]: crate::TapePolicy::Error
+pub fn emit(cmds: &[Cmd], config: &Config) -> Vec<u8> {
+    let program = linux::program(cmds, None, config);
+
+    let mut builder = Builder {
+        asm: Assembler::new(config.cell_width),
+        config,
+        labels: vec![None; program.labels.len()],
+        jumps: Vec::new(),
+        messages: Vec::new(),
+        bss: Vec::new(),
+    };
+    for inst in &program.insts {
+        builder.inst(inst);
+    }
+    builder.link()
+}
+
+struct Builder<'a> {
+    asm: Assembler,
+    config: &'a Config,
+    /// Where each label is bound
+    labels: Vec<Option<usize>>,
+    /// Positions of rel32 jump and call operands and the label they target
+    jumps: Vec<(usize, Label)>,
+    /// Positions of rel32 operands and the message they point to
+    messages: Vec<(usize, &'static str)>,
+    /// Positions of imm64 operands and the offset into the zero-filled segment they hold
+    bss: Vec<(usize, u64)>,
+}
+
+impl Builder<'_> {
+    fn inst(&mut self, inst: &Inst) {
+        match *inst {
+            Inst::Bind(label) => self.labels[label.0] = Some(self.asm.code.len()),
+            Inst::Comment(_) => {}
+            Inst::Jmp(label) => self.jump(&[0xE9], label),
+            Inst::Jcc(cond, label) => {
+                let at = self.asm.jcc(condition_code(cond));
+                self.jumps.push((at, label));
+            }
+            Inst::Call(label) => self.jump(&[0xE8], label),
+            Inst::Ret => self.asm.emit(&[0xC3]),
+            Inst::Syscall => self.asm.emit(&[0x0F, 0x05]),
+            Inst::Cqo => self.asm.emit(&[0x48, 0x99]),
+            Inst::MovImm(reg, value) => self.asm.mov_imm(reg as u8, value),
+            Inst::Alu(op, dst, src) => {
+                let opcode = match op {
+                    Alu::Mov => 0x89,
+                    Alu::Add => 0x01,
+                    Alu::Sub => 0x29,
+                    Alu::Cmp => 0x39,
+                    Alu::Test => 0x85,
+                    Alu::Xor => 0x31,
+                };
+                self.asm.reg_op(&[opcode], src as u8, dst as u8);
+            }
+            Inst::AluImm(op, dst, value) => {
+                let extension = match op {
+                    Alu::Add => 0,
+                    Alu::Sub => 5,
+                    Alu::Cmp => 7,
+                    _ => unreachable!("{op:?} has no immediate form here"),
+                };
+                self.asm.reg_op(&[0x81], extension, dst as u8);
+                self.asm.emit_u32(value as u32);
+            }
+            Inst::Idiv(reg) => self.asm.reg_op(&[0xF7], 7, reg as u8),
+            Inst::Inc(reg) => self.asm.reg_op(&[0xFF], 0, reg as u8),
+            Inst::Push(reg) => self.push_pop(0x50, reg as u8),
+            Inst::Pop(reg) => self.push_pop(0x58, reg as u8),
+            Inst::Imul(dst, src) => self.asm.reg_op(&[0x0F, 0xAF], dst as u8, src as u8),
+            Inst::ImulImm(dst, src, value) => {
+                self.asm.reg_op(&[0x69], dst as u8, src as u8);
+                self.asm.emit_u32(value as u32);
+            }
+            Inst::Lea(reg, symbol) => self.lea(reg as u8, symbol),
+            Inst::CellAlu(op, index, src) => {
+                let (byte_opcode, opcode) = match op {
+                    Alu::Mov => (0x88, 0x89),
+                    Alu::Add => (0x00, 0x01),
+                    Alu::Cmp => (0x38, 0x39),
+                    _ => unreachable!("{op:?} has no cell form here"),
+                };
+                self.asm
+                    .cell_op_at(index as u8, byte_opcode, opcode, src as u8);
+            }
+            Inst::CellCmpZero(index) => {
+                // cmp [cell], 0
+                self.asm.cell_op_at(index as u8, 0x80, 0x83, 7);
+                self.asm.emit(&[0x00]);
+            }
+            Inst::LoadCell(index) => self.asm.load_cell(index as u8),
+            Inst::StoreByte { base, index, src } => {
+                self.asm
+                    .byte_op(&[0x88], src as u8, base as u8, Some(index as u8));
+            }
+            Inst::LoadByte { dst, base } => {
+                self.asm.byte_op(&[0x0F, 0xB6], dst as u8, base as u8, None);
+            }
+            Inst::CmpByte(reg, value) => {
+                let reg = reg as u8;
+                self.asm
+                    .emit(&[0x40 | reg >> 3, 0x80, 0xF8 | (reg & 7), value]);
+            }
+        }
+    }
+
+    /// Emits a jump or call with a rel32 operand pointing at `label`.
+    fn jump(&mut self, opcode: &[u8], label: Label) {
+        self.asm.emit(opcode);
+        self.jumps.push((self.asm.code.len(), label));
+        self.asm.emit_u32(0);
+    }
+
+    fn push_pop(&mut self, opcode: u8, reg: u8) {
+        if reg >= 8 {
+            self.asm.emit(&[0x41]);
+        }
+        self.asm.emit(&[opcode | (reg & 7)]);
+    }
+
+    fn lea(&mut self, reg: u8, symbol: Symbol) {
+        let offset = match symbol {
+            Symbol::OutBuf => 0,
+            Symbol::Tape => OUT_BUF as u64,
+            Symbol::OffTape | Symbol::PastEof => {
+                // lea reg, [rip + rel32]
+                self.asm
+                    .emit(&[0x48 | (reg >> 3) << 2, 0x8D, 0x05 | (reg & 7) << 3]);
+                let message = symbol.message().unwrap();
+                self.messages.push((self.asm.code.len(), message));
+                self.asm.emit_u32(0);
+                return;
+            }
+        };
+
+        // The segment is at a fixed address, so load it as mov reg, imm64
+        self.asm.emit(&[0x48 | reg >> 3, 0xB8 | (reg & 7)]);
+        self.bss.push((self.asm.code.len(), offset));
+        self.asm.emit_u64(0);
+    }
+
+    /// Resolves the jumps, emits the messages, then wraps the code in an ELF file.
+    fn link(mut self) -> Vec<u8> {
+        for (at, label) in std::mem::take(&mut self.jumps) {
+            let target = self.labels[label.0].expect("labels are bound");
+            self.asm.patch(at, target);
+        }
+
+        let mut placed: Vec<(&str, usize)> = Vec::new();
+        for (at, message) in std::mem::take(&mut self.messages) {
+            let target = match placed.iter().find(|(m, _)| *m == message) {
+                Some(&(_, target)) => target,
+                None => {
+                    let target = self.asm.code.len();
+                    self.asm.emit(message.as_bytes());
+                    placed.push((message, target));
+                    target
+                }
+            };
+            self.asm.patch(at, target);
+        }
+
+        let file_len = (HEADERS + self.asm.code.len()) as u64;
+        let bss = (BASE + file_len).next_multiple_of(PAGE);
+        let bss_len = OUT_BUF as u64 + self.config.tape_len as u64 * self.asm.width.bytes() as u64;
+        for &(at, offset) in &self.bss {
+            self.asm.code[at..at + 8].copy_from_slice(&(bss + offset).to_le_bytes());
+        }
+
+        let mut elf = Vec::with_capacity(file_len as usize);
+        // e_ident: magic, 64-bit, little-endian, version 1, System V ABI
+        elf.extend_from_slice(b"\x7FELF\x02\x01\x01\x00");
+        elf.extend_from_slice(&[0; 8]);
+        // e_type: executable, e_machine: x86-64, e_version
+        elf.extend_from_slice(&2u16.to_le_bytes());
+        elf.extend_from_slice(&0x3Eu16.to_le_bytes());
+        elf.extend_from_slice(&1u32.to_le_bytes());
+        // e_entry, e_phoff, e_shoff
+        elf.extend_from_slice(&(BASE + HEADERS as u64).to_le_bytes());
+        elf.extend_from_slice(&64u64.to_le_bytes());
+        elf.extend_from_slice(&0u64.to_le_bytes());
+        // e_flags, e_ehsize, e_phentsize, e_phnum, e_shentsize, e_shnum, e_shstrndx
+        elf.extend_from_slice(&0u32.to_le_bytes());
+        for half in [64u16, 56, 2, 64, 0, 0] {
+            elf.extend_from_slice(&half.to_le_bytes());
+        }
+
+        // The whole file, readable and executable
+        program_header(&mut elf, 0b101, BASE, file_len, file_len);
+        // The output buffer and the tape, readable and writable
+        program_header(&mut elf, 0b110, bss, 0, bss_len);
+
+        elf.extend_from_slice(&self.asm.code);
+        elf
+    }
+}
+
+/// The second opcode byte of a conditional jump with a rel32 operand.
+fn condition_code(cond: Cond) -> u8 {
+    match cond {
+        Cond::Below => 0x82,
+        Cond::Equal => 0x84,
+        Cond::NotEqual => 0x85,
+        Cond::Sign => 0x88,
+        Cond::NotSign => 0x89,
+        Cond::LessOrEqual => 0x8E,
+    }
+}
+
+/// Appends a loadable segment at file offset 0, mapped at `vaddr` with `flags`.
+fn program_header(elf: &mut Vec<u8>, flags: u32, vaddr: u64, file_len: u64, mem_len: u64) {
+    // p_type: PT_LOAD, p_flags
+    elf.extend_from_slice(&1u32.to_le_bytes());
+    elf.extend_from_slice(&flags.to_le_bytes());
+    // p_offset, p_vaddr, p_paddr, p_filesz, p_memsz, p_align
+    for word in [0, vaddr, vaddr, file_len, mem_len, PAGE] {
+        elf.extend_from_slice(&word.to_le_bytes());
+    }
+}
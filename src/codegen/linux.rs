@@ -0,0 +1,494 @@
+//! The x86-64 Linux program behind both [`elf`](super::elf), which encodes it, and
+//! [`asm`](super::asm), which prints it as assembler source.
+//!
+//! The tape and output buffer are static, and the program talks to the kernel through raw
+//! `read`, `write` and `exit` system calls, so it needs no C library.
+//!
+//! Register usage inside the generated code:
+//! - `rbx` holds the base address of the tape
+//! - `r12` holds the memory pointer as an index into the tape
+//! - `r13` holds the number of bytes waiting in the output buffer
+//! - `r14` holds the index of a cell at an offset from the memory pointer
+//! - `r15` holds the length of the tape
+
+use crate::{
+    config::{Config, EofPolicy, FlushPolicy, TapePolicy},
+    parser::{Cmd, Op, Span},
+    x86::Reg::{self, *},
+};
+
+/// Size of the output buffer.
+pub(crate) const OUT_BUF: usize = 4096;
+
+/// A position in the code, numbered in the order the labels were made.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Label(pub(crate) usize);
+
+/// Static data the code refers to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Symbol {
+    OutBuf,
+    Tape,
+    OffTape,
+    PastEof,
+}
+
+impl Symbol {
+    /// The text of a message printed before exiting with an error.
+    pub(crate) fn message(self) -> Option<&'static str> {
+        match self {
+            Symbol::OffTape => Some("memory pointer moved off the tape\n"),
+            Symbol::PastEof => Some("tried to read past the end of input\n"),
+            Symbol::OutBuf | Symbol::Tape => None,
+        }
+    }
+}
+
+/// Conditions of a conditional jump.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Cond {
+    Below,
+    Equal,
+    NotEqual,
+    Sign,
+    NotSign,
+    LessOrEqual,
+}
+
+/// Operations on two operands, the first of which is also the destination.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Alu {
+    Mov,
+    Add,
+    Sub,
+    Cmp,
+    Test,
+    Xor,
+}
+
+/// An instruction, or a label or comment between them. Registers are 64 bits wide unless
+/// noted, and cells are addressed as `[rbx + index * width]`.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) enum Inst {
+    Bind(Label),
+    Comment(String),
+    Jmp(Label),
+    Jcc(Cond, Label),
+    Call(Label),
+    Ret,
+    Syscall,
+    /// Sign-extends `rax` into `rdx`.
+    Cqo,
+    MovImm(Reg, i64),
+    Alu(Alu, Reg, Reg),
+    AluImm(Alu, Reg, i32),
+    Idiv(Reg),
+    Inc(Reg),
+    Push(Reg),
+    Pop(Reg),
+    Imul(Reg, Reg),
+    ImulImm(Reg, Reg, i32),
+    /// Loads the address of a symbol.
+    Lea(Reg, Symbol),
+    /// An [`Alu::Mov`], [`Alu::Add`] or [`Alu::Cmp`] of the cell `index` points at and the
+    /// low part of a register as wide as a cell.
+    CellAlu(Alu, Reg, Reg),
+    /// Compares the cell `index` points at with zero.
+    CellCmpZero(Reg),
+    /// Zero-extends the cell `index` points at into `rsi`.
+    LoadCell(Reg),
+    /// Stores the low byte of `src` at `[base + index]`.
+    StoreByte {
+        base: Reg,
+        index: Reg,
+        src: Reg,
+    },
+    /// Zero-extends the byte at `[base]` into `dst`.
+    LoadByte {
+        dst: Reg,
+        base: Reg,
+    },
+    /// Compares the low byte of a register with a value.
+    CmpByte(Reg, u8),
+}
+
+/// A program's instructions and the name of each of its labels.
+pub(crate) struct Program {
+    pub(crate) insts: Vec<Inst>,
+    pub(crate) labels: Vec<String>,
+}
+
+/// Returns a program that behaves like `cmds` run with `config`.
+///
+/// Each command's instructions are preceded by a comment with its index and, if `spans`
+/// is given, where it starts in the source. `spans` has to line up with `cmds`.
+pub(crate) fn program(cmds: &[Cmd], spans: Option<&[Span]>, config: &Config) -> Program {
+    let mut builder = Builder {
+        insts: Vec::new(),
+        labels: ["flush", "putc", "getc", "die"].map(String::from).to_vec(),
+        config,
+        routines: Routines {
+            flush: Label(0),
+            putc: Label(1),
+            getc: Label(2),
+            die: Label(3),
+        },
+    };
+    builder.program(cmds, spans);
+    builder.routines();
+
+    Program {
+        insts: builder.insts,
+        labels: builder.labels,
+    }
+}
+
+/// Labels of the helper routines the program body calls.
+struct Routines {
+    /// Writes out the output buffer.
+    flush: Label,
+    /// Appends `al` to the output buffer.
+    putc: Label,
+    /// Reads a byte into `rax`, or -1 at the end of input.
+    getc: Label,
+    /// Flushes, prints the message `rdx` bytes long at `rsi` to stderr and exits with 1.
+    die: Label,
+}
+
+struct Builder<'a> {
+    insts: Vec<Inst>,
+    labels: Vec<String>,
+    config: &'a Config,
+    routines: Routines,
+}
+
+impl Builder<'_> {
+    fn label(&mut self, name: String) -> Label {
+        self.labels.push(name);
+        Label(self.labels.len() - 1)
+    }
+
+    fn emit(&mut self, inst: Inst) {
+        self.insts.push(inst);
+    }
+
+    fn program(&mut self, cmds: &[Cmd], spans: Option<&[Span]>) {
+        self.emit(Inst::Lea(Rbx, Symbol::Tape));
+        self.emit(Inst::MovImm(R15, self.config.tape_len as i64));
+        self.emit(Inst::Alu(Alu::Xor, R12, R12));
+        self.emit(Inst::Alu(Alu::Xor, R13, R13));
+
+        // The label of every command a jump lands on, plus one past the end
+        let mut targets = vec![None; cmds.len() + 1];
+        for cmd in cmds {
+            if matches!(cmd.operator, Op::JmpZero | Op::JmpNonZero) {
+                let target = cmd.operand as usize;
+                if targets[target].is_none() {
+                    targets[target] = Some(self.label(format!(".L{target}")));
+                }
+            }
+        }
+
+        for (instr_ptr, cmd) in cmds.iter().enumerate() {
+            if let Some(label) = targets[instr_ptr] {
+                self.emit(Inst::Bind(label));
+            }
+            self.emit(Inst::Comment(
+                match spans.and_then(|spans| spans.get(instr_ptr)) {
+                    Some(span) => format!("{instr_ptr}: {cmd} at {span}"),
+                    None => format!("{instr_ptr}: {cmd}"),
+                },
+            ));
+            self.cmd(instr_ptr, cmd, &targets);
+        }
+
+        if let Some(label) = targets[cmds.len()] {
+            self.emit(Inst::Bind(label));
+        }
+        self.emit(Inst::Call(self.routines.flush));
+        self.exit(0);
+    }
+
+    fn cmd(&mut self, instr_ptr: usize, cmd: &Cmd, targets: &[Option<Label>]) {
+        match cmd.operator {
+            Op::Add => {
+                let index = self.address(cmd.offset, instr_ptr);
+                let amount = cmd.operand as u64 & self.config.cell_width.max();
+                self.emit(Inst::MovImm(Rax, amount as i64));
+                self.emit(Inst::CellAlu(Alu::Add, index, Rax));
+            }
+            Op::Move => self.move_ptr(cmd.operand, instr_ptr),
+            Op::Out => {
+                let index = self.address(cmd.offset, instr_ptr);
+                self.emit(Inst::LoadCell(index));
+                self.emit(Inst::Alu(Alu::Mov, Rax, Rsi));
+                for _ in 0..cmd.operand {
+                    self.emit(Inst::Call(self.routines.putc));
+                }
+            }
+            Op::In => {
+                let index = self.address(cmd.offset, instr_ptr);
+                self.input(instr_ptr, index, cmd.operand as usize);
+            }
+            Op::JmpZero | Op::JmpNonZero => {
+                let cond = match cmd.operator {
+                    Op::JmpZero => Cond::Equal,
+                    _ => Cond::NotEqual,
+                };
+                let target = targets[cmd.operand as usize].expect("jump targets have labels");
+                self.emit(Inst::CellCmpZero(R12));
+                self.emit(Inst::Jcc(cond, target));
+            }
+            Op::SetZero => {
+                let index = self.address(cmd.offset, instr_ptr);
+                self.emit(Inst::Alu(Alu::Xor, Rax, Rax));
+                self.emit(Inst::CellAlu(Alu::Mov, index, Rax));
+            }
+            Op::ScanLeft | Op::ScanRight => {
+                let delta = match cmd.operator {
+                    Op::ScanLeft => -cmd.operand,
+                    _ => cmd.operand,
+                };
+                let top = self.local(instr_ptr, "scan");
+                let done = self.local(instr_ptr, "done");
+
+                self.emit(Inst::Bind(top));
+                self.emit(Inst::CellCmpZero(R12));
+                self.emit(Inst::Jcc(Cond::Equal, done));
+                self.move_ptr(delta, instr_ptr);
+                self.emit(Inst::Jmp(top));
+                self.emit(Inst::Bind(done));
+            }
+            Op::MulAdd => {
+                let done = self.local(instr_ptr, "done");
+                self.emit(Inst::CellCmpZero(R12));
+                self.emit(Inst::Jcc(Cond::Equal, done));
+
+                let index = self.address(cmd.offset, instr_ptr);
+                self.emit(Inst::LoadCell(R12));
+                match i32::try_from(cmd.operand) {
+                    Ok(factor) => self.emit(Inst::ImulImm(Rsi, Rsi, factor)),
+                    Err(_) => {
+                        self.emit(Inst::MovImm(Rax, cmd.operand as i64));
+                        self.emit(Inst::Imul(Rsi, Rax));
+                    }
+                }
+                self.emit(Inst::CellAlu(Alu::Add, index, Rsi));
+                self.emit(Inst::Bind(done));
+            }
+        }
+    }
+
+    /// Makes a label for use within the command at `instr_ptr`.
+    fn local(&mut self, instr_ptr: usize, name: &str) -> Label {
+        self.label(format!(".L{instr_ptr}_{name}"))
+    }
+
+    /// Returns the register holding the index of the cell `offset` away from the current
+    /// one, loading it into `r14` unless `offset` is zero.
+    fn address(&mut self, offset: isize, instr_ptr: usize) -> Reg {
+        if offset == 0 {
+            return R12;
+        }
+
+        // The end of the slow path that applies the tape policy
+        let done = self.local(instr_ptr, "at");
+        self.emit(Inst::MovImm(R14, offset as i64));
+        self.emit(Inst::Alu(Alu::Add, R14, R12));
+        self.emit(Inst::Alu(Alu::Cmp, R14, R15));
+        self.emit(Inst::Jcc(Cond::Below, done));
+        self.off_tape(R14, done);
+        self.emit(Inst::Bind(done));
+        R14
+    }
+
+    fn move_ptr(&mut self, delta: isize, instr_ptr: usize) {
+        if delta == 0 {
+            return;
+        }
+
+        let done = self.local(instr_ptr, "moved");
+        match i32::try_from(delta) {
+            Ok(delta) => self.emit(Inst::AluImm(Alu::Add, R12, delta)),
+            Err(_) => {
+                self.emit(Inst::MovImm(Rax, delta as i64));
+                self.emit(Inst::Alu(Alu::Add, R12, Rax));
+            }
+        }
+        // Off either end of the tape, since a negative index compares as huge
+        self.emit(Inst::Alu(Alu::Cmp, R12, R15));
+        self.emit(Inst::Jcc(Cond::Below, done));
+        self.off_tape(R12, done);
+        self.emit(Inst::Bind(done));
+    }
+
+    /// Applies the tape policy to the index in `reg`, which is off the tape.
+    fn off_tape(&mut self, reg: Reg, done: Label) {
+        match self.config.tape_policy {
+            TapePolicy::Wrap => {
+                self.emit(Inst::Alu(Alu::Mov, Rax, reg));
+                self.emit(Inst::Cqo);
+                self.emit(Inst::Idiv(R15));
+                self.emit(Inst::Alu(Alu::Mov, reg, Rdx));
+                self.emit(Inst::Alu(Alu::Test, reg, reg));
+                self.emit(Inst::Jcc(Cond::NotSign, done));
+                self.emit(Inst::Alu(Alu::Add, reg, R15));
+            }
+            // The tape can't grow
+            TapePolicy::Error | TapePolicy::Grow => self.fail(Symbol::OffTape),
+        }
+    }
+
+    fn input(&mut self, instr_ptr: usize, index: Reg, count: usize) {
+        let (eof, done) = (self.local(instr_ptr, "eof"), self.local(instr_ptr, "done"));
+        for _ in 0..count {
+            self.emit(Inst::Call(self.routines.getc));
+            self.emit(Inst::Alu(Alu::Test, Rax, Rax));
+            self.emit(Inst::Jcc(Cond::Sign, eof));
+            self.emit(Inst::CellAlu(Alu::Mov, index, Rax));
+        }
+        self.emit(Inst::Jmp(done));
+
+        self.emit(Inst::Bind(eof));
+        match self.config.eof {
+            EofPolicy::Zero => {
+                self.emit(Inst::Alu(Alu::Xor, Rax, Rax));
+                self.emit(Inst::CellAlu(Alu::Mov, index, Rax));
+            }
+            EofPolicy::Max => {
+                self.emit(Inst::MovImm(Rax, -1));
+                self.emit(Inst::CellAlu(Alu::Mov, index, Rax));
+            }
+            EofPolicy::Unchanged => {}
+            EofPolicy::Error => self.fail(Symbol::PastEof),
+        }
+        self.emit(Inst::Bind(done));
+    }
+
+    fn exit(&mut self, status: i64) {
+        self.emit(Inst::MovImm(Rdi, status));
+        self.emit(Inst::MovImm(Rax, 60));
+        self.emit(Inst::Syscall);
+    }
+
+    /// Stops the program with the message at `symbol`.
+    fn fail(&mut self, symbol: Symbol) {
+        let len = symbol.message().expect("symbol is a message").len();
+        self.emit(Inst::Lea(Rsi, symbol));
+        self.emit(Inst::MovImm(Rdx, len as i64));
+        self.emit(Inst::Call(self.routines.die));
+    }
+
+    fn routines(&mut self) {
+        self.flush();
+        self.putc();
+        self.getc();
+        self.die();
+    }
+
+    fn flush(&mut self) {
+        let top = self.label(".Lflush_loop".to_string());
+        let done = self.label(".Lflush_done".to_string());
+        let failed = self.label(".Lflush_failed".to_string());
+
+        self.emit(Inst::Bind(self.routines.flush));
+        self.emit(Inst::Lea(Rsi, Symbol::OutBuf));
+        self.emit(Inst::Alu(Alu::Mov, Rdx, R13));
+        self.emit(Inst::Bind(top));
+        self.emit(Inst::Alu(Alu::Test, Rdx, Rdx));
+        self.emit(Inst::Jcc(Cond::Equal, done));
+        // write(stdout, rsi, rdx)
+        self.emit(Inst::MovImm(Rax, 1));
+        self.emit(Inst::MovImm(Rdi, 1));
+        self.emit(Inst::Syscall);
+        self.emit(Inst::Alu(Alu::Test, Rax, Rax));
+        self.emit(Inst::Jcc(Cond::LessOrEqual, failed));
+        self.emit(Inst::Alu(Alu::Add, Rsi, Rax));
+        self.emit(Inst::Alu(Alu::Sub, Rdx, Rax));
+        self.emit(Inst::Jmp(top));
+        self.emit(Inst::Bind(done));
+        self.emit(Inst::Alu(Alu::Xor, R13, R13));
+        self.emit(Inst::Ret);
+        // Nothing can be reported if stdout is gone
+        self.emit(Inst::Bind(failed));
+        self.exit(1);
+    }
+
+    fn putc(&mut self) {
+        let room = self.label(".Lputc_room".to_string());
+
+        self.emit(Inst::Bind(self.routines.putc));
+        self.emit(Inst::AluImm(Alu::Cmp, R13, OUT_BUF as i32));
+        self.emit(Inst::Jcc(Cond::Below, room));
+        self.flush_keeping_rax();
+        self.emit(Inst::Bind(room));
+        self.emit(Inst::Lea(Rdi, Symbol::OutBuf));
+        self.emit(Inst::StoreByte {
+            base: Rdi,
+            index: R13,
+            src: Rax,
+        });
+        self.emit(Inst::Inc(R13));
+
+        match self.config.flush {
+            FlushPolicy::Always => self.flush_keeping_rax(),
+            FlushPolicy::Newline => {
+                let done = self.label(".Lputc_done".to_string());
+                self.emit(Inst::CmpByte(Rax, b'\n'));
+                self.emit(Inst::Jcc(Cond::NotEqual, done));
+                self.flush_keeping_rax();
+                self.emit(Inst::Bind(done));
+            }
+            FlushPolicy::BeforeRead | FlushPolicy::Exit => {}
+        }
+        self.emit(Inst::Ret);
+    }
+
+    fn flush_keeping_rax(&mut self) {
+        self.emit(Inst::Push(Rax));
+        self.emit(Inst::Call(self.routines.flush));
+        self.emit(Inst::Pop(Rax));
+    }
+
+    fn getc(&mut self) {
+        let eof = self.label(".Lgetc_eof".to_string());
+
+        self.emit(Inst::Bind(self.routines.getc));
+        if self.config.flush == FlushPolicy::BeforeRead {
+            self.emit(Inst::Call(self.routines.flush));
+        }
+        // Make room to read into, then read(stdin, rsp, 1)
+        self.emit(Inst::Push(Rax));
+        self.emit(Inst::Alu(Alu::Xor, Rax, Rax));
+        self.emit(Inst::Alu(Alu::Xor, Rdi, Rdi));
+        self.emit(Inst::Alu(Alu::Mov, Rsi, Rsp));
+        self.emit(Inst::MovImm(Rdx, 1));
+        self.emit(Inst::Syscall);
+        self.emit(Inst::Alu(Alu::Test, Rax, Rax));
+        self.emit(Inst::Jcc(Cond::LessOrEqual, eof));
+        self.emit(Inst::LoadByte {
+            dst: Rax,
+            base: Rsp,
+        });
+        self.emit(Inst::Pop(Rcx));
+        self.emit(Inst::Ret);
+        self.emit(Inst::Bind(eof));
+        self.emit(Inst::Pop(Rcx));
+        self.emit(Inst::MovImm(Rax, -1));
+        self.emit(Inst::Ret);
+    }
+
+    fn die(&mut self) {
+        self.emit(Inst::Bind(self.routines.die));
+        self.emit(Inst::Push(Rsi));
+        self.emit(Inst::Push(Rdx));
+        self.emit(Inst::Call(self.routines.flush));
+        self.emit(Inst::Pop(Rdx));
+        self.emit(Inst::Pop(Rsi));
+        // write(stderr, rsi, rdx)
+        self.emit(Inst::MovImm(Rdi, 2));
+        self.emit(Inst::MovImm(Rax, 1));
+        self.emit(Inst::Syscall);
+        self.exit(1);
+    }
+}
@@ -0,0 +1,477 @@
+//! Translates commands into textual LLVM IR.
+//!
+//! The module defines `main` and calls into the C library for I/O, so it can be optimized
+//! and linked with the LLVM toolchain without this crate linking LLVM itself:
+//!
+//! ```text
+//! clang -O2 prog.ll -o prog
+//! ```
+//!
+//! Pointers are written as opaque `ptr`s, which LLVM 15 and later read by default.
+
+use std::fmt::Write;
+
+use crate::{
+    config::{CellWidth, Config, EofPolicy, FlushPolicy, TapePolicy},
+    parser::{Cmd, Op},
+};
+
+const OFF_TAPE: &str = "memory pointer moved off the tape\n";
+const PAST_EOF: &str = "tried to read past the end of input\n";
+
+/// Returns an LLVM module that behaves like `cmds` run with `config`.
+///
+/// The tape is a global array and the memory pointer lives in a stack slot, which LLVM
+/// promotes to a register. Each command's instructions are preceded by a comment naming
+/// it, and the output only depends on `cmds` and `config`.
+///
+/// The cell type, tape length and tape, EOF and flush policies carry over, except that
+/// the tape can't grow, so [`TapePolicy::Grow`] stops the program like
+/// [`TapePolicy::Error`] does. Output is always written as raw bytes whatever
+/// [`Config::output`] says, and [`Config::step_limit`] is ignored.
+pub fn emit(cmds: &[Cmd], config: &Config) -> String {
+    let cell = match config.cell_width {
+        CellWidth::U8 => "i8",
+        CellWidth::U16 => "i16",
+        CellWidth::U32 => "i32",
+        CellWidth::U64 => "i64",
+    };
+    let mut lowering = Lowering {
+        body: String::new(),
+        config,
+        cell,
+        tape: format!("[{} x {cell}]", config.tape_len),
+        next: 0,
+        seeks: false,
+        outputs: false,
+        inputs: false,
+    };
+
+    for (instr_ptr, cmd) in cmds.iter().enumerate() {
+        writeln!(lowering.body, "  ; {instr_ptr}: {cmd}").unwrap();
+        lowering.cmd(instr_ptr, cmd);
+    }
+
+    lowering.module()
+}
+
+struct Lowering<'a> {
+    /// The instructions of `main` after its entry block.
+    body: String,
+    config: &'a Config,
+    /// The cell type.
+    cell: &'static str,
+    /// The type of the tape array.
+    tape: String,
+    /// Numbers the values in `main`.
+    next: usize,
+    /// Which helpers `main` calls.
+    seeks: bool,
+    outputs: bool,
+    inputs: bool,
+}
+
+impl Lowering<'_> {
+    /// Names a new value in `main`.
+    fn fresh(&mut self, prefix: &str) -> String {
+        self.next += 1;
+        format!("%{prefix}{}", self.next)
+    }
+
+    fn line(&mut self, line: &str) {
+        writeln!(self.body, "  {line}").unwrap();
+    }
+
+    fn label(&mut self, label: &str) {
+        writeln!(self.body, "{label}:").unwrap();
+    }
+
+    /// `value` as a constant of the cell type.
+    fn constant(&self, value: isize) -> i64 {
+        let shift = 64 - self.config.cell_width.bytes() * 8;
+        ((value as i64) << shift) >> shift
+    }
+
+    fn cmd(&mut self, instr_ptr: usize, cmd: &Cmd) {
+        let cell = self.cell;
+
+        match cmd.operator {
+            Op::Add => {
+                let address = self.address(cmd.offset);
+                let value = self.load(&address);
+                let sum = self.fresh("v");
+                let amount = self.constant(cmd.operand);
+                self.line(&format!("{sum} = add {cell} {value}, {amount}"));
+                self.line(&format!("store {cell} {sum}, ptr {address}"));
+            }
+            Op::Move => {
+                let index = self.index();
+                let moved = self.seek(&index, cmd.operand);
+                self.line(&format!("store i64 {moved}, ptr %p"));
+            }
+            Op::Out => {
+                self.outputs = true;
+                let address = self.address(cmd.offset);
+                let value = self.load(&address);
+                self.line(&format!(
+                    "call void @out({cell} {value}, i64 {})",
+                    cmd.operand
+                ));
+            }
+            Op::In => {
+                self.inputs = true;
+                let address = self.address(cmd.offset);
+                self.line(&format!(
+                    "call void @in(ptr {address}, i64 {})",
+                    cmd.operand
+                ));
+            }
+            Op::JmpZero | Op::JmpNonZero => {
+                let address = self.address(0);
+                let value = self.load(&address);
+                let test = self.fresh("z");
+                let condition = match cmd.operator {
+                    Op::JmpZero => "eq",
+                    _ => "ne",
+                };
+                self.line(&format!("{test} = icmp {condition} {cell} {value}, 0"));
+                self.line(&format!(
+                    "br i1 {test}, label %b{}, label %b{}",
+                    cmd.operand,
+                    instr_ptr + 1
+                ));
+                self.label(&format!("b{}", instr_ptr + 1));
+            }
+            Op::SetZero => {
+                let address = self.address(cmd.offset);
+                self.line(&format!("store {cell} 0, ptr {address}"));
+            }
+            Op::ScanLeft | Op::ScanRight => {
+                let delta = match cmd.operator {
+                    Op::ScanLeft => -cmd.operand,
+                    _ => cmd.operand,
+                };
+
+                self.line(&format!("br label %b{instr_ptr}.scan"));
+                self.label(&format!("b{instr_ptr}.scan"));
+                let index = self.index();
+                let address = self.element(&index);
+                let value = self.load(&address);
+                let test = self.fresh("z");
+                self.line(&format!("{test} = icmp eq {cell} {value}, 0"));
+                self.line(&format!(
+                    "br i1 {test}, label %b{instr_ptr}.done, label %b{instr_ptr}.step"
+                ));
+
+                self.label(&format!("b{instr_ptr}.step"));
+                let moved = self.seek(&index, delta);
+                self.line(&format!("store i64 {moved}, ptr %p"));
+                self.line(&format!("br label %b{instr_ptr}.scan"));
+                self.label(&format!("b{instr_ptr}.done"));
+            }
+            Op::MulAdd => {
+                let source = self.address(0);
+                let value = self.load(&source);
+
+                // Finding the cell may fail, which a zero source cell must not do
+                let bounded = self.config.tape_policy != TapePolicy::Wrap;
+                if bounded {
+                    let test = self.fresh("z");
+                    self.line(&format!("{test} = icmp eq {cell} {value}, 0"));
+                    self.line(&format!(
+                        "br i1 {test}, label %b{instr_ptr}.done, label %b{instr_ptr}.mul"
+                    ));
+                    self.label(&format!("b{instr_ptr}.mul"));
+                }
+
+                let address = self.address(cmd.offset);
+                let target = self.load(&address);
+                let product = match self.constant(cmd.operand) {
+                    1 => value,
+                    factor => {
+                        let product = self.fresh("v");
+                        self.line(&format!("{product} = mul {cell} {value}, {factor}"));
+                        product
+                    }
+                };
+                let sum = self.fresh("v");
+                self.line(&format!("{sum} = add {cell} {target}, {product}"));
+                self.line(&format!("store {cell} {sum}, ptr {address}"));
+
+                if bounded {
+                    self.line(&format!("br label %b{instr_ptr}.done"));
+                    self.label(&format!("b{instr_ptr}.done"));
+                }
+            }
+        }
+    }
+
+    /// Loads the memory pointer.
+    fn index(&mut self) -> String {
+        let index = self.fresh("i");
+        self.line(&format!("{index} = load i64, ptr %p"));
+        index
+    }
+
+    /// Returns the position `delta` cells away from `index`.
+    fn seek(&mut self, index: &str, delta: isize) -> String {
+        self.seeks = true;
+        let moved = self.fresh("i");
+        self.line(&format!(
+            "{moved} = call i64 @seek(i64 {index}, i64 {delta})"
+        ));
+        moved
+    }
+
+    /// Returns a pointer to the cell at `index`.
+    fn element(&mut self, index: &str) -> String {
+        let address = self.fresh("a");
+        let tape = self.tape.clone();
+        self.line(&format!(
+            "{address} = getelementptr inbounds {tape}, ptr @tape, i64 0, i64 {index}"
+        ));
+        address
+    }
+
+    /// Returns a pointer to the cell `offset` away from the current one.
+    fn address(&mut self, offset: isize) -> String {
+        let index = self.index();
+        let index = match offset {
+            0 => index,
+            _ => self.seek(&index, offset),
+        };
+        self.element(&index)
+    }
+
+    fn load(&mut self, address: &str) -> String {
+        let value = self.fresh("v");
+        let cell = self.cell;
+        self.line(&format!("{value} = load {cell}, ptr {address}"));
+        value
+    }
+
+    fn runs_off_tape(&self) -> bool {
+        self.seeks && self.config.tape_policy != TapePolicy::Wrap
+    }
+
+    fn reads_past_eof(&self) -> bool {
+        self.inputs && self.config.eof == EofPolicy::Error
+    }
+
+    /// Whether anything can stop the program with an error.
+    fn dies(&self) -> bool {
+        self.runs_off_tape() || self.reads_past_eof()
+    }
+
+    /// Puts the globals, the helpers `main` calls and the declarations they need around
+    /// the body of `main`.
+    fn module(&self) -> String {
+        let mut ll = String::new();
+        let flushes = self.dies()
+            || (self.outputs
+                && matches!(
+                    self.config.flush,
+                    FlushPolicy::Always | FlushPolicy::Newline
+                ))
+            || (self.inputs && self.config.flush == FlushPolicy::BeforeRead);
+
+        writeln!(ll, "@tape = internal global {} zeroinitializer", self.tape).unwrap();
+        for (name, message, used) in [
+            ("off_tape", OFF_TAPE, self.runs_off_tape()),
+            ("past_eof", PAST_EOF, self.reads_past_eof()),
+        ] {
+            if used {
+                writeln!(
+                    ll,
+                    "@{name} = private unnamed_addr constant [{} x i8] c\"{}\"",
+                    message.len(),
+                    message.replace('\n', "\\0A")
+                )
+                .unwrap();
+            }
+        }
+        ll.push('\n');
+
+        if self.outputs {
+            ll.push_str("declare i32 @putchar(i32)\n");
+        }
+        if self.inputs {
+            ll.push_str("declare i32 @getchar()\n");
+        }
+        if flushes {
+            ll.push_str("declare i32 @fflush(ptr)\n");
+        }
+        if self.dies() {
+            ll.push_str("declare i64 @write(i32, ptr, i64)\ndeclare void @exit(i32) noreturn\n");
+        }
+        if self.outputs || self.inputs || flushes {
+            ll.push('\n');
+        }
+
+        if self.dies() {
+            ll.push_str(
+                "; Flushes, prints the message `len` bytes long at `msg` to stderr and exits with 1.
+define internal void @die(ptr %msg, i64 %len) noreturn {
+entry:
+  call i32 @fflush(ptr null)
+  call i64 @write(i32 2, ptr %msg, i64 %len)
+  call void @exit(i32 1)
+  unreachable
+}
+
+",
+            );
+        }
+        if self.seeks {
+            self.seek_helper(&mut ll);
+        }
+        if self.outputs {
+            self.out_helper(&mut ll);
+        }
+        if self.inputs {
+            self.in_helper(&mut ll);
+        }
+
+        writeln!(
+            ll,
+            "define i32 @main() {{
+entry:
+  %p = alloca i64
+  store i64 0, ptr %p
+{}  ret i32 0
+}}",
+            self.body
+        )
+        .unwrap();
+        ll
+    }
+
+    fn seek_helper(&self, ll: &mut String) {
+        let len = self.config.tape_len;
+        let off_tape = match self.config.tape_policy {
+            TapePolicy::Wrap => format!(
+                "  %rem = srem i64 %to, {len}
+  %negative = icmp slt i64 %rem, 0
+  %wrapped = add i64 %rem, {len}
+  %index = select i1 %negative, i64 %wrapped, i64 %rem
+  ret i64 %index"
+            ),
+            TapePolicy::Error | TapePolicy::Grow => format!(
+                "  call void @die(ptr @off_tape, i64 {})
+  unreachable",
+                OFF_TAPE.len()
+            ),
+        };
+
+        writeln!(
+            ll,
+            "; Moves `delta` cells away from `from`, returning the new position.
+define internal i64 @seek(i64 %from, i64 %delta) {{
+entry:
+  %to = add i64 %from, %delta
+  %on_tape = icmp ult i64 %to, {len}
+  br i1 %on_tape, label %done, label %off_tape
+done:
+  ret i64 %to
+off_tape:
+{off_tape}
+}}
+"
+        )
+        .unwrap();
+    }
+
+    fn out_helper(&self, ll: &mut String) {
+        let cell = self.cell;
+        let byte = match self.config.cell_width {
+            CellWidth::U8 => "  %byte = zext i8 %value to i32".to_string(),
+            _ => format!("  %low = trunc {cell} %value to i8\n  %byte = zext i8 %low to i32"),
+        };
+        let flush = match self.config.flush {
+            FlushPolicy::Always => "  call i32 @fflush(ptr null)\n  ret void",
+            FlushPolicy::Newline => {
+                "  %newline = icmp eq i32 %byte, 10
+  br i1 %newline, label %flush, label %done
+flush:
+  call i32 @fflush(ptr null)
+  br label %done
+done:
+  ret void"
+            }
+            FlushPolicy::BeforeRead | FlushPolicy::Exit => "  ret void",
+        };
+
+        writeln!(
+            ll,
+            "; Writes the low byte of `value` `count` times.
+define internal void @out({cell} %value, i64 %count) {{
+entry:
+{byte}
+  br label %loop
+loop:
+  %left = phi i64 [ %count, %entry ], [ %next, %body ]
+  %finished = icmp eq i64 %left, 0
+  br i1 %finished, label %exit, label %body
+body:
+  call i32 @putchar(i32 %byte)
+  %next = sub i64 %left, 1
+  br label %loop
+exit:
+{flush}
+}}
+"
+        )
+        .unwrap();
+    }
+
+    fn in_helper(&self, ll: &mut String) {
+        let cell = self.cell;
+        let flush = match self.config.flush {
+            FlushPolicy::BeforeRead => "  call i32 @fflush(ptr null)\n",
+            _ => "",
+        };
+        let (convert, value) = match self.config.cell_width {
+            CellWidth::U8 => ("  %value = trunc i32 %byte to i8\n", "%value"),
+            CellWidth::U16 => ("  %value = trunc i32 %byte to i16\n", "%value"),
+            CellWidth::U32 => ("", "%byte"),
+            CellWidth::U64 => ("  %value = zext i32 %byte to i64\n", "%value"),
+        };
+        let eof = match self.config.eof {
+            EofPolicy::Zero => format!("  store {cell} 0, ptr %cell\n  br label %exit"),
+            EofPolicy::Max => format!("  store {cell} -1, ptr %cell\n  br label %exit"),
+            EofPolicy::Unchanged => "  br label %exit".to_string(),
+            EofPolicy::Error => format!(
+                "  call void @die(ptr @past_eof, i64 {})\n  unreachable",
+                PAST_EOF.len()
+            ),
+        };
+
+        writeln!(
+            ll,
+            "; Reads `count` bytes into `cell`, keeping the last, and applies the EOF policy at
+; the end of input.
+define internal void @in(ptr %cell, i64 %count) {{
+entry:
+{flush}  br label %loop
+loop:
+  %left = phi i64 [ %count, %entry ], [ %next, %read ]
+  %finished = icmp eq i64 %left, 0
+  br i1 %finished, label %exit, label %body
+body:
+  %byte = call i32 @getchar()
+  %at_eof = icmp slt i32 %byte, 0
+  br i1 %at_eof, label %eof, label %read
+read:
+{convert}  store {cell} {value}, ptr %cell
+  %next = sub i64 %left, 1
+  br label %loop
+eof:
+{eof}
+exit:
+  ret void
+}}
+"
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,340 @@
+//! Translates commands into a standalone Rust program.
+
+use std::fmt::Write;
+
+use crate::{
+    config::{CellWidth, Config, EofPolicy, FlushPolicy, TapePolicy},
+    parser::{Cmd, Op},
+};
+
+/// Returns a Rust program that behaves like `cmds` run with `config`.
+///
+/// The program is built around `pub fn run(input: &mut impl Read, output: &mut impl Write)`,
+/// which can be copied into another crate along with the items above it; `main` just runs
+/// it on stdin and stdout. Loops become `while` loops, and cleared and multiplied cells
+/// become assignments.
+///
+/// The cell type, tape length and tape, EOF and flush policies all carry over, and errors
+/// are returned as [`std::io::Error`]s. Output is always written as raw bytes whatever
+/// [`Config::output`] says, and [`Config::step_limit`] is ignored.
+pub fn emit(cmds: &[Cmd], config: &Config) -> String {
+    let mut rust = String::new();
+    let writer = Writer { config };
+
+    // Moves at the very end can't change the output, but can still run off the tape
+    let trailing = cmds
+        .iter()
+        .rev()
+        .take_while(|cmd| cmd.operator == Op::Move)
+        .count();
+    let (body, tail) = cmds.split_at(cmds.len() - trailing);
+    let tail = match writer.bounded() {
+        true => tail,
+        false => &[],
+    };
+
+    writer.prelude(&mut rust, body, tail);
+
+    let mut depth = 1;
+    for cmd in body {
+        if cmd.operator == Op::JmpNonZero {
+            depth -= 1;
+        }
+        let indent = "    ".repeat(depth);
+        for line in writer.cmd(cmd).lines() {
+            writeln!(rust, "{indent}{line}").unwrap();
+        }
+        if cmd.operator == Op::JmpZero {
+            depth += 1;
+        }
+    }
+
+    // Each move starts where the last one ended, but the last position is never read
+    for (i, cmd) in tail.iter().enumerate() {
+        match i + 1 < tail.len() {
+            true => writeln!(rust, "    p = {};", writer.seek(cmd.operand)),
+            false => writeln!(rust, "    {};", writer.seek(cmd.operand)),
+        }
+        .unwrap();
+    }
+
+    if !body.is_empty() || !tail.is_empty() {
+        rust.push('\n');
+    }
+    rust.push_str(
+        "    output.flush()
+}
+
+fn main() -> io::Result<()> {
+    let mut output = io::BufWriter::new(io::stdout().lock());
+    run(&mut io::stdin().lock(), &mut output)
+}
+",
+    );
+    rust
+}
+
+struct Writer<'a> {
+    config: &'a Config,
+}
+
+impl Writer<'_> {
+    /// Whether the tape has ends a position can't go past.
+    fn bounded(&self) -> bool {
+        self.config.tape_policy != TapePolicy::Wrap
+    }
+
+    /// The statements for `cmd`, one per line.
+    fn cmd(&self, cmd: &Cmd) -> String {
+        let (bind, cell) = self.cell(cmd.offset);
+
+        match cmd.operator {
+            Op::Add => {
+                let (method, amount) = match cmd.operand {
+                    operand if operand < 0 => ("wrapping_sub", operand.unsigned_abs()),
+                    operand => ("wrapping_add", operand as usize),
+                };
+                let amount = amount as u64 & self.config.cell_width.max();
+                format!("{bind}{cell} = {cell}.{method}({amount});")
+            }
+            Op::Move => format!("p = {};", self.seek(cmd.operand)),
+            Op::Out => format!("{bind}put(output, {cell}, {})?;", cmd.operand),
+            Op::In => {
+                let flush = match self.config.flush {
+                    FlushPolicy::BeforeRead => "output.flush()?;\n",
+                    _ => "",
+                };
+                format!("{flush}{bind}get(input, &mut {cell}, {})?;", cmd.operand)
+            }
+            Op::JmpZero => "while tape[p] != 0 {".to_string(),
+            Op::JmpNonZero => "}".to_string(),
+            Op::SetZero => format!("{bind}{cell} = 0;"),
+            Op::ScanLeft | Op::ScanRight => {
+                let delta = match cmd.operator {
+                    Op::ScanLeft => -cmd.operand,
+                    _ => cmd.operand,
+                };
+                format!("while tape[p] != 0 {{\n    p = {};\n}}", self.seek(delta))
+            }
+            Op::MulAdd => {
+                let (method, factor) = match cmd.operand {
+                    factor if factor < 0 => ("wrapping_sub", factor.unsigned_abs()),
+                    factor => ("wrapping_add", factor as usize),
+                };
+                let product = match factor as u64 & self.config.cell_width.max() {
+                    1 => "tape[p]".to_string(),
+                    factor => format!("tape[p].wrapping_mul({factor})"),
+                };
+                let add = format!("{cell} = {cell}.{method}({product});");
+
+                match self.bounded() {
+                    // Finding the cell may fail or grow the tape, which a zero source
+                    // cell must not do
+                    true => format!("if tape[p] != 0 {{\n    {bind}    {add}\n}}"),
+                    false => format!("{bind}{add}"),
+                }
+            }
+        }
+    }
+
+    /// Returns a statement binding the index of the cell `offset` away from `p` to `c`, if
+    /// one is needed, and an expression for the cell.
+    fn cell(&self, offset: isize) -> (String, String) {
+        if offset == 0 {
+            return (String::new(), "tape[p]".to_string());
+        }
+
+        let index = match self.config.tape_policy {
+            TapePolicy::Wrap => self.wrapped(offset),
+            TapePolicy::Error | TapePolicy::Grow => format!("at(&mut tape, &mut p, {offset})?"),
+        };
+        (format!("let c = {index};\n"), "tape[c]".to_string())
+    }
+
+    /// An expression for the position `delta` cells away from `p`.
+    fn seek(&self, delta: isize) -> String {
+        match self.config.tape_policy {
+            TapePolicy::Wrap => self.wrapped(delta),
+            TapePolicy::Error | TapePolicy::Grow => format!("seek(&mut tape, p, {delta})?"),
+        }
+    }
+
+    /// An expression for the position `delta` cells away from `p` on a wrapping tape.
+    fn wrapped(&self, delta: isize) -> String {
+        let len = self.config.tape_len;
+        match delta.rem_euclid(len as isize) as usize {
+            0 => "p".to_string(),
+            delta if delta <= len / 2 => format!("(p + {delta}) % LEN"),
+            delta => format!("(p + LEN - {}) % LEN", len - delta),
+        }
+    }
+
+    /// Writes the imports, the helpers the program calls and the start of `run`, which
+    /// goes on with the commands in `body` followed by the moves in `tail`, whose results
+    /// are never used.
+    fn prelude(&self, rust: &mut String, body: &[Cmd], tail: &[Cmd]) {
+        let has = |op| body.iter().any(|cmd| cmd.operator == op);
+        let bounded = self.bounded();
+        let has_offsets = body.iter().any(|cmd| cmd.offset != 0);
+        let moves = has(Op::Move) || has(Op::ScanLeft) || has(Op::ScanRight);
+        let seeks = bounded && (moves || has_offsets || !tail.is_empty());
+
+        rust.push_str("use std::io::{self, Read, Write};\n\n");
+        if !body.is_empty() || !tail.is_empty() {
+            let cell_type = match self.config.cell_width {
+                CellWidth::U8 => "u8",
+                CellWidth::U16 => "u16",
+                CellWidth::U32 => "u32",
+                CellWidth::U64 => "u64",
+            };
+            writeln!(
+                rust,
+                "type Cell = {cell_type};\n\nconst LEN: usize = {};\n",
+                self.config.tape_len
+            )
+            .unwrap();
+        }
+
+        if seeks {
+            self.seek_helper(rust);
+        }
+        if bounded && has_offsets {
+            rust.push_str(
+                "/// The index of the cell `offset` away from `*p`, which shifts if the tape grows
+/// to the left.
+fn at(tape: &mut Vec<Cell>, p: &mut usize, offset: isize) -> io::Result<usize> {
+    let to = seek(tape, *p, offset)?;
+    *p = seek(tape, to, -offset)?;
+    Ok(to)
+}
+
+",
+            );
+        }
+        if has(Op::Out) {
+            self.put_helper(rust);
+        }
+        if has(Op::In) {
+            self.get_helper(rust);
+        }
+
+        let input = if has(Op::In) { "input" } else { "_input" };
+        writeln!(
+            rust,
+            "/// Runs the program, reading from `input` and writing to `output`.
+pub fn run({input}: &mut impl Read, output: &mut impl Write) -> io::Result<()> {{"
+        )
+        .unwrap();
+
+        if !body.is_empty() || !tail.is_empty() {
+            let writes = [Op::Add, Op::In, Op::SetZero, Op::MulAdd]
+                .into_iter()
+                .any(has);
+            let tape_mut = if writes || seeks { "mut " } else { "" };
+            let p_mut = if moves || (bounded && has_offsets) || tail.len() > 1 {
+                "mut "
+            } else {
+                ""
+            };
+            writeln!(
+                rust,
+                "    let {tape_mut}tape: Vec<Cell> = vec![0; LEN];
+    let {p_mut}p = 0;
+"
+            )
+            .unwrap();
+        }
+    }
+
+    fn seek_helper(&self, rust: &mut String) {
+        let off_tape = match self.config.tape_policy {
+            TapePolicy::Grow => {
+                "    if to >= 0 {
+        // Grow geometrically so pointer sweeps stay amortized linear
+        tape.resize((to as usize + 1).max(tape.len() * 2), 0);
+        Ok(to as usize)
+    } else {
+        let extra = to.unsigned_abs().max(tape.len());
+        tape.splice(0..0, vec![0; extra]);
+        Ok((to + extra as isize) as usize)
+    }"
+            }
+            _ => "    Err(io::Error::other(\"memory pointer moved off the tape\"))",
+        };
+
+        writeln!(
+            rust,
+            "/// Moves `delta` cells away from `from`, returning the new position.
+fn seek(tape: &mut Vec<Cell>, from: usize, delta: isize) -> io::Result<usize> {{
+    let to = from as isize + delta;
+    if (0..tape.len() as isize).contains(&to) {{
+        return Ok(to as usize);
+    }}
+{off_tape}
+}}
+"
+        )
+        .unwrap();
+    }
+
+    fn put_helper(&self, rust: &mut String) {
+        let flush = match self.config.flush {
+            FlushPolicy::Always => "    output.flush()",
+            FlushPolicy::Newline => {
+                "    if cell as u8 == b'\\n' {
+        output.flush()?;
+    }
+    Ok(())"
+            }
+            FlushPolicy::BeforeRead | FlushPolicy::Exit => "    Ok(())",
+        };
+
+        writeln!(
+            rust,
+            "/// Writes the low byte of `cell` `count` times.
+fn put(output: &mut impl Write, cell: Cell, count: usize) -> io::Result<()> {{
+    for _ in 0..count {{
+        output.write_all(&[cell as u8])?;
+    }}
+{flush}
+}}
+"
+        )
+        .unwrap();
+    }
+
+    fn get_helper(&self, rust: &mut String) {
+        let eof = match self.config.eof {
+            EofPolicy::Zero => "{\n                *cell = 0;\n                return Ok(());\n            }",
+            EofPolicy::Max => {
+                "{\n                *cell = Cell::MAX;\n                return Ok(());\n            }"
+            }
+            EofPolicy::Unchanged => "return Ok(()),",
+            EofPolicy::Error => {
+                "{\n                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    \"tried to read past the end of input\",
+                ));\n            }"
+            }
+        };
+
+        writeln!(
+            rust,
+            "/// Reads `count` bytes into `cell`, keeping the last, and applies the EOF policy at
+/// the end of input.
+fn get(input: &mut impl Read, cell: &mut Cell, count: usize) -> io::Result<()> {{
+    let mut byte = [0];
+    for _ in 0..count {{
+        match input.read_exact(&mut byte) {{
+            Ok(()) => *cell = byte[0].into(),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {eof}
+            Err(err) => return Err(err),
+        }}
+    }}
+    Ok(())
+}}
+"
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,499 @@
+//! Translates commands into a WebAssembly module, as a binary or as text.
+//!
+//! The module imports its I/O from the host and exports its memory and one function:
+//!
+//! ```text
+//! (import "env" "out" (func $out (param i32)))  ;; writes the byte given
+//! (import "env" "in" (func $in (result i32)))   ;; reads a byte, or returns -1 at the end
+//! (memory (export "memory") ...)                ;; the tape, starting at address 0
+//! (func (export "run") ...)                     ;; runs the program once
+//! ```
+//!
+//! Errors, such as moving off the tape under [`TapePolicy::Error`], trap.
+
+use std::fmt::Write;
+
+use crate::{
+    config::{CellWidth, Config, EofPolicy, TapePolicy},
+    parser::{Cmd, Op},
+};
+
+/// Returns the binary encoding of a module that behaves like `cmds` run with `config`.
+///
+/// The cell width, tape length and tape and EOF policies carry over, except that the
+/// tape lives in a fixed amount of memory, so [`TapePolicy::Grow`] traps like
+/// [`TapePolicy::Error`] does. Output is always passed to the host as raw bytes whatever
+/// [`Config::output`] says, and [`Config::step_limit`] and [`Config::flush`] are left to
+/// the host.
+pub fn emit(cmds: &[Cmd], config: &Config) -> Vec<u8> {
+    let mut module = b"\0asm\x01\0\0\0".to_vec();
+
+    // Types: out, in and run
+    section(
+        &mut module,
+        1,
+        &[3, 0x60, 1, 0x7F, 0, 0x60, 0, 1, 0x7F, 0x60, 0, 0],
+    );
+
+    let mut imports = vec![2];
+    for (name, ty) in [("out", 0), ("in", 1)] {
+        name_bytes(&mut imports, "env");
+        name_bytes(&mut imports, name);
+        imports.extend_from_slice(&[0x00, ty]);
+    }
+    section(&mut module, 2, &imports);
+
+    // One function, of type run
+    section(&mut module, 3, &[1, 2]);
+
+    let pages = pages(config);
+    let mut memory = vec![1, 0x01];
+    unsigned(&mut memory, pages);
+    unsigned(&mut memory, pages);
+    section(&mut module, 5, &memory);
+
+    let mut exports = vec![2];
+    name_bytes(&mut exports, "memory");
+    exports.extend_from_slice(&[0x02, 0]);
+    name_bytes(&mut exports, "run");
+    exports.extend_from_slice(&[0x00, Func::Run as u8]);
+    section(&mut module, 7, &exports);
+
+    // Four i32 locals followed by one i64
+    let mut body = vec![2, 4, 0x7F, 1, 0x7E];
+    for instr in lower(cmds, config).iter().flatten() {
+        instr.encode(&mut body, config.cell_width);
+    }
+    body.push(0x0B);
+    let mut code = vec![1];
+    unsigned(&mut code, body.len() as u64);
+    code.extend_from_slice(&body);
+    section(&mut module, 10, &code);
+
+    module
+}
+
+/// Returns the text format of the module [`emit`] encodes, with each command's
+/// instructions preceded by the command as a comment.
+pub fn emit_wat(cmds: &[Cmd], config: &Config) -> String {
+    let pages = pages(config);
+    let mut wat = format!(
+        "(module
+  (import \"env\" \"out\" (func $out (param i32)))
+  (import \"env\" \"in\" (func $in (result i32)))
+  (memory (export \"memory\") {pages} {pages})
+  (func (export \"run\")
+    (local $p i32) (local $t i32) (local $a i32) (local $b i32) (local $v i64)
+"
+    );
+
+    let mut depth = 2;
+    for (cmd, instrs) in cmds.iter().zip(lower(cmds, config)) {
+        writeln!(wat, "{};; {cmd}", "  ".repeat(depth)).unwrap();
+        for instr in instrs {
+            if instr == Instr::End {
+                depth -= 1;
+            }
+            writeln!(
+                wat,
+                "{}{}",
+                "  ".repeat(depth),
+                instr.text(config.cell_width)
+            )
+            .unwrap();
+            if matches!(instr, Instr::Block | Instr::Loop | Instr::If) {
+                depth += 1;
+            }
+        }
+    }
+
+    wat.push_str("  )\n)\n");
+    wat
+}
+
+/// The 64 KiB pages needed to hold the tape.
+fn pages(config: &Config) -> u64 {
+    let bytes = config.tape_len as u64 * config.cell_width.bytes() as u64;
+    bytes.div_ceil(0x10000)
+}
+
+/// Appends section `id` holding `contents`.
+fn section(module: &mut Vec<u8>, id: u8, contents: &[u8]) {
+    module.push(id);
+    unsigned(module, contents.len() as u64);
+    module.extend_from_slice(contents);
+}
+
+fn name_bytes(bytes: &mut Vec<u8>, name: &str) {
+    unsigned(bytes, name.len() as u64);
+    bytes.extend_from_slice(name.as_bytes());
+}
+
+/// Appends `value` as an unsigned LEB128.
+fn unsigned(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// Appends `value` as a signed LEB128.
+fn signed(bytes: &mut Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        let sign = byte & 0x40 != 0;
+        if (value == 0 && !sign) || (value == -1 && sign) {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Func {
+    Out,
+    In,
+    Run,
+}
+
+/// The locals of the run function.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Local {
+    /// The memory pointer, as a cell index.
+    P,
+    /// The index of a cell at an offset from the memory pointer.
+    T,
+    /// A cell's byte address.
+    A,
+    /// A byte read or written.
+    B,
+    /// A cell's value.
+    V,
+}
+
+/// The instructions the backend uses. Blocks never take or leave values.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Instr {
+    Unreachable,
+    Block,
+    Loop,
+    If,
+    End,
+    Br(u32),
+    BrIf(u32),
+    Call(Func),
+    LocalGet(Local),
+    LocalSet(Local),
+    LocalTee(Local),
+    I32Const(i32),
+    I64Const(i64),
+    I32Eqz,
+    I32LtS,
+    I32GeU,
+    I32Add,
+    I32RemU,
+    I32And,
+    I32Shl,
+    I64Eqz,
+    I64Add,
+    I64Mul,
+    I32WrapI64,
+    I64ExtendI32U,
+    /// Loads a cell, zero-extended to 64 bits.
+    Load,
+    /// Stores the low bits of a 64-bit value into a cell.
+    Store,
+}
+
+impl Instr {
+    fn encode(self, bytes: &mut Vec<u8>, width: CellWidth) {
+        let opcode = match self {
+            Instr::Unreachable => 0x00,
+            Instr::Block => return bytes.extend_from_slice(&[0x02, 0x40]),
+            Instr::Loop => return bytes.extend_from_slice(&[0x03, 0x40]),
+            Instr::If => return bytes.extend_from_slice(&[0x04, 0x40]),
+            Instr::End => 0x0B,
+            Instr::Br(depth) => return bytes.extend_from_slice(&[0x0C, depth as u8]),
+            Instr::BrIf(depth) => return bytes.extend_from_slice(&[0x0D, depth as u8]),
+            Instr::Call(func) => return bytes.extend_from_slice(&[0x10, func as u8]),
+            Instr::LocalGet(local) => return bytes.extend_from_slice(&[0x20, local as u8]),
+            Instr::LocalSet(local) => return bytes.extend_from_slice(&[0x21, local as u8]),
+            Instr::LocalTee(local) => return bytes.extend_from_slice(&[0x22, local as u8]),
+            Instr::I32Const(value) => {
+                bytes.push(0x41);
+                return signed(bytes, value.into());
+            }
+            Instr::I64Const(value) => {
+                bytes.push(0x42);
+                return signed(bytes, value);
+            }
+            Instr::I32Eqz => 0x45,
+            Instr::I32LtS => 0x48,
+            Instr::I32GeU => 0x4F,
+            Instr::I32Add => 0x6A,
+            Instr::I32RemU => 0x70,
+            Instr::I32And => 0x71,
+            Instr::I32Shl => 0x74,
+            Instr::I64Eqz => 0x50,
+            Instr::I64Add => 0x7C,
+            Instr::I64Mul => 0x7E,
+            Instr::I32WrapI64 => 0xA7,
+            Instr::I64ExtendI32U => 0xAD,
+            Instr::Load | Instr::Store => {
+                let (load, store) = match width {
+                    CellWidth::U8 => (0x31, 0x3C),
+                    CellWidth::U16 => (0x33, 0x3D),
+                    CellWidth::U32 => (0x35, 0x3E),
+                    CellWidth::U64 => (0x29, 0x37),
+                };
+                let opcode = if self == Instr::Load { load } else { store };
+                // Naturally aligned, no offset
+                let align = width.bytes().trailing_zeros() as u8;
+                return bytes.extend_from_slice(&[opcode, align, 0]);
+            }
+        };
+        bytes.push(opcode);
+    }
+
+    fn text(self, width: CellWidth) -> String {
+        let suffix = match width {
+            CellWidth::U8 => "8",
+            CellWidth::U16 => "16",
+            CellWidth::U32 => "32",
+            CellWidth::U64 => "",
+        };
+
+        match self {
+            Instr::Unreachable => "unreachable".to_string(),
+            Instr::Block => "block".to_string(),
+            Instr::Loop => "loop".to_string(),
+            Instr::If => "if".to_string(),
+            Instr::End => "end".to_string(),
+            Instr::Br(depth) => format!("br {depth}"),
+            Instr::BrIf(depth) => format!("br_if {depth}"),
+            Instr::Call(func) => format!("call ${}", func.name()),
+            Instr::LocalGet(local) => format!("local.get ${}", local.name()),
+            Instr::LocalSet(local) => format!("local.set ${}", local.name()),
+            Instr::LocalTee(local) => format!("local.tee ${}", local.name()),
+            Instr::I32Const(value) => format!("i32.const {value}"),
+            Instr::I64Const(value) => format!("i64.const {value}"),
+            Instr::I32Eqz => "i32.eqz".to_string(),
+            Instr::I32LtS => "i32.lt_s".to_string(),
+            Instr::I32GeU => "i32.ge_u".to_string(),
+            Instr::I32Add => "i32.add".to_string(),
+            Instr::I32RemU => "i32.rem_u".to_string(),
+            Instr::I32And => "i32.and".to_string(),
+            Instr::I32Shl => "i32.shl".to_string(),
+            Instr::I64Eqz => "i64.eqz".to_string(),
+            Instr::I64Add => "i64.add".to_string(),
+            Instr::I64Mul => "i64.mul".to_string(),
+            Instr::I32WrapI64 => "i32.wrap_i64".to_string(),
+            Instr::I64ExtendI32U => "i64.extend_i32_u".to_string(),
+            Instr::Load if width == CellWidth::U64 => "i64.load".to_string(),
+            Instr::Load => format!("i64.load{suffix}_u"),
+            Instr::Store => format!("i64.store{suffix}"),
+        }
+    }
+}
+
+impl Func {
+    fn name(self) -> &'static str {
+        match self {
+            Func::Out => "out",
+            Func::In => "in",
+            Func::Run => "run",
+        }
+    }
+}
+
+impl Local {
+    fn name(self) -> &'static str {
+        match self {
+            Local::P => "p",
+            Local::T => "t",
+            Local::A => "a",
+            Local::B => "b",
+            Local::V => "v",
+        }
+    }
+}
+
+/// Returns the instructions for each command.
+fn lower(cmds: &[Cmd], config: &Config) -> Vec<Vec<Instr>> {
+    let lowering = Lowering {
+        len: config.tape_len as i64,
+        shift: config.cell_width.bytes().trailing_zeros() as i32,
+        config,
+    };
+
+    cmds.iter()
+        .map(|cmd| {
+            let mut instrs = Vec::new();
+            lowering.cmd(&mut instrs, cmd);
+            instrs
+        })
+        .collect()
+}
+
+struct Lowering<'a> {
+    /// The tape length.
+    len: i64,
+    /// Turns a cell index into a byte address.
+    shift: i32,
+    config: &'a Config,
+}
+
+impl Lowering<'_> {
+    fn cmd(&self, instrs: &mut Vec<Instr>, cmd: &Cmd) {
+        use Instr::*;
+
+        match cmd.operator {
+            Op::Add => {
+                self.address(instrs, cmd.offset);
+                instrs.extend([
+                    LocalTee(Local::A),
+                    LocalGet(Local::A),
+                    Load,
+                    I64Const(cmd.operand as i64),
+                    I64Add,
+                    Store,
+                ]);
+            }
+            Op::Move => self.move_ptr(instrs, cmd.operand),
+            Op::Out => {
+                self.address(instrs, cmd.offset);
+                instrs.extend([Load, I32WrapI64, I32Const(0xFF), I32And, LocalSet(Local::B)]);
+                for _ in 0..cmd.operand {
+                    instrs.extend([LocalGet(Local::B), Call(Func::Out)]);
+                }
+            }
+            Op::In => {
+                // Stops reading at the end of input, like the interpreter does
+                instrs.push(Block);
+                for _ in 0..cmd.operand {
+                    instrs.extend([Call(Func::In), LocalTee(Local::B), I32Const(0), I32LtS, If]);
+                    match self.config.eof {
+                        EofPolicy::Zero => self.store_const(instrs, cmd.offset, 0),
+                        EofPolicy::Max => self.store_const(instrs, cmd.offset, -1),
+                        EofPolicy::Unchanged => {}
+                        EofPolicy::Error => instrs.push(Unreachable),
+                    }
+                    instrs.extend([Br(1), End]);
+
+                    self.address(instrs, cmd.offset);
+                    instrs.extend([LocalGet(Local::B), I64ExtendI32U, Store]);
+                }
+                instrs.push(End);
+            }
+            Op::JmpZero => {
+                instrs.extend([Block, Loop]);
+                self.exit_if_zero(instrs);
+            }
+            Op::JmpNonZero => instrs.extend([Br(0), End, End]),
+            Op::SetZero => self.store_const(instrs, cmd.offset, 0),
+            Op::ScanLeft | Op::ScanRight => {
+                instrs.extend([Block, Loop]);
+                self.exit_if_zero(instrs);
+                let delta = match cmd.operator {
+                    Op::ScanLeft => -cmd.operand,
+                    _ => cmd.operand,
+                };
+                self.move_ptr(instrs, delta);
+                instrs.extend([Br(0), End, End]);
+            }
+            Op::MulAdd => {
+                self.address(instrs, 0);
+                instrs.extend([Load, LocalTee(Local::V), I64Eqz, I32Eqz, If]);
+                self.address(instrs, cmd.offset);
+                instrs.extend([
+                    LocalTee(Local::A),
+                    LocalGet(Local::A),
+                    Load,
+                    LocalGet(Local::V),
+                    I64Const(cmd.operand as i64),
+                    I64Mul,
+                    I64Add,
+                    Store,
+                    End,
+                ]);
+            }
+        }
+    }
+
+    /// Breaks out of the enclosing block if the current cell is zero.
+    fn exit_if_zero(&self, instrs: &mut Vec<Instr>) {
+        self.address(instrs, 0);
+        instrs.extend([Instr::Load, Instr::I64Eqz, Instr::BrIf(1)]);
+    }
+
+    fn store_const(&self, instrs: &mut Vec<Instr>, offset: isize, value: i64) {
+        self.address(instrs, offset);
+        instrs.extend([Instr::I64Const(value), Instr::Store]);
+    }
+
+    /// Pushes the byte address of the cell `offset` away from the current one, applying
+    /// the tape policy if it is off the tape. The memory pointer stays where it is.
+    fn address(&self, instrs: &mut Vec<Instr>, offset: isize) {
+        if offset == 0 {
+            instrs.push(Instr::LocalGet(Local::P));
+        } else {
+            instrs.extend([
+                Instr::LocalGet(Local::P),
+                Instr::I32Const(self.distance(offset)),
+                Instr::I32Add,
+                Instr::LocalTee(Local::T),
+            ]);
+            self.wrap(instrs, Local::T);
+            instrs.push(Instr::LocalGet(Local::T));
+        }
+
+        if self.shift > 0 {
+            instrs.extend([Instr::I32Const(self.shift), Instr::I32Shl]);
+        }
+    }
+
+    fn move_ptr(&self, instrs: &mut Vec<Instr>, delta: isize) {
+        instrs.extend([
+            Instr::LocalGet(Local::P),
+            Instr::I32Const(self.distance(delta)),
+            Instr::I32Add,
+            Instr::LocalTee(Local::P),
+        ]);
+        self.wrap(instrs, Local::P);
+    }
+
+    /// Applies the tape policy to the index both on the stack and in `local`, which is
+    /// consumed from the stack.
+    fn wrap(&self, instrs: &mut Vec<Instr>, local: Local) {
+        // Off either end of the tape, since a negative index compares as huge
+        instrs.extend([Instr::I32Const(self.len as i32), Instr::I32GeU, Instr::If]);
+        match self.config.tape_policy {
+            TapePolicy::Wrap => instrs.extend([
+                Instr::LocalGet(local),
+                Instr::I32Const(self.len as i32),
+                Instr::I32RemU,
+                Instr::LocalSet(local),
+            ]),
+            TapePolicy::Error | TapePolicy::Grow => instrs.push(Instr::Unreachable),
+        }
+        instrs.push(Instr::End);
+    }
+
+    /// The constant to add to an index to move it `delta` cells. When wrapping, this is
+    /// less than the tape length, so the sum is less than twice the tape length and a
+    /// remainder brings it back onto the tape. Otherwise it is clamped, since anything
+    /// that large is off the tape anyway.
+    fn distance(&self, delta: isize) -> i32 {
+        match self.config.tape_policy {
+            TapePolicy::Wrap => (delta as i64).rem_euclid(self.len) as i32,
+            _ => delta.clamp(i32::MIN as isize, i32::MAX as isize) as i32,
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::{fmt, str::FromStr};
+
+/// What happens when the memory pointer moves past either end of the tape.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum TapePolicy {
+    /// Continue from the opposite end of the tape.
+    #[default]
+    Wrap,
+    /// Stop with [`RuntimeErrorKind::TapeOverflow`](crate::RuntimeErrorKind::TapeOverflow).
+    Error,
+    /// Extend the tape with zeroed cells on whichever side was left.
+    Grow,
+}
+
+impl FromStr for TapePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wrap" => Ok(TapePolicy::Wrap),
+            "error" => Ok(TapePolicy::Error),
+            "grow" => Ok(TapePolicy::Grow),
+            _ => Err(format!(
+                "unknown tape policy `{s}`, expected wrap, error or grow"
+            )),
+        }
+    }
+}
+
+/// What `,` does to the current cell once input has run out.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum EofPolicy {
+    /// Set the cell to 0.
+    #[default]
+    Zero,
+    /// Set the cell to -1, i.e. the largest value it can hold.
+    Max,
+    /// Leave the cell as it was.
+    Unchanged,
+    /// Stop with [`RuntimeErrorKind::UnexpectedEof`](crate::RuntimeErrorKind::UnexpectedEof).
+    Error,
+}
+
+impl FromStr for EofPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "zero" | "0" => Ok(EofPolicy::Zero),
+            "max" | "-1" => Ok(EofPolicy::Max),
+            "unchanged" => Ok(EofPolicy::Unchanged),
+            "error" => Ok(EofPolicy::Error),
+            _ => Err(format!(
+                "unknown EOF policy `{s}`, expected zero, max, unchanged or error"
+            )),
+        }
+    }
+}
+
+/// How the values printed by `.` are turned into output bytes.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum OutputEncoding {
+    /// Write the low byte of the cell as is.
+    #[default]
+    Raw,
+    /// Like [`OutputEncoding::Raw`], but only allow values up to 127.
+    Ascii,
+    /// Treat values up to 255 as Latin-1 characters and write them as UTF-8.
+    Latin1,
+    /// Write bytes as is, but check that together they form valid UTF-8.
+    Utf8,
+}
+
+impl FromStr for OutputEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raw" => Ok(OutputEncoding::Raw),
+            "ascii" => Ok(OutputEncoding::Ascii),
+            "latin1" => Ok(OutputEncoding::Latin1),
+            "utf8" => Ok(OutputEncoding::Utf8),
+            _ => Err(format!(
+                "unknown output encoding `{s}`, expected raw, ascii, latin1 or utf8"
+            )),
+        }
+    }
+}
+
+/// When buffered output is flushed to the underlying stream. Output is always
+/// flushed once the program stops.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum FlushPolicy {
+    /// After every `.`.
+    Always,
+    /// Whenever a newline is written, so progress output from long-running programs
+    /// shows up line by line.
+    #[default]
+    Newline,
+    /// Before every `,`, so prompts show up before the program waits for input.
+    BeforeRead,
+    /// Only when the buffer fills up or the program stops.
+    Exit,
+}
+
+impl FromStr for FlushPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "always" => Ok(FlushPolicy::Always),
+            "newline" => Ok(FlushPolicy::Newline),
+            "read" => Ok(FlushPolicy::BeforeRead),
+            "exit" => Ok(FlushPolicy::Exit),
+            _ => Err(format!(
+                "unknown flush policy `{s}`, expected always, newline, read or exit"
+            )),
+        }
+    }
+}
+
+/// How many bits each cell on the tape holds.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum CellWidth {
+    #[default]
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl CellWidth {
+    pub fn bits(self) -> u32 {
+        match self {
+            CellWidth::U8 => 8,
+            CellWidth::U16 => 16,
+            CellWidth::U32 => 32,
+            CellWidth::U64 => 64,
+        }
+    }
+
+    pub fn bytes(self) -> usize {
+        self.bits() as usize / 8
+    }
+
+    /// The largest value a cell can hold.
+    pub fn max(self) -> u64 {
+        u64::MAX >> (64 - self.bits())
+    }
+}
+
+impl FromStr for CellWidth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "8" => Ok(CellWidth::U8),
+            "16" => Ok(CellWidth::U16),
+            "32" => Ok(CellWidth::U32),
+            "64" => Ok(CellWidth::U64),
+            _ => Err(format!(
+                "unknown cell width `{s}`, expected 8, 16, 32 or 64"
+            )),
+        }
+    }
+}
+
+/// Settings shared by every backend that executes a program.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Config {
+    /// Maximum number of commands to execute before giving up, or `None` to run forever.
+    pub step_limit: Option<u64>,
+    /// Number of cells the tape starts with, at least one.
+    pub tape_len: usize,
+    pub tape_policy: TapePolicy,
+    pub cell_width: CellWidth,
+    pub eof: EofPolicy,
+    pub output: OutputEncoding,
+    pub flush: FlushPolicy,
+}
+
+impl Config {
+    /// Checks the settings that no backend can run with.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.tape_len == 0 {
+            return Err(ConfigError::EmptyTape);
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            step_limit: None,
+            tape_len: 30_000,
+            tape_policy: TapePolicy::default(),
+            cell_width: CellWidth::default(),
+            eof: EofPolicy::default(),
+            output: OutputEncoding::default(),
+            flush: FlushPolicy::default(),
+        }
+    }
+}
+
+/// A setting in a [`Config`] that no backend can run with.
+#[derive(PartialEq, Eq, Debug)]
+pub enum ConfigError {
+    /// `tape_len` is zero, leaving no cell for the memory pointer to start on.
+    EmptyTape,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::EmptyTape => write!(f, "the tape must be at least one cell long"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
@@ -1,67 +1,600 @@
 use std::{
+    fmt,
     io::{self, Read, Write},
-    str::from_utf8,
 };
 
-use crate::parser::{Cmd, Op};
+use crate::{
+    bytecode,
+    config::{Config, ConfigError, EofPolicy},
+    output::Output,
+    parser::{Cmd, Op, Span},
+    runner::Error,
+    tape::{locate, seek, Cell, Tape},
+};
+
+#[derive(Debug)]
+pub enum RuntimeErrorKind {
+    /// Reading input or writing output failed.
+    Io(io::Error),
+    /// `.` was executed on a cell that can't be written in the configured
+    /// [`OutputEncoding`](crate::OutputEncoding).
+    InvalidOutput(u64),
+    /// The output is not valid UTF-8 under [`OutputEncoding::Utf8`](crate::OutputEncoding::Utf8).
+    InvalidUtf8(Vec<u8>),
+    /// The configured step limit was reached.
+    StepLimit(u64),
+    /// The memory pointer moved off the tape under [`TapePolicy::Error`](crate::TapePolicy::Error).
+    TapeOverflow,
+    /// `,` was executed after input ran out under [`EofPolicy::Error`].
+    UnexpectedEof,
+}
+
+/// An error raised while executing a program, along with where it happened.
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    /// Index of the command that failed.
+    pub instr_ptr: usize,
+    /// Where the failing command starts in the source, if known. Only a
+    /// [`Runner`](crate::Runner) built from source knows where its commands came from.
+    pub span: Option<Span>,
+    /// Tape position at the time of failure, or of the cell being read or written if
+    /// the failing command addresses one at an offset.
+    pub mem_ptr: usize,
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "Runtime error at {span}")?,
+            None => write!(f, "Runtime error at command {}", self.instr_ptr)?,
+        }
+        write!(f, " (memory pointer {}): {}", self.mem_ptr, self.kind)
+    }
+}
+
+impl fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeErrorKind::Io(err) => write!(f, "I/O error: {err}"),
+            RuntimeErrorKind::InvalidOutput(value) => {
+                write!(f, "cell value {value} can't be output in this encoding")
+            }
+            RuntimeErrorKind::InvalidUtf8(bytes) => {
+                write!(f, "output bytes {bytes:02X?} are not valid UTF-8")
+            }
+            RuntimeErrorKind::StepLimit(limit) => write!(f, "step limit of {limit} reached"),
+            RuntimeErrorKind::TapeOverflow => write!(f, "memory pointer moved off the tape"),
+            RuntimeErrorKind::UnexpectedEof => write!(f, "tried to read past the end of input"),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            RuntimeErrorKind::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
 
-const MEM_SIZE: usize = 30_000;
 pub struct Interpreter {
-    mem: [u8; MEM_SIZE],
+    tape: Tape,
+    config: Config,
 }
 
 impl Interpreter {
     pub fn new() -> Self {
-        Self { mem: [0; MEM_SIZE] }
+        Self::with_config(Config::default()).expect("the default config is valid")
+    }
+
+    /// Fails if `config` doesn't pass [`Config::validate`].
+    pub fn with_config(config: Config) -> Result<Self, ConfigError> {
+        config.validate()?;
+
+        Ok(Self {
+            tape: Tape::new(config.cell_width, config.tape_len),
+            config,
+        })
+    }
+
+    /// Reads a program written by [`bytecode::encode`], returning its commands and an
+    /// interpreter for them. The program's cell width and tape settings replace those in
+    /// `config`.
+    pub fn load(bytes: &[u8], config: Config) -> Result<(Self, Vec<Cmd>), Error> {
+        let program = bytecode::decode(bytes)?;
+        Ok((Self::with_config(program.config(config))?, program.cmds))
+    }
+
+    /// Runs `cmds` reading from stdin and writing to stdout.
+    pub fn run_all(&mut self, cmds: &[Cmd]) -> Result<(), RuntimeError> {
+        self.run_with_io(cmds, &mut io::stdin(), &mut io::stdout())
+    }
+
+    /// Runs `cmds` with `input` as the program's input, returning everything it printed.
+    pub fn run_with_input(
+        &mut self,
+        cmds: &[Cmd],
+        mut input: &[u8],
+    ) -> Result<Vec<u8>, RuntimeError> {
+        let mut output = Vec::new();
+        self.run_with_io(cmds, &mut input, &mut output)?;
+        Ok(output)
+    }
+
+    /// Runs the program reading from `input` and writing to `output`, flushing `output`
+    /// once it stops.
+    pub fn run_with_io<R: Read, W: Write>(
+        &mut self,
+        cmds: &[Cmd],
+        input: &mut R,
+        output: &mut W,
+    ) -> Result<(), RuntimeError> {
+        match &mut self.tape {
+            Tape::U8(mem) => execute(&self.config, mem, cmds, input, output),
+            Tape::U16(mem) => execute(&self.config, mem, cmds, input, output),
+            Tape::U32(mem) => execute(&self.config, mem, cmds, input, output),
+            Tape::U64(mem) => execute(&self.config, mem, cmds, input, output),
+        }
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
     }
+}
+
+fn execute<C: Cell, R: Read, W: Write>(
+    config: &Config,
+    mem: &mut Vec<C>,
+    cmds: &[Cmd],
+    input: &mut R,
+    output: &mut W,
+) -> Result<(), RuntimeError> {
+    let mut output = Output::new(output, config);
+    let mut mem_ptr = 0;
+    let mut instr_ptr = 0;
+    let mut steps = 0;
 
-    pub fn run_all(&mut self, cmds: Vec<Cmd>) {
-        let mut mem_ptr = 0;
-        let mut instr_ptr = 0;
+    let error = |kind, instr_ptr, mem_ptr| RuntimeError {
+        kind,
+        instr_ptr,
+        span: None,
+        mem_ptr,
+    };
 
-        while instr_ptr < cmds.len() {
-            let cell = &mut self.mem[mem_ptr];
-            let cmd = &cmds[instr_ptr];
+    while instr_ptr < cmds.len() {
+        count_step(&mut steps, config.step_limit)
+            .map_err(|kind| error(kind, instr_ptr, mem_ptr))?;
 
-            match cmd.operator {
-                Op::Add => *cell = cell.wrapping_add(cmd.operand as u8),
-                Op::Sub => *cell = cell.wrapping_sub(cmd.operand as u8),
-                Op::Left => {
-                    mem_ptr = (mem_ptr as i128 - cmd.operand as i128).rem_euclid(MEM_SIZE as i128)
-                        as usize
+        let cmd = &cmds[instr_ptr];
+        let overflow = |mem_ptr| error(RuntimeErrorKind::TapeOverflow, instr_ptr, mem_ptr);
+
+        match cmd.operator {
+            Op::Add => {
+                let at = cell_index(mem, config, &mut mem_ptr, cmd.offset)
+                    .ok_or_else(|| overflow(mem_ptr))?;
+                mem[at] = mem[at].wrapping_add(C::truncate(cmd.operand as u64));
+            }
+            Op::Move => {
+                mem_ptr = seek(mem, config.tape_policy, mem_ptr, cmd.operand)
+                    .ok_or_else(|| overflow(mem_ptr))?;
+            }
+            Op::Out => {
+                let at = cell_index(mem, config, &mut mem_ptr, cmd.offset)
+                    .ok_or_else(|| overflow(mem_ptr))?;
+                output
+                    .write_cell(mem[at].to_u64(), cmd.operand as usize)
+                    .map_err(|kind| error(kind, instr_ptr, at))?;
+            }
+            Op::In => {
+                let at = cell_index(mem, config, &mut mem_ptr, cmd.offset)
+                    .ok_or_else(|| overflow(mem_ptr))?;
+                output
+                    .before_read()
+                    .map_err(|err| error(RuntimeErrorKind::Io(err), instr_ptr, at))?;
+
+                let value = read_cell(input, cmd.operand as usize, mem[at].to_u64(), config)
+                    .map_err(|err| error(RuntimeErrorKind::Io(err), instr_ptr, at))?
+                    .ok_or_else(|| error(RuntimeErrorKind::UnexpectedEof, instr_ptr, at))?;
+
+                mem[at] = C::truncate(value);
+            }
+            Op::JmpZero => {
+                if mem[mem_ptr] == C::default() {
+                    instr_ptr = cmd.operand as usize;
+                    continue;
                 }
-                Op::Right => mem_ptr = (mem_ptr + cmd.operand).rem_euclid(MEM_SIZE),
-                Op::Out => {
-                    if !cell.is_ascii() {
-                        panic!("Runtime error: tried to output invalid ascii");
-                    }
-
-                    let output: Vec<_> = (0..cmd.operand).map(|_| *cell).collect();
-                    print!("{}", from_utf8(&output).expect("Is valid ascii"));
-                    io::stdout().flush().expect("Could not flush to stdout");
+            }
+            Op::JmpNonZero => {
+                if mem[mem_ptr] != C::default() {
+                    instr_ptr = cmd.operand as usize;
+                    continue;
                 }
-                Op::In => {
-                    let mut buf = vec![0; cmd.operand];
-                    let res = io::stdin().read_exact(&mut buf);
+            }
+            Op::SetZero => {
+                let at = cell_index(mem, config, &mut mem_ptr, cmd.offset)
+                    .ok_or_else(|| overflow(mem_ptr))?;
+                mem[at] = C::default();
+            }
+            Op::ScanLeft | Op::ScanRight => {
+                let delta = match cmd.operator {
+                    Op::ScanLeft => -cmd.operand,
+                    _ => cmd.operand,
+                };
 
-                    // Only the last byte stays
-                    *cell = res.map(|_| buf[cmd.operand - 1]).unwrap_or(0);
-                }
-                Op::JmpZero => {
-                    if *cell == 0 {
-                        instr_ptr = cmd.operand;
-                        continue;
-                    }
+                // Every move counts, so a scan that never finds a zero still stops
+                while mem[mem_ptr] != C::default() {
+                    count_step(&mut steps, config.step_limit)
+                        .map_err(|kind| error(kind, instr_ptr, mem_ptr))?;
+                    mem_ptr = seek(mem, config.tape_policy, mem_ptr, delta)
+                        .ok_or_else(|| overflow(mem_ptr))?;
                 }
-                Op::JmpNonZero => {
-                    if *cell != 0 {
-                        instr_ptr = cmd.operand;
-                        continue;
-                    }
+            }
+            Op::MulAdd => {
+                if mem[mem_ptr] != C::default() {
+                    let target = cell_index(mem, config, &mut mem_ptr, cmd.offset)
+                        .ok_or_else(|| overflow(mem_ptr))?;
+
+                    let value = mem[mem_ptr].wrapping_mul(C::truncate(cmd.operand as u64));
+                    mem[target] = mem[target].wrapping_add(value);
                 }
-            };
+            }
+        };
+
+        instr_ptr += 1;
+    }
+
+    output
+        .finish()
+        .map_err(|kind| error(kind, instr_ptr, mem_ptr))
+}
+
+/// Counts a step, failing if `limit` steps have already been taken.
+fn count_step(steps: &mut u64, limit: Option<u64>) -> Result<(), RuntimeErrorKind> {
+    match limit {
+        Some(limit) if *steps == limit => Err(RuntimeErrorKind::StepLimit(limit)),
+        Some(_) => {
+            *steps += 1;
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Position of the cell `offset` cells away from the memory pointer, which is shifted
+/// along with the cells if the tape grows to the left. Returns `None` if the cell is off
+/// the tape under [`TapePolicy::Error`](crate::TapePolicy::Error).
+fn cell_index<C: Cell>(
+    mem: &mut Vec<C>,
+    config: &Config,
+    mem_ptr: &mut usize,
+    offset: isize,
+) -> Option<usize> {
+    if offset == 0 {
+        return Some(*mem_ptr);
+    }
 
-            instr_ptr += 1;
+    let (at, moved) = locate(mem, config.tape_policy, *mem_ptr, offset)?;
+    *mem_ptr = moved;
+    Some(at)
+}
+
+/// Performs `count` consecutive reads into a cell holding `value` and returns what the
+/// cell ends up holding, or `None` if input ran out under [`EofPolicy::Error`].
+pub(crate) fn read_cell<R: Read + ?Sized>(
+    input: &mut R,
+    count: usize,
+    value: u64,
+    config: &Config,
+) -> io::Result<Option<u64>> {
+    let mut value = value;
+    let mut buf = [0];
+
+    for _ in 0..count {
+        match input.read_exact(&mut buf) {
+            // Only the last byte stays
+            Ok(()) => value = buf[0].into(),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                return Ok(match config.eof {
+                    EofPolicy::Zero => Some(0),
+                    EofPolicy::Max => Some(config.cell_width.max()),
+                    EofPolicy::Unchanged => Some(value),
+                    EofPolicy::Error => None,
+                });
+            }
+            Err(err) => return Err(err),
         }
     }
+
+    Ok(Some(value))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        config::{CellWidth, FlushPolicy, OutputEncoding, TapePolicy},
+        parser::Parser,
+    };
+
+    fn parse(src: &str) -> Vec<Cmd> {
+        Parser::from_bytes(src.as_bytes()).parse_all().unwrap()
+    }
+
+    fn run(src: &str, config: Config) -> Result<Vec<u8>, RuntimeError> {
+        interpret(&parse(src), config, b"")
+    }
+
+    /// Runs `cmds` on `input` with one of the engines, so the JIT can be held to the
+    /// same expectations as the interpreter.
+    pub(crate) type Engine = fn(&[Cmd], Config, &[u8]) -> Result<Vec<u8>, RuntimeError>;
+
+    fn interpret(cmds: &[Cmd], config: Config, input: &[u8]) -> Result<Vec<u8>, RuntimeError> {
+        Interpreter::with_config(config)
+            .unwrap()
+            .run_with_input(cmds, input)
+    }
+
+    /// A stream that fails every read, write and flush.
+    pub(crate) struct Broken;
+
+    impl Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("broken"))
+        }
+    }
+
+    impl Write for Broken {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("broken"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::Error::other("broken"))
+        }
+    }
+
+    #[test]
+    fn failed_writes_are_io_errors() {
+        let config = Config {
+            flush: FlushPolicy::Always,
+            ..Config::default()
+        };
+        let err = Interpreter::with_config(config)
+            .unwrap()
+            .run_with_io(&parse("+>."), &mut io::empty(), &mut Broken)
+            .unwrap_err();
+
+        assert!(matches!(err.kind, RuntimeErrorKind::Io(_)));
+        assert_eq!((err.instr_ptr, err.mem_ptr), (2, 1));
+    }
+
+    #[test]
+    fn buffered_writes_fail_once_the_program_stops() {
+        let config = Config {
+            flush: FlushPolicy::Exit,
+            ..Config::default()
+        };
+        let cmds = parse("+.");
+        let err = Interpreter::with_config(config)
+            .unwrap()
+            .run_with_io(&cmds, &mut io::empty(), &mut Broken)
+            .unwrap_err();
+
+        assert!(matches!(err.kind, RuntimeErrorKind::Io(_)));
+        assert_eq!(err.instr_ptr, cmds.len());
+    }
+
+    #[test]
+    fn failed_reads_are_io_errors() {
+        let err = Interpreter::new()
+            .run_with_io(&parse(">,"), &mut Broken, &mut io::sink())
+            .unwrap_err();
+
+        assert!(matches!(err.kind, RuntimeErrorKind::Io(_)));
+        assert_eq!((err.instr_ptr, err.mem_ptr), (1, 1));
+        assert_eq!(
+            std::error::Error::source(&err).unwrap().to_string(),
+            "broken"
+        );
+    }
+
+    #[test]
+    fn unencodable_output_is_rejected() {
+        let config = Config {
+            output: OutputEncoding::Ascii,
+            ..Config::default()
+        };
+        let err = run("+.--.", config).unwrap_err();
+
+        assert!(matches!(err.kind, RuntimeErrorKind::InvalidOutput(255)));
+        assert_eq!(err.instr_ptr, 3);
+    }
+
+    #[test]
+    fn step_limits_stop_endless_loops() {
+        let config = Config {
+            step_limit: Some(10),
+            ..Config::default()
+        };
+        let err = run("+[]", config).unwrap_err();
+
+        assert!(matches!(err.kind, RuntimeErrorKind::StepLimit(10)));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Runtime error at command {} (memory pointer 0): step limit of 10 reached",
+                err.instr_ptr
+            )
+        );
+    }
+
+    #[test]
+    fn step_limits_count_every_command() {
+        let config = |limit| Config {
+            step_limit: Some(limit),
+            ..Config::default()
+        };
+
+        assert_eq!(run("+.+.", config(4)).unwrap(), [1, 2]);
+        assert!(matches!(
+            run("+.+.", config(3)).unwrap_err().kind,
+            RuntimeErrorKind::StepLimit(3)
+        ));
+    }
+
+    #[test]
+    fn moving_off_the_tape_is_an_error() {
+        let config = Config {
+            tape_policy: TapePolicy::Error,
+            tape_len: 2,
+            ..Config::default()
+        };
+
+        let err = run(">.<<", config.clone()).unwrap_err();
+        assert!(matches!(err.kind, RuntimeErrorKind::TapeOverflow));
+        assert_eq!((err.instr_ptr, err.mem_ptr), (2, 1));
+
+        let err = run(">>", config).unwrap_err();
+        assert!(matches!(err.kind, RuntimeErrorKind::TapeOverflow));
+    }
+
+    #[test]
+    fn reading_past_the_end_is_an_error_if_asked() {
+        let config = Config {
+            eof: EofPolicy::Error,
+            ..Config::default()
+        };
+        let err = Interpreter::with_config(config)
+            .unwrap()
+            .run_with_input(&parse(",,"), b"a")
+            .unwrap_err();
+
+        assert!(matches!(err.kind, RuntimeErrorKind::UnexpectedEof));
+        assert_eq!(err.instr_ptr, 0);
+    }
+
+    /// Value of the current cell after adding `amount` to a fresh one, read back through
+    /// ASCII output, which reports values it can't encode.
+    pub(crate) fn cell_after_adding(engine: Engine, amount: isize, cell_width: CellWidth) -> u64 {
+        let config = Config {
+            cell_width,
+            output: OutputEncoding::Ascii,
+            ..Config::default()
+        };
+        let cmds = [Cmd::new(Op::Add, amount), Cmd::new(Op::Out, 1)];
+
+        match engine(&cmds, config, b"") {
+            Ok(output) => output[0].into(),
+            Err(RuntimeError {
+                kind: RuntimeErrorKind::InvalidOutput(value),
+                ..
+            }) => value,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    /// Amounts added to a fresh cell of each width, with the value the cell should hold.
+    pub(crate) const WRAPPING: [(isize, CellWidth, u64); 9] = [
+        (-1, CellWidth::U8, 0xFF),
+        (-1, CellWidth::U16, 0xFFFF),
+        (-1, CellWidth::U32, 0xFFFF_FFFF),
+        (-1, CellWidth::U64, u64::MAX),
+        (0x1_0001, CellWidth::U8, 1),
+        (0x1_0001, CellWidth::U16, 1),
+        (0x1_0001, CellWidth::U32, 0x1_0001),
+        (1 << 32, CellWidth::U32, 0),
+        (1 << 32, CellWidth::U64, 1 << 32),
+    ];
+
+    #[test]
+    fn cells_wrap_at_their_width() {
+        for (amount, cell_width, expected) in WRAPPING {
+            assert_eq!(
+                cell_after_adding(interpret, amount, cell_width),
+                expected,
+                "adding {amount} to a {cell_width:?} cell"
+            );
+        }
+    }
+
+    #[test]
+    fn wide_cells_loop_until_they_wrap_to_zero() {
+        let config = Config {
+            cell_width: CellWidth::U16,
+            ..Config::default()
+        };
+
+        // 256 is zero in an 8-bit cell but not in a 16-bit one
+        let src = format!("{}[>+.<[-]]", "+".repeat(256));
+        assert_eq!(run(&src, config).unwrap(), [1]);
+        assert_eq!(run(&src, Config::default()).unwrap(), []);
+    }
+
+    /// Value of the current cell after reading from `input` into a cell holding 5, read
+    /// back like in [`cell_after_adding`].
+    pub(crate) fn cell_after_reading(
+        engine: Engine,
+        input: &[u8],
+        eof: EofPolicy,
+        cell_width: CellWidth,
+    ) -> Result<u64, RuntimeErrorKind> {
+        let config = Config {
+            cell_width,
+            eof,
+            output: OutputEncoding::Ascii,
+            ..Config::default()
+        };
+        let cmds = [
+            Cmd::new(Op::Add, 5),
+            Cmd::new(Op::In, 1),
+            Cmd::new(Op::Out, 1),
+        ];
+
+        match engine(&cmds, config, input) {
+            Ok(output) => Ok(output[0].into()),
+            Err(RuntimeError {
+                kind: RuntimeErrorKind::InvalidOutput(value),
+                ..
+            }) => Ok(value),
+            Err(err) => Err(err.kind),
+        }
+    }
+
+    /// What reading past the end leaves in a cell holding 5 under each EOF policy, or
+    /// `None` if the read fails.
+    pub(crate) const EOF_VALUES: [(EofPolicy, CellWidth, Option<u64>); 7] = [
+        (EofPolicy::Zero, CellWidth::U16, Some(0)),
+        (EofPolicy::Unchanged, CellWidth::U16, Some(5)),
+        (EofPolicy::Max, CellWidth::U8, Some(0xFF)),
+        (EofPolicy::Max, CellWidth::U16, Some(0xFFFF)),
+        (EofPolicy::Max, CellWidth::U32, Some(0xFFFF_FFFF)),
+        (EofPolicy::Max, CellWidth::U64, Some(u64::MAX)),
+        (EofPolicy::Error, CellWidth::U64, None),
+    ];
+
+    /// Checks that `engine` reads input whatever the EOF policy, and treats the end of
+    /// input as [`EOF_VALUES`] says.
+    pub(crate) fn check_eof_policies(engine: Engine) {
+        for (eof, cell_width, expected) in EOF_VALUES {
+            let read = cell_after_reading(engine, b"A", eof, cell_width);
+            assert!(matches!(read, Ok(65)), "{eof:?} on input: {read:?}");
+
+            match (cell_after_reading(engine, b"", eof, cell_width), expected) {
+                (Ok(value), Some(expected)) => {
+                    assert_eq!(value, expected, "{eof:?} {cell_width:?}")
+                }
+                (Err(RuntimeErrorKind::UnexpectedEof), None) => {}
+                (read, _) => panic!("{eof:?} {cell_width:?} at EOF: {read:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn eof_policies_set_the_documented_values() {
+        check_eof_policies(interpret);
+    }
 }
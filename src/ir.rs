@@ -0,0 +1,210 @@
+//! A line-based text format for command lists, for reading optimizer output and writing
+//! test fixtures.
+//!
+//! Each line holds one command: its index, its operator, its operand and, if non-zero,
+//! its offset after an `@`. Jump operands are the index execution continues at when the
+//! jump is taken. Everything after a `;` is a comment.
+//!
+//! ```text
+//! ; [->+<]
+//!    0  jz 4
+//!    1  add -1
+//!    2  add 1 @1
+//!    3  jnz 1
+//! ```
+//!
+//! When parsing, indices may be left out, as may the operands of `jz` and `jnz`, which
+//! are then worked out from the brackets. `zero` never takes an operand, and jumps never
+//! take an offset.
+
+use std::fmt;
+
+use crate::{
+    bytecode::{offset_in_range, operand_in_range},
+    optimizer::link_jumps,
+    parser::{Cmd, Op},
+};
+
+/// Formats `cmds` one per line, prefixed with their index.
+pub fn print(cmds: &[Cmd]) -> String {
+    cmds.iter()
+        .enumerate()
+        .map(|(instr_ptr, cmd)| format!("{instr_ptr:>4}  {cmd}\n"))
+        .collect()
+}
+
+/// Reads commands in the format written by [`print`].
+pub fn parse(src: &str) -> Result<Vec<Cmd>, IrError> {
+    let mut cmds = Vec::new();
+    // Explicit jump operands, checked once every jump has been linked
+    let mut targets = Vec::new();
+
+    for (line, text) in src.lines().enumerate() {
+        let error = |kind| IrError {
+            kind,
+            line: line + 1,
+        };
+
+        let text = text.split(';').next().unwrap_or_default();
+        let mut words = text.split_whitespace().peekable();
+        if words.peek().is_none() {
+            continue;
+        }
+
+        if let Some(index) = words.peek().and_then(|word| word.parse::<usize>().ok()) {
+            if index != cmds.len() {
+                return Err(error(IrErrorKind::WrongIndex(index)));
+            }
+            words.next();
+        }
+
+        let word = words
+            .next()
+            .ok_or_else(|| error(IrErrorKind::MissingOperator))?;
+        let operator: Op = word
+            .parse()
+            .map_err(|_| error(IrErrorKind::UnknownOperator(word.to_string())))?;
+
+        let is_jump = matches!(operator, Op::JmpZero | Op::JmpNonZero);
+        let mut operand = None;
+        let mut offset = 0;
+        for word in words {
+            let (value, is_offset) = match word.strip_prefix('@') {
+                Some(value) => (value, true),
+                None => (word, false),
+            };
+            let value = value
+                .parse()
+                .map_err(|_| error(IrErrorKind::Unexpected(word.to_string())))?;
+
+            match (is_offset, operand) {
+                // Jumps test the current cell
+                (true, _) if is_jump => {
+                    return Err(error(IrErrorKind::Unexpected(word.to_string())))
+                }
+                (true, _) if !offset_in_range(value) => {
+                    return Err(error(IrErrorKind::OffsetOutOfRange(value)))
+                }
+                (true, _) => offset = value,
+                (false, None) if operator != Op::SetZero => operand = Some(value),
+                (false, _) => return Err(error(IrErrorKind::Unexpected(word.to_string()))),
+            }
+        }
+
+        if is_jump {
+            targets.push((cmds.len(), operand, line + 1));
+        }
+
+        let operand = match operand {
+            Some(operand) => operand,
+            None if is_jump || operator == Op::SetZero => 0,
+            None => return Err(error(IrErrorKind::MissingOperand)),
+        };
+        if !operand_in_range(operator, operand) {
+            return Err(error(IrErrorKind::OperandOutOfRange(operand)));
+        }
+
+        cmds.push(Cmd {
+            operator,
+            operand,
+            offset,
+        });
+    }
+
+    check_brackets(&cmds, &targets)?;
+    link_jumps(&mut cmds);
+
+    for (instr_ptr, target, line) in targets {
+        if target.is_some_and(|target| target != cmds[instr_ptr].operand) {
+            return Err(IrError {
+                kind: IrErrorKind::WrongTarget(cmds[instr_ptr].operand),
+                line,
+            });
+        }
+    }
+
+    Ok(cmds)
+}
+
+/// Reports the first `jz` or `jnz` without a partner.
+fn check_brackets(cmds: &[Cmd], jumps: &[(usize, Option<isize>, usize)]) -> Result<(), IrError> {
+    let mut open = Vec::new();
+
+    for &(instr_ptr, _, line) in jumps {
+        match cmds[instr_ptr].operator {
+            Op::JmpZero => open.push(line),
+            _ if open.pop().is_none() => {
+                return Err(IrError {
+                    kind: IrErrorKind::UnmatchedJump,
+                    line,
+                })
+            }
+            _ => {}
+        }
+    }
+
+    match open.first() {
+        Some(&line) => Err(IrError {
+            kind: IrErrorKind::UnmatchedJump,
+            line,
+        }),
+        None => Ok(()),
+    }
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum IrErrorKind {
+    /// The line has an index, but not an operator.
+    MissingOperator,
+    UnknownOperator(String),
+    /// A command other than a jump or `zero` has no operand.
+    MissingOperand,
+    /// A word that isn't a number, one number too many, or an offset on a jump.
+    Unexpected(String),
+    /// An operand the parser and optimizer could never produce for the command, such as
+    /// a scan that doesn't move or a move further than
+    /// [`MAX_TAPE_LEN`](crate::bytecode::MAX_TAPE_LEN).
+    OperandOutOfRange(isize),
+    /// An offset further than [`MAX_TAPE_LEN`](crate::bytecode::MAX_TAPE_LEN) from the
+    /// memory pointer.
+    OffsetOutOfRange(isize),
+    /// The line starts with an index other than the command's position.
+    WrongIndex(usize),
+    /// A `jz` without a `jnz` or the other way around.
+    UnmatchedJump,
+    /// A jump's operand isn't where its bracket says it should go, which is given.
+    WrongTarget(isize),
+}
+
+/// An error in IR text, along with the 1-based line it is on.
+#[derive(PartialEq, Eq, Debug)]
+pub struct IrError {
+    pub kind: IrErrorKind,
+    pub line: usize,
+}
+
+impl fmt::Display for IrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IR error on line {}: ", self.line)?;
+
+        match &self.kind {
+            IrErrorKind::MissingOperator => write!(f, "missing operator"),
+            IrErrorKind::UnknownOperator(word) => write!(f, "unknown operator `{word}`"),
+            IrErrorKind::MissingOperand => write!(f, "missing operand"),
+            IrErrorKind::Unexpected(word) => write!(f, "unexpected `{word}`"),
+            IrErrorKind::OperandOutOfRange(operand) => {
+                write!(f, "operand {operand} is out of range")
+            }
+            IrErrorKind::OffsetOutOfRange(offset) => write!(f, "offset {offset} is out of range"),
+            IrErrorKind::WrongIndex(index) => {
+                write!(f, "index {index} doesn't match the command's position")
+            }
+            IrErrorKind::UnmatchedJump => write!(f, "jump without a matching jump"),
+            IrErrorKind::WrongTarget(expected) => {
+                write!(f, "jump target should be {expected}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for IrError {}
@@ -1 +1,747 @@
+use std::{
+    io::{self, Read, Write},
+    ptr, slice,
+};
 
+use crate::{
+    config::{CellWidth, Config},
+    interpreter::{read_cell, RuntimeError, RuntimeErrorKind},
+    output::Output,
+    parser::{Cmd, Op},
+    runner::Error,
+    tape::Tape,
+    x86::{Assembler, R12, RDX},
+};
+
+/// Machine code compiled from a list of commands, runnable on x86-64.
+///
+/// Register usage inside the generated code:
+/// - `rbx` holds the base address of the tape
+/// - `r12` holds the memory pointer as an index into the tape
+/// - `r13` holds a pointer to the [`Context`] handed to the callbacks
+/// - `r14` holds the number of steps left when a step limit is configured
+/// - `r15` holds the length of the tape
+pub struct Jit {
+    mem: Tape,
+    config: Config,
+    code: ExecutableBuffer,
+    cmd_count: usize,
+}
+
+impl Jit {
+    pub fn compile(cmds: &[Cmd]) -> Result<Self, Error> {
+        Self::compile_with_config(cmds, Config::default())
+    }
+
+    /// Fails if `config` doesn't pass [`Config::validate`] or the code can't be mapped
+    /// executable.
+    pub fn compile_with_config(cmds: &[Cmd], config: Config) -> Result<Self, Error> {
+        config.validate()?;
+
+        let code = Assembler::assemble(cmds, &config);
+
+        Ok(Self {
+            mem: Tape::new(config.cell_width, config.tape_len),
+            config,
+            code: ExecutableBuffer::new(&code)?,
+            cmd_count: cmds.len(),
+        })
+    }
+
+    /// Runs the compiled program reading from stdin and writing to stdout.
+    pub fn run(&mut self) -> Result<(), RuntimeError> {
+        self.run_with_io(&mut io::stdin(), &mut io::stdout())
+    }
+
+    /// Runs the compiled program with `input` as its input, returning everything it printed.
+    pub fn run_with_input(&mut self, mut input: &[u8]) -> Result<Vec<u8>, RuntimeError> {
+        let mut output = Vec::new();
+        self.run_with_io(&mut input, &mut output)?;
+        Ok(output)
+    }
+
+    /// Runs the program reading from `input` and writing to `output`, flushing `output`
+    /// once it stops.
+    pub fn run_with_io<R: Read, W: Write>(
+        &mut self,
+        input: &mut R,
+        output: &mut W,
+    ) -> Result<(), RuntimeError> {
+        let mut ctx = Context {
+            tape: self.mem.as_mut_ptr(),
+            tape_len: self.mem.len(),
+            mem_ptr: 0,
+            mem: &mut self.mem,
+            input,
+            output: Output::new(output, &self.config),
+            config: &self.config,
+            error: None,
+        };
+
+        // SAFETY: the buffer holds code produced by `Assembler`, which follows the
+        // System V calling convention and only touches the tape it is given.
+        let entry: extern "C" fn(*mut Context) -> u8 =
+            unsafe { std::mem::transmute(self.code.ptr) };
+
+        if entry(&mut ctx) != 0 {
+            return Err(ctx
+                .error
+                .expect("callbacks record an error before aborting"));
+        }
+
+        ctx.output.finish().map_err(|kind| RuntimeError {
+            kind,
+            instr_ptr: self.cmd_count,
+            span: None,
+            mem_ptr: ctx.mem_ptr,
+        })
+    }
+}
+
+/// State shared between the generated code and the callbacks.
+///
+/// The generated code accesses `tape`, `tape_len` and `mem_ptr` at fixed offsets, so
+/// they must stay the first three fields.
+#[repr(C)]
+struct Context<'a> {
+    tape: *mut u8,
+    tape_len: usize,
+    /// Where the memory pointer ended up once the program finished.
+    mem_ptr: usize,
+    mem: &'a mut Tape,
+    input: &'a mut dyn Read,
+    output: Output<&'a mut dyn Write>,
+    config: &'a Config,
+    error: Option<RuntimeError>,
+}
+
+impl Context<'_> {
+    /// Records an error for [`Jit::run_with_io`] and returns the status that aborts the program.
+    fn fail(&mut self, kind: RuntimeErrorKind, instr_ptr: usize, mem_ptr: usize) -> u8 {
+        self.error = Some(RuntimeError {
+            kind,
+            instr_ptr,
+            span: None,
+            mem_ptr,
+        });
+        1
+    }
+}
+
+extern "C" fn jit_out(
+    ctx: *mut Context,
+    value: u64,
+    count: usize,
+    instr_ptr: usize,
+    mem_ptr: usize,
+) -> u8 {
+    // SAFETY: the generated code always passes the context it was entered with.
+    let ctx = unsafe { &mut *ctx };
+
+    match ctx.output.write_cell(value, count) {
+        Ok(()) => 0,
+        Err(kind) => ctx.fail(kind, instr_ptr, mem_ptr),
+    }
+}
+
+extern "C" fn jit_in(
+    ctx: *mut Context,
+    cell: *mut u8,
+    count: usize,
+    instr_ptr: usize,
+    mem_ptr: usize,
+) -> u8 {
+    // SAFETY: the generated code always passes the context it was entered with.
+    let ctx = unsafe { &mut *ctx };
+
+    if let Err(err) = ctx.output.before_read() {
+        return ctx.fail(RuntimeErrorKind::Io(err), instr_ptr, mem_ptr);
+    }
+
+    // SAFETY: the generated code passes a pointer to the current cell, which is
+    // `cell_width` wide.
+    let value = unsafe {
+        match ctx.config.cell_width {
+            CellWidth::U8 => cell.read().into(),
+            CellWidth::U16 => (cell as *mut u16).read().into(),
+            CellWidth::U32 => (cell as *mut u32).read().into(),
+            CellWidth::U64 => (cell as *mut u64).read(),
+        }
+    };
+
+    let value = match read_cell(ctx.input, count, value, ctx.config) {
+        Ok(Some(value)) => value,
+        Ok(None) => return ctx.fail(RuntimeErrorKind::UnexpectedEof, instr_ptr, mem_ptr),
+        Err(err) => return ctx.fail(RuntimeErrorKind::Io(err), instr_ptr, mem_ptr),
+    };
+
+    // SAFETY: as above.
+    unsafe {
+        match ctx.config.cell_width {
+            CellWidth::U8 => cell.write(value as u8),
+            CellWidth::U16 => (cell as *mut u16).write(value as u16),
+            CellWidth::U32 => (cell as *mut u32).write(value as u32),
+            CellWidth::U64 => (cell as *mut u64).write(value),
+        }
+    }
+
+    0
+}
+
+/// Handles the memory pointer leaving the tape, moving it to `to` after a move of `delta`.
+/// Returns the new memory pointer, or -1 if the program must stop.
+extern "C" fn jit_seek(ctx: *mut Context, to: isize, delta: isize, instr_ptr: usize) -> isize {
+    // SAFETY: the generated code always passes the context it was entered with.
+    let ctx = unsafe { &mut *ctx };
+
+    let from = (to - delta) as usize;
+    let Some(mem_ptr) = ctx.mem.seek(ctx.config.tape_policy, from, delta) else {
+        ctx.fail(RuntimeErrorKind::TapeOverflow, instr_ptr, from);
+        return -1;
+    };
+
+    // The tape may have been reallocated
+    ctx.tape = ctx.mem.as_mut_ptr();
+    ctx.tape_len = ctx.mem.len();
+
+    mem_ptr as isize
+}
+
+/// Finds the cell `offset` cells away from `mem_ptr` when it is off the tape, storing the
+/// possibly shifted memory pointer in the context. Returns the cell's position, or -1 if
+/// the program must stop.
+extern "C" fn jit_offset(
+    ctx: *mut Context,
+    mem_ptr: usize,
+    offset: isize,
+    instr_ptr: usize,
+) -> isize {
+    // SAFETY: the generated code always passes the context it was entered with.
+    let ctx = unsafe { &mut *ctx };
+
+    let Some((target, mem_ptr)) = ctx.mem.locate(ctx.config.tape_policy, mem_ptr, offset) else {
+        ctx.fail(RuntimeErrorKind::TapeOverflow, instr_ptr, mem_ptr);
+        return -1;
+    };
+
+    // The tape may have been reallocated
+    ctx.tape = ctx.mem.as_mut_ptr();
+    ctx.tape_len = ctx.mem.len();
+    ctx.mem_ptr = mem_ptr;
+
+    target as isize
+}
+
+extern "C" fn jit_step_limit(ctx: *mut Context, instr_ptr: usize, mem_ptr: usize) -> u8 {
+    // SAFETY: the generated code always passes the context it was entered with.
+    let ctx = unsafe { &mut *ctx };
+
+    let limit = ctx.config.step_limit.unwrap_or_default();
+    ctx.fail(RuntimeErrorKind::StepLimit(limit), instr_ptr, mem_ptr)
+}
+
+/// pop r15; pop r14; pop r13; pop r12; pop rbx; ret
+const EXIT: [u8; 10] = [0x41, 0x5F, 0x41, 0x5E, 0x41, 0x5D, 0x41, 0x5C, 0x5B, 0xC3];
+
+impl Assembler {
+    fn assemble(cmds: &[Cmd], config: &Config) -> Vec<u8> {
+        let mut asm = Self::new(config.cell_width);
+
+        // Start offset of every command, plus one past the end
+        let mut offsets = Vec::with_capacity(cmds.len() + 1);
+        // Positions of rel32 jump operands and the command they target
+        let mut fixups = Vec::new();
+        // Positions of rel32 jump operands into the step limit stubs and the command each
+        // stub belongs to
+        let mut limit_fixups = Vec::new();
+
+        asm.prologue(config.step_limit);
+
+        for (instr_ptr, cmd) in cmds.iter().enumerate() {
+            offsets.push(asm.code.len());
+
+            if config.step_limit.is_some() {
+                limit_fixups.push((asm.count_step(), instr_ptr));
+            }
+
+            match cmd.operator {
+                Op::Add => {
+                    let index = asm.cell(cmd.offset, instr_ptr);
+                    asm.add_cell(index, cmd.operand as u64);
+                }
+                Op::Move => asm.move_ptr(cmd.operand, instr_ptr),
+                Op::Out => {
+                    let index = asm.cell(cmd.offset, instr_ptr);
+                    asm.out(index, cmd.operand as usize, instr_ptr);
+                }
+                Op::In => {
+                    let index = asm.cell(cmd.offset, instr_ptr);
+                    asm.input(index, cmd.operand as usize, instr_ptr);
+                }
+                Op::JmpZero => {
+                    asm.cmp_cell_zero();
+                    fixups.push((asm.jcc(0x84), cmd.operand as usize));
+                }
+                Op::JmpNonZero => {
+                    asm.cmp_cell_zero();
+                    fixups.push((asm.jcc(0x85), cmd.operand as usize));
+                }
+                Op::SetZero => {
+                    let index = asm.cell(cmd.offset, instr_ptr);
+                    asm.set_zero(index);
+                }
+                Op::ScanLeft | Op::ScanRight => {
+                    let delta = match cmd.operator {
+                        Op::ScanLeft => -cmd.operand,
+                        _ => cmd.operand,
+                    };
+                    let count = config.step_limit.is_some();
+                    let limit = asm.scan(delta, instr_ptr, count);
+                    limit_fixups.extend(limit.map(|at| (at, instr_ptr)));
+                }
+                Op::MulAdd => asm.mul_add(cmd.offset, cmd.operand, instr_ptr),
+            }
+        }
+
+        offsets.push(asm.code.len());
+        asm.epilogue();
+
+        for (at, target) in fixups {
+            asm.patch(at, offsets[target]);
+        }
+
+        if !limit_fixups.is_empty() {
+            let mut stubs = Vec::with_capacity(cmds.len());
+            for instr_ptr in 0..cmds.len() {
+                stubs.push(asm.limit_stub(instr_ptr));
+            }
+
+            let handler = asm.code.len();
+            asm.limit_handler();
+
+            for (at, instr_ptr) in limit_fixups {
+                asm.patch(at, stubs[instr_ptr].0);
+            }
+            for (_, at) in stubs {
+                asm.patch(at, handler);
+            }
+        }
+
+        asm.code
+    }
+
+    fn prologue(&mut self, step_limit: Option<u64>) {
+        // push rbx; push r12; push r13; push r14; push r15
+        self.emit(&[0x53, 0x41, 0x54, 0x41, 0x55, 0x41, 0x56, 0x41, 0x57]);
+        // mov r13, rdi
+        self.emit(&[0x49, 0x89, 0xFD]);
+        self.load_tape();
+        // xor r12d, r12d
+        self.emit(&[0x45, 0x31, 0xE4]);
+
+        if let Some(limit) = step_limit {
+            // mov r14, imm64
+            self.emit(&[0x49, 0xBE]);
+            self.emit_u64(limit);
+        }
+    }
+
+    fn epilogue(&mut self) {
+        // mov [r13 + 16], r12
+        self.emit(&[0x4D, 0x89, 0x65, 0x10]);
+        // xor eax, eax
+        self.emit(&[0x31, 0xC0]);
+        self.exit();
+    }
+
+    /// Restores the callee-saved registers and returns whatever is in `al`.
+    fn exit(&mut self) {
+        self.emit(&EXIT);
+    }
+
+    /// Returns the register holding the index of the cell `offset` away from the current
+    /// one, loading it into `rdx` unless `offset` is zero.
+    fn cell(&mut self, offset: isize, instr_ptr: usize) -> u8 {
+        if offset == 0 {
+            return R12;
+        }
+
+        self.address(offset, instr_ptr);
+        RDX
+    }
+
+    /// Moves by `delta` until the current cell is zero. If `count_steps` is set, every
+    /// move counts as a step, and the position of the rel32 operand that jumps to the
+    /// step limit stub is returned.
+    fn scan(&mut self, delta: isize, instr_ptr: usize, count_steps: bool) -> Option<usize> {
+        let top = self.code.len();
+        self.cmp_cell_zero();
+        let done = self.jcc(0x84);
+        let limit = count_steps.then(|| self.count_step());
+        self.move_ptr(delta, instr_ptr);
+        // jmp rel32
+        self.emit(&[0xE9]);
+        let at = self.code.len();
+        self.emit_u32(0);
+        self.patch(at, top);
+        self.patch(done, self.code.len());
+
+        limit
+    }
+
+    /// Adds the current cell times `factor` to the cell `offset` away, if the current cell
+    /// is non-zero.
+    fn mul_add(&mut self, offset: isize, factor: isize, instr_ptr: usize) {
+        self.cmp_cell_zero();
+        let skip = self.jcc(0x84);
+
+        self.address(offset, instr_ptr);
+        self.load_cell(R12);
+        if let Ok(factor) = i32::try_from(factor) {
+            // imul rsi, rsi, imm32
+            self.emit(&[0x48, 0x69, 0xF6]);
+            self.emit_u32(factor as u32);
+        } else {
+            // mov rax, imm64; imul rsi, rax
+            self.emit(&[0x48, 0xB8]);
+            self.emit_u64(factor as u64);
+            self.emit(&[0x48, 0x0F, 0xAF, 0xF0]);
+        }
+        // add [rbx + rdx * width], rsi
+        self.cell_op_at(RDX, 0x00, 0x01, 6);
+
+        self.patch(skip, self.code.len());
+    }
+
+    /// Loads the index of the cell `offset` away from the current one into `rdx`, applying
+    /// the tape policy if it is off the tape.
+    fn address(&mut self, offset: isize, instr_ptr: usize) {
+        // mov rdx, imm64
+        self.emit(&[0x48, 0xBA]);
+        self.emit_u64(offset as u64);
+        // add rdx, r12
+        self.emit(&[0x4C, 0x01, 0xE2]);
+        // cmp rdx, r15; jb past the slow path
+        self.emit(&[0x4C, 0x39, 0xFA, 0x72]);
+        let skip = self.code.len();
+        self.emit(&[0]);
+
+        // mov rdi, r13; mov rsi, r12
+        self.emit(&[0x4C, 0x89, 0xEF, 0x4C, 0x89, 0xE6]);
+        // mov rdx, imm64
+        self.emit(&[0x48, 0xBA]);
+        self.emit_u64(offset as u64);
+        // mov rcx, imm64
+        self.emit(&[0x48, 0xB9]);
+        self.emit_u64(instr_ptr as u64);
+        self.call(jit_offset as *const () as usize);
+        // test rax, rax; jns past the exit sequence
+        self.emit(&[0x48, 0x85, 0xC0, 0x79, 2 + EXIT.len() as u8]);
+        // mov al, 1
+        self.emit(&[0xB0, 0x01]);
+        self.exit();
+        // mov rdx, rax; mov r12, [r13 + 16]
+        self.emit(&[0x48, 0x89, 0xC2, 0x4D, 0x8B, 0x65, 0x10]);
+        self.load_tape();
+
+        self.code[skip] = (self.code.len() - skip - 1) as u8;
+    }
+
+    /// Loads the tape's base address and length out of the context.
+    fn load_tape(&mut self) {
+        // mov rbx, [r13]; mov r15, [r13 + 8]
+        self.emit(&[0x49, 0x8B, 0x5D, 0x00, 0x4D, 0x8B, 0x7D, 0x08]);
+    }
+
+    fn move_ptr(&mut self, delta: isize, instr_ptr: usize) {
+        if delta == 0 {
+            return;
+        }
+
+        // mov rax, imm64
+        self.emit(&[0x48, 0xB8]);
+        self.emit_u64(delta as u64);
+        // add r12, rax
+        self.emit(&[0x49, 0x01, 0xC4]);
+        // cmp r12, r15; jb past the slow path
+        self.emit(&[0x4D, 0x39, 0xFC, 0x72]);
+        let skip = self.code.len();
+        self.emit(&[0]);
+
+        // Off either end of the tape, since a negative index compares as huge
+        // mov rdi, r13; mov rsi, r12
+        self.emit(&[0x4C, 0x89, 0xEF, 0x4C, 0x89, 0xE6]);
+        // mov rdx, imm64
+        self.emit(&[0x48, 0xBA]);
+        self.emit_u64(delta as u64);
+        // mov rcx, imm64
+        self.emit(&[0x48, 0xB9]);
+        self.emit_u64(instr_ptr as u64);
+        self.call(jit_seek as *const () as usize);
+        // test rax, rax; jns past the exit sequence
+        self.emit(&[0x48, 0x85, 0xC0, 0x79, 2 + EXIT.len() as u8]);
+        // mov al, 1
+        self.emit(&[0xB0, 0x01]);
+        self.exit();
+        // mov r12, rax
+        self.emit(&[0x49, 0x89, 0xC4]);
+        self.load_tape();
+
+        self.code[skip] = (self.code.len() - skip - 1) as u8;
+    }
+
+    fn out(&mut self, index: u8, count: usize, instr_ptr: usize) {
+        self.load_cell(index);
+        self.call_with_location(jit_out as *const () as usize, index, count, instr_ptr);
+        self.bail_on_error();
+    }
+
+    fn input(&mut self, index: u8, count: usize, instr_ptr: usize) {
+        // lea rsi, [cell]
+        let scale = self.width.bytes().trailing_zeros() as u8;
+        let (rex_x, sib) = Self::sib(index, scale);
+        self.emit(&[0x48 | rex_x, 0x8D, 0x34, sib]);
+        self.call_with_location(jit_in as *const () as usize, index, count, instr_ptr);
+        self.bail_on_error();
+    }
+
+    /// Calls `addr` with the context, whatever is already in `rsi`, `count`, `instr_ptr`
+    /// and the index of the cell in register `index` as arguments.
+    fn call_with_location(&mut self, addr: usize, index: u8, count: usize, instr_ptr: usize) {
+        // mov r8, index, before rdx is overwritten
+        self.emit(&[0x49 | (index >> 3) << 2, 0x89, 0xC0 | (index & 7) << 3]);
+        // mov rdi, r13
+        self.emit(&[0x4C, 0x89, 0xEF]);
+        // mov rdx, imm64
+        self.emit(&[0x48, 0xBA]);
+        self.emit_u64(count as u64);
+        // mov rcx, imm64
+        self.emit(&[0x48, 0xB9]);
+        self.emit_u64(instr_ptr as u64);
+        self.call(addr);
+    }
+
+    fn call(&mut self, addr: usize) {
+        // mov rax, imm64; call rax
+        self.emit(&[0x48, 0xB8]);
+        self.emit_u64(addr as u64);
+        self.emit(&[0xFF, 0xD0]);
+    }
+
+    /// Decrements the step counter and returns the position of the rel32 operand
+    /// that jumps to the step limit stub once it runs out.
+    fn count_step(&mut self) -> usize {
+        // sub r14, 1
+        self.emit(&[0x49, 0x83, 0xEE, 0x01]);
+        // jb rel32
+        self.jcc(0x82)
+    }
+
+    /// Emits a stub that loads `instr_ptr` and jumps to the step limit handler.
+    /// Returns the stub's offset and the position of its rel32 jump operand.
+    fn limit_stub(&mut self, instr_ptr: usize) -> (usize, usize) {
+        let start = self.code.len();
+        // mov rsi, imm64
+        self.emit(&[0x48, 0xBE]);
+        self.emit_u64(instr_ptr as u64);
+        // jmp rel32
+        self.emit(&[0xE9]);
+        let at = self.code.len();
+        self.emit_u32(0);
+        (start, at)
+    }
+
+    fn limit_handler(&mut self) {
+        // mov rdi, r13; mov rdx, r12
+        self.emit(&[0x4C, 0x89, 0xEF, 0x4C, 0x89, 0xE2]);
+        self.call(jit_step_limit as *const () as usize);
+        self.exit();
+    }
+
+    /// Returns early with the callback's status if it was non-zero.
+    fn bail_on_error(&mut self) {
+        // test al, al; jz past the exit sequence
+        self.emit(&[0x84, 0xC0, 0x74, EXIT.len() as u8]);
+        self.exit();
+    }
+}
+
+/// An `mmap`ed region holding read-only, executable machine code.
+struct ExecutableBuffer {
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl ExecutableBuffer {
+    fn new(code: &[u8]) -> io::Result<Self> {
+        let len = code.len().max(1);
+
+        // SAFETY: anonymous private mapping, checked for failure below.
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        let buffer = Self {
+            ptr: ptr as *mut u8,
+            len,
+        };
+
+        // SAFETY: the mapping is at least `code.len()` bytes long and writable.
+        unsafe { slice::from_raw_parts_mut(buffer.ptr, code.len()) }.copy_from_slice(code);
+
+        // SAFETY: `ptr` and `len` describe the mapping created above.
+        if unsafe { libc::mprotect(ptr, len, libc::PROT_READ | libc::PROT_EXEC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(buffer)
+    }
+}
+
+impl Drop for ExecutableBuffer {
+    fn drop(&mut self) {
+        // SAFETY: `ptr` and `len` describe a mapping owned by this buffer.
+        unsafe {
+            libc::munmap(self.ptr as *mut libc::c_void, self.len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::{EofPolicy, FlushPolicy, OutputEncoding, TapePolicy},
+        interpreter::{
+            tests::{cell_after_adding, check_eof_policies, Broken, WRAPPING},
+            Interpreter,
+        },
+        parser::Parser,
+    };
+
+    /// Runs `cmds` on both the JIT and the interpreter, checking they print the same and
+    /// fail the same way.
+    fn run_both(cmds: &[Cmd], config: Config, input: &[u8]) -> Result<Vec<u8>, RuntimeError> {
+        let expected = Interpreter::with_config(config.clone())
+            .unwrap()
+            .run_with_input(cmds, input);
+        let result = Jit::compile_with_config(cmds, config)
+            .unwrap()
+            .run_with_input(input);
+
+        match (&result, &expected) {
+            (Ok(output), Ok(expected)) => assert_eq!(output, expected),
+            (Err(err), Err(expected)) => {
+                assert_eq!(err.to_string(), expected.to_string());
+                assert_eq!(
+                    (err.instr_ptr, err.mem_ptr),
+                    (expected.instr_ptr, expected.mem_ptr)
+                );
+            }
+            _ => panic!("the JIT returned {result:?} but the interpreter {expected:?}"),
+        }
+        result
+    }
+
+    /// Like [`run_both`] for a program that must fail.
+    fn fail(src: &str, config: Config) -> RuntimeError {
+        let cmds = Parser::from_bytes(src.as_bytes()).parse_all().unwrap();
+        run_both(&cmds, config, b"").unwrap_err()
+    }
+
+    #[test]
+    fn step_limits_stop_endless_loops() {
+        let config = Config {
+            step_limit: Some(10),
+            ..Config::default()
+        };
+        let err = fail("+[]", config);
+
+        assert!(matches!(err.kind, RuntimeErrorKind::StepLimit(10)));
+    }
+
+    #[test]
+    fn moving_off_the_tape_is_an_error() {
+        let config = Config {
+            tape_policy: TapePolicy::Error,
+            tape_len: 2,
+            ..Config::default()
+        };
+
+        assert!(matches!(
+            fail(">.<<", config.clone()).kind,
+            RuntimeErrorKind::TapeOverflow
+        ));
+        assert!(matches!(
+            fail("+[>+]", config).kind,
+            RuntimeErrorKind::TapeOverflow
+        ));
+    }
+
+    #[test]
+    fn unencodable_output_is_rejected() {
+        let config = Config {
+            output: OutputEncoding::Ascii,
+            cell_width: CellWidth::U16,
+            ..Config::default()
+        };
+        let err = fail("+.--.", config);
+
+        assert!(matches!(err.kind, RuntimeErrorKind::InvalidOutput(65535)));
+    }
+
+    #[test]
+    fn reading_past_the_end_is_an_error_if_asked() {
+        let config = Config {
+            eof: EofPolicy::Error,
+            ..Config::default()
+        };
+
+        assert!(matches!(
+            fail(">,", config).kind,
+            RuntimeErrorKind::UnexpectedEof
+        ));
+    }
+
+    #[test]
+    fn failed_writes_are_io_errors() {
+        let config = Config {
+            flush: FlushPolicy::Always,
+            ..Config::default()
+        };
+        let cmds = Parser::from_bytes(b"+>.").parse_all().unwrap();
+        let err = Jit::compile_with_config(&cmds, config)
+            .unwrap()
+            .run_with_io(&mut io::empty(), &mut Broken)
+            .unwrap_err();
+
+        assert!(matches!(err.kind, RuntimeErrorKind::Io(_)));
+        assert_eq!((err.instr_ptr, err.mem_ptr), (2, 1));
+    }
+
+    #[test]
+    fn cells_wrap_at_their_width() {
+        for (amount, cell_width, expected) in WRAPPING {
+            assert_eq!(
+                cell_after_adding(run_both, amount, cell_width),
+                expected,
+                "adding {amount} to a {cell_width:?} cell"
+            );
+        }
+    }
+
+    #[test]
+    fn eof_policies_set_the_documented_values() {
+        check_eof_policies(run_both);
+    }
+}
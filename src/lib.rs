@@ -0,0 +1,38 @@
+//! A Brainfuck parser, optimizer, interpreter and x86-64 JIT.
+//!
+//! The quickest way to run a program is through [`RunnerBuilder`]:
+//!
+//! ```no_run
+//! use bf_interpreter::{Backend, RunnerBuilder};
+//!
+//! let mut runner = RunnerBuilder::from_file("tests/hello_world.bf".as_ref())?
+//!     .backend(Backend::Interpreter)
+//!     .build()?;
+//!
+//! runner.run()?;
+//! # Ok::<(), bf_interpreter::Error>(())
+//! ```
+
+pub mod ast;
+pub mod bytecode;
+pub mod codegen;
+mod config;
+pub mod interpreter;
+pub mod ir;
+#[cfg(all(target_arch = "x86_64", unix))]
+pub mod jit;
+pub mod optimizer;
+mod output;
+pub mod parser;
+mod runner;
+mod tape;
+mod x86;
+
+pub use ast::Node;
+pub use config::{
+    CellWidth, Config, ConfigError, EofPolicy, FlushPolicy, OutputEncoding, TapePolicy,
+};
+pub use interpreter::{Interpreter, RuntimeError, RuntimeErrorKind};
+pub use optimizer::{optimize, OptLevel, PassManager};
+pub use parser::{Cmd, Op, ParseError, Parser, Span};
+pub use runner::{Backend, Error, Runner, RunnerBuilder};
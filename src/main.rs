@@ -1,23 +1,385 @@
-use std::{io, path};
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    process,
+    str::FromStr,
+};
 
-use interpreter::Interpreter;
-use parser::Parser;
+use bf_interpreter::{
+    bytecode,
+    codegen::{self, asm},
+    ir,
+    optimizer::PASSES,
+    Backend, Config, Error, OptLevel, ParseError, Parser, PassManager, Runner, RunnerBuilder,
+    RuntimeError, Span,
+};
+use console::style;
 
-mod interpreter;
-mod jit;
-mod parser;
+const USAGE: &str = "usage: bf-interpreter [build|compile [-o <output>] | run] [--jit] [-O0|-O1|-O2|-O3] [--enable-pass <pass>] \
+                     [--disable-pass <pass>] [--dump-ir] [--list-passes] [--emit=ir|c|asm|llvm|rust|wasm|wat] \
+                     [--asm-syntax att|intel] [--step-limit <n>] \
+                     [--tape-len <n>] [--tape-policy wrap|error|grow] [--cell-width 8|16|32|64] \
+                     [--eof zero|max|unchanged|error] [--output raw|ascii|latin1|utf8] \
+                     [--flush always|newline|read|exit (default newline)] <file>";
 
-fn main() -> io::Result<()> {
-    let args: Vec<_> = std::env::args().collect();
-    let input = &args[1];
+/// What to do with the program instead of running it from source, given as the first
+/// argument.
+#[derive(Clone, Copy, PartialEq)]
+enum Subcommand {
+    /// Write an executable.
+    Build,
+    /// Write bytecode.
+    Compile,
+    /// Run bytecode written by `compile`.
+    Run,
+}
+
+impl FromStr for Subcommand {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "build" => Ok(Subcommand::Build),
+            "compile" => Ok(Subcommand::Compile),
+            "run" => Ok(Subcommand::Run),
+            _ => Err(format!(
+                "unknown subcommand `{s}`, expected build, compile or run"
+            )),
+        }
+    }
+}
+
+/// What to print instead of running the program.
+#[derive(Clone, Copy)]
+enum Emit {
+    Ir,
+    C,
+    Asm,
+    Llvm,
+    Rust,
+    Wasm,
+    Wat,
+}
+
+impl FromStr for Emit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ir" => Ok(Emit::Ir),
+            "c" => Ok(Emit::C),
+            "asm" => Ok(Emit::Asm),
+            "llvm" => Ok(Emit::Llvm),
+            "rust" => Ok(Emit::Rust),
+            "wasm" => Ok(Emit::Wasm),
+            "wat" => Ok(Emit::Wat),
+            _ => Err(format!(
+                "unknown output kind `{s}`, expected ir, c, asm, llvm, rust, wasm or wat"
+            )),
+        }
+    }
+}
+
+fn main() {
+    if let Err(err) = run() {
+        eprintln!("{err}");
+        process::exit(1);
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("{USAGE}");
+    process::exit(2);
+}
+
+/// Parses the value following a flag, bailing out with the usage if it is missing or invalid.
+fn value<T: FromStr>(arg: Option<String>) -> T {
+    arg.and_then(|arg| arg.parse().ok())
+        .unwrap_or_else(|| usage())
+}
 
-    let cmds = Parser::from_file(path::Path::new(input))?
-        .parse_all()
-        .unwrap();
+fn run() -> Result<(), Error> {
+    let mut backend = Backend::Interpreter;
+    let mut level = OptLevel::default();
+    // Pass names to enable or disable on top of the level, in order
+    let mut toggles = Vec::new();
+    let mut dump_ir = false;
+    let mut emit = None;
+    let mut syntax = asm::Syntax::default();
+    let mut config = Config::default();
+    let mut input = None;
+    // Where `build` writes the executable, or `compile` the bytecode
+    let mut output = None;
 
-    let mut interpreter = Interpreter::new();
+    let mut args = std::env::args().skip(1).peekable();
+    let subcommand = args.peek().and_then(|arg| arg.parse().ok());
+    if subcommand.is_some() {
+        args.next();
+    }
+    let writes = matches!(subcommand, Some(Subcommand::Build | Subcommand::Compile));
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" if writes => output = Some(value::<String>(args.next())),
+            "-o" => {
+                eprintln!("-o only applies to build and compile");
+                process::exit(2);
+            }
+            #[cfg(all(target_arch = "x86_64", unix))]
+            "--jit" => backend = Backend::Jit,
+            #[cfg(not(all(target_arch = "x86_64", unix)))]
+            "--jit" => {
+                eprintln!("--jit is only supported on x86-64 Unix");
+                process::exit(2);
+            }
+            "--enable-pass" => toggles.push((value::<String>(args.next()), true)),
+            "--disable-pass" => toggles.push((value::<String>(args.next()), false)),
+            "--dump-ir" => dump_ir = true,
+            "--list-passes" => {
+                for pass in PASSES {
+                    println!(
+                        "{:<12} -O{:<3} {}",
+                        pass.name, pass.level as u8, pass.description
+                    );
+                }
+                return Ok(());
+            }
+            _ if arg.starts_with("--emit=") => emit = Some(value(Some(arg[7..].to_string()))),
+            _ if arg.starts_with("-O") => level = value(Some(arg[2..].to_string())),
+            "--asm-syntax" => syntax = value(args.next()),
+            "--step-limit" => config.step_limit = Some(value(args.next())),
+            "--tape-len" => config.tape_len = value(args.next()),
+            "--tape-policy" => config.tape_policy = value(args.next()),
+            "--cell-width" => config.cell_width = value(args.next()),
+            "--eof" => config.eof = value(args.next()),
+            "--output" => config.output = value(args.next()),
+            "--flush" => config.flush = value(args.next()),
+            _ if arg.starts_with("--") || input.is_some() => usage(),
+            _ => input = Some(arg),
+        }
+    }
+    let Some(input) = input else { usage() };
+    if writes && emit.is_some() {
+        eprintln!("--emit can't be combined with build or compile");
+        process::exit(2);
+    }
+    if let Err(err) = config.validate() {
+        eprintln!("{err}");
+        process::exit(2);
+    }
+    // Bytecode with a longer tape would be refused when it's run
+    if subcommand == Some(Subcommand::Compile) && config.tape_len > bytecode::MAX_TAPE_LEN {
+        eprintln!(
+            "compiled programs can't have a tape longer than {} cells",
+            bytecode::MAX_TAPE_LEN
+        );
+        process::exit(2);
+    }
 
-    interpreter.run_all(cmds);
+    let mut passes = PassManager::new(level);
+    for (name, enabled) in toggles {
+        let toggled = match enabled {
+            true => passes.enable(&name),
+            false => passes.disable(&name),
+        };
+        if let Err(err) = toggled {
+            eprintln!("{err}");
+            process::exit(2);
+        }
+    }
+    if dump_ir {
+        passes.after_pass(|name, cmds| {
+            eprint!("; after {name}\n{}", ir::print(cmds));
+        });
+    }
 
+    let src = fs::read(&input)?;
+    let runner = match subcommand {
+        Some(Subcommand::Run) => Runner::from_bytecode(&src, backend, config),
+        _ => RunnerBuilder::from_bytes(&src)
+            .backend(backend)
+            .passes(passes)
+            .config(config)
+            .build(),
+    };
+
+    match runner {
+        Ok(runner) if subcommand == Some(Subcommand::Compile) => {
+            let output = output.unwrap_or_else(|| {
+                Path::new(&input)
+                    .with_extension("bfc")
+                    .display()
+                    .to_string()
+            });
+            fs::write(output, bytecode::encode(runner.cmds(), runner.config()))?;
+            Ok(())
+        }
+        Ok(runner) if subcommand == Some(Subcommand::Build) => {
+            let output = output
+                .unwrap_or_else(|| Path::new(&input).with_extension("").display().to_string());
+            write_executable(&output, &codegen::elf::emit(runner.cmds(), runner.config()))?;
+            Ok(())
+        }
+        Ok(mut runner) => match emit {
+            Some(emit) => {
+                match emit {
+                    Emit::Ir => print!("{}", ir::print(runner.cmds())),
+                    Emit::C => print!("{}", codegen::c::emit(runner.cmds(), runner.config())),
+                    Emit::Asm => {
+                        // Bytecode has no source to point back at
+                        let spans = Some(runner.spans()).filter(|spans| !spans.is_empty());
+                        let asm = asm::emit(runner.cmds(), spans, runner.config(), syntax);
+                        print!("{asm}");
+                    }
+                    Emit::Llvm => print!("{}", codegen::llvm::emit(runner.cmds(), runner.config())),
+                    Emit::Rust => print!("{}", codegen::rust::emit(runner.cmds(), runner.config())),
+                    Emit::Wasm => io::stdout()
+                        .write_all(&codegen::wasm::emit(runner.cmds(), runner.config()))?,
+                    Emit::Wat => print!(
+                        "{}",
+                        codegen::wasm::emit_wat(runner.cmds(), runner.config())
+                    ),
+                }
+                Ok(())
+            }
+            None => match runner.run() {
+                Err(Error::Runtime(
+                    err @ RuntimeError {
+                        span: Some(span), ..
+                    },
+                )) => {
+                    eprint!("{}", render_runtime_error(&input, &src, span, &err));
+                    process::exit(1);
+                }
+                result => result,
+            },
+        },
+        Err(Error::Parse(err)) => {
+            // Re-parse to report every unmatched bracket rather than just the first
+            let errors = Parser::from_bytes(&src)
+                .parse_all_recovering()
+                .err()
+                .unwrap_or_else(|| vec![err]);
+            for err in &errors {
+                eprint!("{}", render_parse_error(&input, &src, err));
+            }
+            process::exit(1);
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Writes `code` to `path` and marks it executable.
+fn write_executable(path: &str, code: &[u8]) -> io::Result<()> {
+    fs::write(path, code)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
     Ok(())
 }
+
+/// Formats `err` with the offending source line and a caret under the bracket.
+fn render_parse_error(path: &str, src: &[u8], err: &ParseError) -> String {
+    let message = match std::error::Error::source(err) {
+        Some(source) => format!("{}: {source}", err.message()),
+        None => err.message().to_string(),
+    };
+    render_diagnostic(path, src, err.span(), &message)
+}
+
+/// Formats `err` with the source line of the failing command and a caret under it.
+fn render_runtime_error(path: &str, src: &[u8], span: Span, err: &RuntimeError) -> String {
+    let message = format!("{} (memory pointer {})", err.kind, err.mem_ptr);
+    render_diagnostic(path, src, span, &message)
+}
+
+fn render_diagnostic(path: &str, src: &[u8], span: Span, message: &str) -> String {
+    let line = src
+        .split(|&byte| byte == b'\n')
+        .nth(span.line - 1)
+        .unwrap_or_default();
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim_end_matches('\r');
+
+    // Keep tabs so the caret lines up with the source however wide they render
+    let indent: String = line
+        .chars()
+        .take(span.column - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let gutter = " ".repeat(span.line.to_string().len());
+
+    format!(
+        "{}: {}\n{gutter}{} {path}:{span}\n{gutter} {}\n{} {} {line}\n{gutter} {} {indent}{}\n",
+        style("error").red().bold().for_stderr(),
+        style(message).bold().for_stderr(),
+        style("-->").blue().bold().for_stderr(),
+        style("|").blue().bold().for_stderr(),
+        style(span.line).blue().bold().for_stderr(),
+        style("|").blue().bold().for_stderr(),
+        style("|").blue().bold().for_stderr(),
+        style("^").red().bold().for_stderr(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use bf_interpreter::{ParseError, Parser, Span};
+
+    use super::render_parse_error;
+
+    /// Renders the first error in `src` without colors.
+    fn render(src: &[u8]) -> String {
+        let err = Parser::from_bytes(src).parse_all().unwrap_err();
+        console::strip_ansi_codes(&render_parse_error("prog.bf", src, &err)).into_owned()
+    }
+
+    #[test]
+    fn caret_points_at_the_bracket() {
+        assert_eq!(
+            render(b"+\n+[-"),
+            "error: unclosed bracket\n --> prog.bf:2:2\n  |\n2 | +[-\n  |  ^\n"
+        );
+        assert_eq!(
+            render(b"+]"),
+            "error: unopened bracket\n --> prog.bf:1:2\n  |\n1 | +]\n  |  ^\n"
+        );
+    }
+
+    #[test]
+    fn caret_keeps_tabs_and_counts_characters() {
+        assert_eq!(
+            render(b"\t\xC3\xA9 ]"),
+            "error: unopened bracket\n --> prog.bf:1:4\n  |\n1 | \t\u{e9} ]\n  | \t  ^\n"
+        );
+    }
+
+    #[test]
+    fn carriage_returns_are_not_printed() {
+        assert_eq!(
+            render(b"[\r\n+"),
+            "error: unclosed bracket\n --> prog.bf:1:1\n  |\n1 | [\n  | ^\n"
+        );
+    }
+
+    #[test]
+    fn gutter_fits_the_line_number() {
+        let src = [b"\n".repeat(9), b"]".to_vec()].concat();
+
+        assert!(render(&src).ends_with("   |\n10 | ]\n   | ^\n"));
+    }
+
+    #[test]
+    fn read_errors_include_their_cause() {
+        let err = ParseError::Io(Span::default(), io::Error::other("disk on fire"));
+        let rendered = render_parse_error("prog.bf", b"", &err);
+
+        assert!(console::strip_ansi_codes(&rendered)
+            .starts_with("error: failed to read the source: disk on fire\n"));
+    }
+}
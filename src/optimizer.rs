@@ -0,0 +1,403 @@
+//! Rewrites parsed commands into faster equivalents.
+//!
+//! Each rewrite is a [`Pass`] over the command list. A [`PassManager`] runs the ones
+//! enabled by an [`OptLevel`], plus or minus any enabled or disabled by name.
+
+use std::{fmt, str::FromStr};
+
+use crate::{
+    ast::{lift_spanned, lower_spanned, SpannedNode},
+    parser::{Cmd, Op, Span},
+};
+
+/// Commands paired with where they start in the source.
+pub type SpannedCmds = Vec<(Cmd, Span)>;
+
+/// A named rewrite of the command list. Every pass keeps the program's behaviour, apart
+/// from how many steps it takes.
+pub struct Pass {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Rewrites commands paired with where they start in the source. Commands a pass
+    /// combines or replaces take the span of the first source command involved.
+    pub run: fn(SpannedCmds) -> SpannedCmds,
+    /// The lowest level the pass is enabled at.
+    pub level: OptLevel,
+}
+
+/// Every pass, in the order they run.
+pub const PASSES: &[Pass] = &[
+    Pass {
+        name: "loops",
+        description: "rewrite clear, scan and multiply loops",
+        run: rewrite_loops,
+        level: OptLevel::O1,
+    },
+    Pass {
+        name: "dead-loops",
+        description: "remove loops that start on a cell known to be zero",
+        run: remove_dead_loops,
+        level: OptLevel::O3,
+    },
+    Pass {
+        name: "offsets",
+        description: "fold pointer moves into the offsets of later commands",
+        run: defer_moves,
+        level: OptLevel::O2,
+    },
+];
+
+/// Preset selections of [`PASSES`], like a compiler's `-O` flags.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Default)]
+pub enum OptLevel {
+    /// Run the program exactly as parsed.
+    O0,
+    O1,
+    #[default]
+    O2,
+    O3,
+}
+
+impl FromStr for OptLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "0" => Ok(OptLevel::O0),
+            "1" => Ok(OptLevel::O1),
+            "2" => Ok(OptLevel::O2),
+            "3" => Ok(OptLevel::O3),
+            _ => Err(format!(
+                "unknown optimization level `{s}`, expected 0, 1, 2 or 3"
+            )),
+        }
+    }
+}
+
+/// Called after every pass with its name and output.
+type PassHook = Box<dyn FnMut(&str, &[Cmd])>;
+
+/// Runs a selection of [`PASSES`] in order.
+pub struct PassManager {
+    /// Whether each entry of [`PASSES`] runs.
+    enabled: Vec<bool>,
+    after_pass: Option<PassHook>,
+}
+
+impl PassManager {
+    pub fn new(level: OptLevel) -> Self {
+        Self {
+            enabled: PASSES.iter().map(|pass| pass.level <= level).collect(),
+            after_pass: None,
+        }
+    }
+
+    pub fn enable(&mut self, name: &str) -> Result<(), UnknownPass> {
+        self.set_enabled(name, true)
+    }
+
+    pub fn disable(&mut self, name: &str) -> Result<(), UnknownPass> {
+        self.set_enabled(name, false)
+    }
+
+    fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), UnknownPass> {
+        let index = PASSES
+            .iter()
+            .position(|pass| pass.name == name)
+            .ok_or_else(|| UnknownPass(name.to_string()))?;
+        self.enabled[index] = enabled;
+        Ok(())
+    }
+
+    /// The passes that will run, in order.
+    pub fn passes(&self) -> impl Iterator<Item = &'static Pass> + '_ {
+        PASSES
+            .iter()
+            .zip(&self.enabled)
+            .filter_map(|(pass, &enabled)| enabled.then_some(pass))
+    }
+
+    /// Calls `hook` with the name of each pass and the commands it produced, starting with
+    /// `parse` and the commands as given, e.g. to dump the IR while bisecting a miscompile.
+    pub fn after_pass(&mut self, hook: impl FnMut(&str, &[Cmd]) + 'static) {
+        self.after_pass = Some(Box::new(hook));
+    }
+
+    pub fn run(&mut self, cmds: Vec<Cmd>) -> Vec<Cmd> {
+        let spans = vec![Span::default(); cmds.len()];
+        self.run_with_spans(cmds, spans).0
+    }
+
+    /// [`run`](Self::run), also returning where each resulting command came from, given
+    /// the spans [`Parser::parse_all_with_spans`](crate::Parser::parse_all_with_spans)
+    /// returns alongside `cmds`.
+    pub fn run_with_spans(&mut self, cmds: Vec<Cmd>, spans: Vec<Span>) -> (Vec<Cmd>, Vec<Span>) {
+        assert_eq!(cmds.len(), spans.len(), "every command has a span");
+        let mut spanned: Vec<_> = cmds.into_iter().zip(spans).collect();
+
+        call_hook(&mut self.after_pass, "parse", &spanned);
+        for (pass, &enabled) in PASSES.iter().zip(&self.enabled) {
+            if !enabled {
+                continue;
+            }
+
+            spanned = (pass.run)(spanned);
+            call_hook(&mut self.after_pass, pass.name, &spanned);
+        }
+
+        spanned.into_iter().unzip()
+    }
+}
+
+fn call_hook(hook: &mut Option<PassHook>, name: &str, spanned: &[(Cmd, Span)]) {
+    if let Some(hook) = hook {
+        let cmds: Vec<_> = spanned.iter().map(|&(cmd, _)| cmd).collect();
+        hook(name, &cmds);
+    }
+}
+
+impl Default for PassManager {
+    fn default() -> Self {
+        Self::new(OptLevel::default())
+    }
+}
+
+/// A pass name that isn't in [`PASSES`].
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct UnknownPass(pub String);
+
+impl fmt::Display for UnknownPass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown pass `{}`, expected one of ", self.0)?;
+        for (i, pass) in PASSES.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(pass.name)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for UnknownPass {}
+
+/// Runs the passes of the default [`OptLevel`] over `cmds`.
+pub fn optimize(cmds: Vec<Cmd>) -> Vec<Cmd> {
+    PassManager::default().run(cmds)
+}
+
+/// Replaces clear loops like `[-]` with [`Op::SetZero`], scan loops like `[>]` with
+/// [`Op::ScanRight`] and [`Op::ScanLeft`], and copy/multiply loops like `[->+<]` with a
+/// series of [`Op::MulAdd`] followed by [`Op::SetZero`].
+///
+/// Only innermost loops are rewritten, and only ones made of commands straight from the
+/// parser.
+///
+/// The replacement commands take the span of the loop's `[`.
+pub fn rewrite_loops(cmds: SpannedCmds) -> SpannedCmds {
+    lower_spanned(&rewrite_loop_nodes(lift_spanned(cmds)))
+}
+
+fn rewrite_loop_nodes(nodes: Vec<SpannedNode>) -> Vec<SpannedNode> {
+    let mut rewritten = Vec::with_capacity(nodes.len());
+
+    for node in nodes {
+        let SpannedNode::Loop { open, body, close } = node else {
+            rewritten.push(node);
+            continue;
+        };
+
+        let cmds: Option<Vec<Cmd>> = body
+            .iter()
+            .map(|node| match node {
+                SpannedNode::Cmd(cmd, _) => Some(*cmd),
+                SpannedNode::Loop { .. } => None,
+            })
+            .collect();
+
+        match cmds.as_deref().and_then(rewrite_loop) {
+            Some(replacement) => rewritten.extend(
+                replacement
+                    .into_iter()
+                    .map(|cmd| SpannedNode::Cmd(cmd, open)),
+            ),
+            None => rewritten.push(SpannedNode::Loop {
+                open,
+                body: rewrite_loop_nodes(body),
+                close,
+            }),
+        }
+    }
+
+    rewritten
+}
+
+/// Removes loops that can never run because they start right after another loop, a clear
+/// or a scan, or at the very start of the program, where the current cell is always zero.
+pub fn remove_dead_loops(cmds: SpannedCmds) -> SpannedCmds {
+    lower_spanned(&remove_dead_loop_nodes(lift_spanned(cmds), true))
+}
+
+/// `zero` is whether the current cell is known to be zero before the first node.
+fn remove_dead_loop_nodes(nodes: Vec<SpannedNode>, mut zero: bool) -> Vec<SpannedNode> {
+    let mut live = Vec::with_capacity(nodes.len());
+
+    for node in nodes {
+        match node {
+            SpannedNode::Loop { .. } if zero => {}
+            SpannedNode::Loop { open, body, close } => {
+                // The body is only entered on a non-zero cell, and only left on a zero one
+                live.push(SpannedNode::Loop {
+                    open,
+                    body: remove_dead_loop_nodes(body, false),
+                    close,
+                });
+                zero = true;
+            }
+            SpannedNode::Cmd(cmd, _) => {
+                zero = match cmd.operator {
+                    Op::ScanLeft | Op::ScanRight => true,
+                    Op::SetZero => cmd.offset == 0,
+                    _ => false,
+                };
+                live.push(node);
+            }
+        }
+    }
+
+    live
+}
+
+/// Folds pointer moves into the offsets of the commands that follow them, so `>+>++<<-`
+/// becomes three adds at offsets 1, 2 and 0 with no moves at all.
+///
+/// The pointer only actually moves right before loops, scans and [`Op::MulAdd`], which
+/// work on the current cell, and at the end of the program. Each of those moves takes the
+/// span of the first move folded into it.
+pub fn defer_moves(cmds: SpannedCmds) -> SpannedCmds {
+    let mut deferred = Vec::with_capacity(cmds.len());
+    let mut spans = Vec::with_capacity(cmds.len());
+    // The move still to be made, and where the first move making it up starts
+    let mut pending = None;
+
+    for (cmd, span) in cmds {
+        match cmd.operator {
+            Op::Move => {
+                let (offset, _) = pending.get_or_insert((0, span));
+                *offset += cmd.operand;
+            }
+            Op::Add | Op::Out | Op::In | Op::SetZero => {
+                let offset = pending.map_or(0, |(offset, _)| offset);
+                deferred.push(Cmd {
+                    offset: cmd.offset + offset,
+                    ..cmd
+                });
+                spans.push(span);
+            }
+            Op::JmpZero | Op::JmpNonZero | Op::ScanLeft | Op::ScanRight | Op::MulAdd => {
+                flush_move(&mut deferred, &mut spans, &mut pending);
+                deferred.push(cmd);
+                spans.push(span);
+            }
+        }
+    }
+    flush_move(&mut deferred, &mut spans, &mut pending);
+
+    link_jumps(&mut deferred);
+    deferred.into_iter().zip(spans).collect()
+}
+
+fn flush_move(cmds: &mut Vec<Cmd>, spans: &mut Vec<Span>, pending: &mut Option<(isize, Span)>) {
+    if let Some((offset, span)) = pending.take() {
+        if offset != 0 {
+            cmds.push(Cmd::new(Op::Move, offset));
+            spans.push(span);
+        }
+    }
+}
+
+/// Returns the commands that replace a loop with the given body, if it is a known idiom.
+fn rewrite_loop(body: &[Cmd]) -> Option<Vec<Cmd>> {
+    if body.iter().any(|cmd| cmd.offset != 0) {
+        return None;
+    }
+
+    match body {
+        // Adding an odd number always reaches zero eventually
+        [cmd] if cmd.operator == Op::Add && cmd.operand % 2 != 0 => {
+            Some(vec![Cmd::new(Op::SetZero, 0)])
+        }
+        [cmd] if cmd.operator == Op::Move && cmd.operand < 0 => {
+            Some(vec![Cmd::new(Op::ScanLeft, -cmd.operand)])
+        }
+        [cmd] if cmd.operator == Op::Move => Some(vec![Cmd::new(Op::ScanRight, cmd.operand)]),
+        _ => rewrite_mul_loop(body),
+    }
+}
+
+/// Rewrites loops that only add to cells around the current one, return to where they
+/// started and step the current cell down or up by one.
+fn rewrite_mul_loop(body: &[Cmd]) -> Option<Vec<Cmd>> {
+    let mut offset = 0;
+    // Net change of every touched cell, in the order they were first touched
+    let mut deltas: Vec<(isize, isize)> = Vec::new();
+
+    for cmd in body {
+        let delta = match cmd.operator {
+            Op::Add => cmd.operand,
+            Op::Move => {
+                offset += cmd.operand;
+                continue;
+            }
+            _ => return None,
+        };
+
+        match deltas.iter_mut().find(|(at, _)| *at == offset) {
+            Some((_, total)) => *total += delta,
+            None => deltas.push((offset, delta)),
+        }
+    }
+
+    if offset != 0 {
+        return None;
+    }
+
+    // The loop runs `cell` times when counting down and `-cell` times when counting up
+    let sign = match deltas.iter().find(|(at, _)| *at == 0) {
+        Some((_, -1)) => 1,
+        Some((_, 1)) => -1,
+        _ => return None,
+    };
+
+    let mut cmds: Vec<_> = deltas
+        .into_iter()
+        .filter(|&(at, delta)| at != 0 && delta != 0)
+        .map(|(at, delta)| Cmd {
+            operator: Op::MulAdd,
+            operand: delta * sign,
+            offset: at,
+        })
+        .collect();
+    cmds.push(Cmd::new(Op::SetZero, 0));
+
+    Some(cmds)
+}
+
+/// Points every jump in `cmds` past its matching bracket, as [`Parser::parse_all`] does.
+///
+/// [`Parser::parse_all`]: crate::Parser::parse_all
+pub(crate) fn link_jumps(cmds: &mut [Cmd]) {
+    let mut jmp_stack = Vec::new();
+
+    for instr_ptr in 0..cmds.len() {
+        match cmds[instr_ptr].operator {
+            Op::JmpZero => jmp_stack.push(instr_ptr),
+            Op::JmpNonZero => {
+                let open = jmp_stack.pop().expect("brackets are balanced");
+                cmds[instr_ptr].operand = open as isize + 1;
+                cmds[open].operand = instr_ptr as isize + 1;
+            }
+            _ => {}
+        }
+    }
+}
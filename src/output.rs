@@ -0,0 +1,427 @@
+use std::io::{self, Write};
+
+use crate::{
+    config::{Config, FlushPolicy, OutputEncoding},
+    interpreter::RuntimeErrorKind,
+};
+
+/// Bytes buffered before they are written out regardless of the flush policy.
+const BUFFER_SIZE: usize = 8 * 1024;
+
+/// Turns the cell values printed by `.` into bytes according to the configured
+/// [`OutputEncoding`] and buffers them for the underlying stream according to the
+/// configured [`FlushPolicy`].
+pub(crate) struct Output<W: Write> {
+    inner: W,
+    encoding: OutputEncoding,
+    flush: FlushPolicy,
+    buf: Vec<u8>,
+    /// Start of a UTF-8 sequence that hasn't been completed yet.
+    pending: Vec<u8>,
+}
+
+impl<W: Write> Output<W> {
+    pub(crate) fn new(inner: W, config: &Config) -> Self {
+        Self {
+            inner,
+            encoding: config.output,
+            flush: config.flush,
+            buf: Vec::with_capacity(BUFFER_SIZE),
+            pending: Vec::new(),
+        }
+    }
+
+    /// Prints `value` `count` times.
+    pub(crate) fn write_cell(&mut self, value: u64, count: usize) -> Result<(), RuntimeErrorKind> {
+        // Large counts are encoded a buffer's worth at a time
+        let mut left = count;
+        while left > 0 {
+            let run = left.min(BUFFER_SIZE);
+            self.write_run(value, run)?;
+            left -= run;
+        }
+
+        Ok(())
+    }
+
+    fn write_run(&mut self, value: u64, count: usize) -> Result<(), RuntimeErrorKind> {
+        let mut bytes = Vec::with_capacity(count);
+
+        match self.encoding {
+            OutputEncoding::Raw => bytes.resize(count, value as u8),
+            OutputEncoding::Ascii if value > 0x7F => {
+                return Err(RuntimeErrorKind::InvalidOutput(value))
+            }
+            OutputEncoding::Ascii => bytes.resize(count, value as u8),
+            OutputEncoding::Latin1 => {
+                let c = u8::try_from(value).map_err(|_| RuntimeErrorKind::InvalidOutput(value))?;
+                let mut buf = [0; 2];
+                let encoded = char::from(c).encode_utf8(&mut buf).as_bytes();
+
+                for _ in 0..count {
+                    bytes.extend_from_slice(encoded);
+                }
+            }
+            OutputEncoding::Utf8 => {
+                let byte =
+                    u8::try_from(value).map_err(|_| RuntimeErrorKind::InvalidOutput(value))?;
+
+                for _ in 0..count {
+                    self.pending.push(byte);
+
+                    match std::str::from_utf8(&self.pending) {
+                        Ok(_) => bytes.append(&mut self.pending),
+                        // The sequence may still be completed by later output
+                        Err(err) if err.error_len().is_none() => {}
+                        Err(_) => {
+                            return Err(RuntimeErrorKind::InvalidUtf8(std::mem::take(
+                                &mut self.pending,
+                            )))
+                        }
+                    }
+                }
+            }
+        }
+
+        self.write_bytes(&bytes).map_err(RuntimeErrorKind::Io)
+    }
+
+    /// Called before every `,` so prompts are visible while waiting for input.
+    pub(crate) fn before_read(&mut self) -> io::Result<()> {
+        if self.flush == FlushPolicy::BeforeRead {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes all buffered output and checks that no partial output is left over
+    /// once the program has finished.
+    pub(crate) fn finish(&mut self) -> Result<(), RuntimeErrorKind> {
+        self.flush().map_err(RuntimeErrorKind::Io)?;
+
+        if !self.pending.is_empty() {
+            return Err(RuntimeErrorKind::InvalidUtf8(std::mem::take(
+                &mut self.pending,
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+
+        self.buf.extend_from_slice(bytes);
+
+        let flush = match self.flush {
+            FlushPolicy::Always => true,
+            FlushPolicy::Newline => bytes.contains(&b'\n'),
+            FlushPolicy::BeforeRead | FlushPolicy::Exit => false,
+        };
+
+        if flush {
+            self.flush()
+        } else if self.buf.len() >= BUFFER_SIZE {
+            self.write_buf()
+        } else {
+            Ok(())
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_buf()?;
+        self.inner.flush()
+    }
+
+    /// Like [`Write::write_all`], but drops what was written from the buffer as it goes,
+    /// so a failed write isn't repeated by the next flush.
+    fn write_buf(&mut self) -> io::Result<()> {
+        while !self.buf.is_empty() {
+            match self.inner.write(&self.buf) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.buf.drain(..written);
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for Output<W> {
+    /// Makes sure output printed before a runtime error isn't lost.
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Prints `values` with `encoding`, returning the bytes written or the first error.
+    fn encode(encoding: OutputEncoding, values: &[u64]) -> Result<Vec<u8>, RuntimeErrorKind> {
+        let config = Config {
+            output: encoding,
+            ..Config::default()
+        };
+        let mut written = Vec::new();
+        let mut output = Output::new(&mut written, &config);
+
+        for &value in values {
+            output.write_cell(value, 1)?;
+        }
+        output.finish()?;
+        drop(output);
+
+        Ok(written)
+    }
+
+    #[test]
+    fn raw_output_keeps_the_low_byte() {
+        assert_eq!(
+            encode(OutputEncoding::Raw, &[0x41, 0xFF, 0x1_0042]).unwrap(),
+            [0x41, 0xFF, 0x42]
+        );
+    }
+
+    #[test]
+    fn ascii_output_rejects_values_above_0x7f() {
+        assert_eq!(
+            encode(OutputEncoding::Ascii, &[0x41, 0x7F]).unwrap(),
+            [0x41, 0x7F]
+        );
+        assert!(matches!(
+            encode(OutputEncoding::Ascii, &[0x41, 0x80]),
+            Err(RuntimeErrorKind::InvalidOutput(0x80))
+        ));
+    }
+
+    #[test]
+    fn latin1_output_is_encoded_as_utf8() {
+        assert_eq!(
+            encode(OutputEncoding::Latin1, &[0x41, 0xE9, 0xFF]).unwrap(),
+            "Aéÿ".as_bytes()
+        );
+        assert!(matches!(
+            encode(OutputEncoding::Latin1, &[0x100]),
+            Err(RuntimeErrorKind::InvalidOutput(0x100))
+        ));
+    }
+
+    #[test]
+    fn utf8_output_is_checked_across_cells() {
+        let values: Vec<_> = "Aé日".bytes().map(u64::from).collect();
+        assert_eq!(
+            encode(OutputEncoding::Utf8, &values).unwrap(),
+            "Aé日".as_bytes()
+        );
+
+        assert!(matches!(
+            encode(OutputEncoding::Utf8, &[0x1E9]),
+            Err(RuntimeErrorKind::InvalidOutput(0x1E9))
+        ));
+    }
+
+    #[test]
+    fn invalid_utf8_reports_the_offending_bytes() {
+        let Err(RuntimeErrorKind::InvalidUtf8(bytes)) = encode(OutputEncoding::Utf8, &[0xC3, 0x41])
+        else {
+            panic!("expected invalid UTF-8");
+        };
+        assert_eq!(bytes, [0xC3, 0x41]);
+
+        let Err(RuntimeErrorKind::InvalidUtf8(bytes)) = encode(OutputEncoding::Utf8, &[0x80])
+        else {
+            panic!("expected invalid UTF-8");
+        };
+        assert_eq!(bytes, [0x80]);
+    }
+
+    #[test]
+    fn unfinished_utf8_sequences_are_reported_at_exit() {
+        let Err(RuntimeErrorKind::InvalidUtf8(bytes)) =
+            encode(OutputEncoding::Utf8, &[0x41, 0xE6, 0x97])
+        else {
+            panic!("expected invalid UTF-8");
+        };
+        assert_eq!(bytes, [0xE6, 0x97]);
+    }
+
+    #[test]
+    fn repeated_cells_are_encoded_every_time() {
+        let config = Config {
+            output: OutputEncoding::Latin1,
+            ..Config::default()
+        };
+        let mut written = Vec::new();
+        let mut output = Output::new(&mut written, &config);
+        output.write_cell(0xE9, BUFFER_SIZE + 1).unwrap();
+        output.finish().unwrap();
+        drop(output);
+
+        assert_eq!(written, "é".repeat(BUFFER_SIZE + 1).as_bytes());
+    }
+
+    /// Records what reached it and how often it was flushed.
+    #[derive(Default)]
+    struct Recorder {
+        written: Vec<u8>,
+        flushes: usize,
+    }
+
+    impl Write for Recorder {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    fn output(flush: FlushPolicy) -> Output<Recorder> {
+        let config = Config {
+            flush,
+            ..Config::default()
+        };
+        Output::new(Recorder::default(), &config)
+    }
+
+    fn print(output: &mut Output<Recorder>, text: &str) {
+        for byte in text.bytes() {
+            output.write_cell(byte.into(), 1).unwrap();
+        }
+    }
+
+    #[test]
+    fn always_flushes_every_cell() {
+        let mut output = output(FlushPolicy::Always);
+        print(&mut output, "ab");
+
+        assert_eq!(output.inner.written, b"ab");
+        assert_eq!(output.inner.flushes, 2);
+    }
+
+    #[test]
+    fn newline_flushes_complete_lines() {
+        let mut output = output(FlushPolicy::Newline);
+        print(&mut output, "a\nb");
+
+        assert_eq!(output.inner.written, b"a\n");
+        assert_eq!(output.inner.flushes, 1);
+
+        output.before_read().unwrap();
+        assert_eq!(output.inner.written, b"a\n");
+
+        output.finish().unwrap();
+        assert_eq!(output.inner.written, b"a\nb");
+    }
+
+    #[test]
+    fn lines_show_up_as_they_are_printed_by_default() {
+        assert_eq!(FlushPolicy::default(), FlushPolicy::Newline);
+
+        let mut output = Output::new(Recorder::default(), &Config::default());
+        print(&mut output, "working\n...");
+        assert_eq!(output.inner.written, b"working\n");
+    }
+
+    #[test]
+    fn before_read_flushes_prompts_only() {
+        let mut output = output(FlushPolicy::BeforeRead);
+        print(&mut output, "a\n");
+        assert_eq!(output.inner.written, b"");
+
+        output.before_read().unwrap();
+        assert_eq!(output.inner.written, b"a\n");
+        assert_eq!(output.inner.flushes, 1);
+    }
+
+    #[test]
+    fn exit_flushes_once_the_program_stops() {
+        let mut output = output(FlushPolicy::Exit);
+        print(&mut output, "a\n");
+        output.before_read().unwrap();
+        assert_eq!((output.inner.written.len(), output.inner.flushes), (0, 0));
+
+        output.finish().unwrap();
+        assert_eq!(output.inner.written, b"a\n");
+        assert_eq!(output.inner.flushes, 1);
+    }
+
+    #[test]
+    fn full_buffers_are_written_out_without_flushing() {
+        let mut output = output(FlushPolicy::Exit);
+        output.write_cell(b'a'.into(), BUFFER_SIZE - 1).unwrap();
+        assert!(output.inner.written.is_empty());
+
+        output.write_cell(b'a'.into(), 1).unwrap();
+        assert_eq!(output.inner.written.len(), BUFFER_SIZE);
+        assert_eq!(output.inner.flushes, 0);
+    }
+
+    #[test]
+    fn dropping_flushes_what_is_left() {
+        let mut recorder = Recorder::default();
+        let config = Config {
+            flush: FlushPolicy::Exit,
+            ..Config::default()
+        };
+        let mut output = Output::new(&mut recorder, &config);
+        output.write_cell(b'a'.into(), 1).unwrap();
+        drop(output);
+
+        assert_eq!(recorder.written, b"a");
+        assert_eq!(recorder.flushes, 1);
+    }
+
+    /// Takes part of the first write, fails the second and takes everything after that.
+    #[derive(Default)]
+    struct Hiccup {
+        written: Vec<u8>,
+        writes: usize,
+    }
+
+    impl Write for Hiccup {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.writes += 1;
+            let len = match self.writes {
+                1 => buf.len().min(2),
+                2 => return Err(io::Error::other("hiccup")),
+                _ => buf.len(),
+            };
+            self.written.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failed_flushes_are_not_written_twice() {
+        let mut hiccup = Hiccup::default();
+        let config = Config {
+            flush: FlushPolicy::Exit,
+            ..Config::default()
+        };
+        let mut output = Output::new(&mut hiccup, &config);
+        for byte in b"abcd" {
+            output.write_cell((*byte).into(), 1).unwrap();
+        }
+        assert!(matches!(output.finish(), Err(RuntimeErrorKind::Io(_))));
+        drop(output);
+
+        assert_eq!(hiccup.written, b"abcd");
+    }
+}
@@ -1,134 +1,560 @@
 use std::{
-    fs::File,
-    io::{self, BufReader, Bytes, Read},
+    convert::Infallible,
+    fmt,
+    io::{self, BufReader, Bytes, Cursor, Read},
     path::Path,
+    str::FromStr,
 };
 
-struct Lexer {
-    raw: Bytes<BufReader<File>>,
+use crate::ast::{lift, Node};
+
+/// Where a token starts in the source. `line` and `column` are 1-based, and
+/// columns count characters rather than bytes.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Span {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
 }
 
-impl Lexer {
-    fn from_file(path: &Path) -> io::Result<Self> {
-        let file = std::fs::File::open(path)?;
+impl Default for Span {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
 
-        Ok(Self {
-            raw: BufReader::new(file).bytes(),
-        })
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
     }
 }
 
-#[derive(PartialEq, Debug, Clone, Copy)]
+struct Lexer<'a> {
+    raw: Bytes<BufReader<Box<dyn Read + 'a>>>,
+    /// The error that cut reading short, if any.
+    error: Option<ParseError>,
+    pos: Span,
+    /// Leading whitespace on the current line, so far.
+    indent: usize,
+    at_line_start: bool,
+}
+
+impl<'a> Lexer<'a> {
+    fn from_reader(reader: impl Read + 'a) -> Self {
+        let reader: Box<dyn Read + 'a> = Box::new(reader);
+
+        Self {
+            raw: BufReader::new(reader).bytes(),
+            error: None,
+            pos: Span::default(),
+            indent: 0,
+            at_line_start: true,
+        }
+    }
+
+    /// Returns the error that stopped lexing early, if there was one.
+    fn finish(&mut self) -> Result<(), ParseError> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// Moves the position past `byte`.
+    fn advance(&mut self, byte: u8) {
+        self.pos.offset += 1;
+
+        if byte == b'\n' {
+            self.pos.line += 1;
+            self.pos.column = 1;
+            self.indent = 0;
+            self.at_line_start = true;
+            return;
+        }
+
+        if byte & 0xC0 != 0x80 {
+            // UTF-8 continuation bytes don't start a new character
+            self.pos.column += 1;
+        }
+
+        if self.at_line_start && (byte == b' ' || byte == b'\t') {
+            self.indent += 1;
+        } else {
+            self.at_line_start = false;
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Token {
+    op: Op,
+    /// What the token contributes to the operand of the command it is folded into.
+    operand: isize,
+    span: Span,
+    /// Leading whitespace of the line the token is on.
+    indent: usize,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum Op {
+    /// Adds `operand` to the cell, wrapping at the cell width.
     Add,
-    Sub,
-    Left,
-    Right,
+    /// Moves the memory pointer by `operand` cells, to the right if positive.
+    Move,
     Out,
     In,
     JmpZero,
     JmpNonZero,
+    /// Sets the cell to zero, i.e. `[-]`.
+    SetZero,
+    /// Moves left by `operand` cells until it finds a zero cell, e.g. `[<]`.
+    ScanLeft,
+    /// Moves right by `operand` cells until it finds a zero cell, e.g. `[>]`.
+    ScanRight,
+    /// Adds the cell times `operand` to the cell `offset` cells away, e.g. `[->+<]`.
+    MulAdd,
+}
+
+impl Op {
+    /// Short lowercase name used when printing IR.
+    pub fn name(self) -> &'static str {
+        match self {
+            Op::Add => "add",
+            Op::Move => "move",
+            Op::Out => "out",
+            Op::In => "in",
+            Op::JmpZero => "jz",
+            Op::JmpNonZero => "jnz",
+            Op::SetZero => "zero",
+            Op::ScanLeft => "scanl",
+            Op::ScanRight => "scanr",
+            Op::MulAdd => "muladd",
+        }
+    }
+}
+
+impl FromStr for Op {
+    type Err = String;
+
+    /// Parses the names printed by [`Op::name`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "add" => Ok(Op::Add),
+            "move" => Ok(Op::Move),
+            "out" => Ok(Op::Out),
+            "in" => Ok(Op::In),
+            "jz" => Ok(Op::JmpZero),
+            "jnz" => Ok(Op::JmpNonZero),
+            "zero" => Ok(Op::SetZero),
+            "scanl" => Ok(Op::ScanLeft),
+            "scanr" => Ok(Op::ScanRight),
+            "muladd" => Ok(Op::MulAdd),
+            _ => Err(format!("unknown operator `{s}`")),
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
-impl Iterator for Lexer {
-    type Item = Op;
+impl Iterator for Lexer<'_> {
+    type Item = Token;
 
+    /// Stops at the first read error, which is kept for [`Lexer::finish`].
     fn next(&mut self) -> Option<Self::Item> {
-        for byte in self.raw.by_ref() {
-            let byte = byte.ok()?;
+        while let Some(byte) = self.raw.next() {
+            let span = self.pos;
+            let byte = match byte {
+                Ok(byte) => byte,
+                Err(err) => {
+                    self.error = Some(ParseError::Io(span, err));
+                    return None;
+                }
+            };
+            self.advance(byte);
+
             if !b"+-<>.,[]".contains(&byte) {
                 continue;
             }
 
-            let token = match byte {
-                b'+' => Op::Add,
-                b'-' => Op::Sub,
-                b'<' => Op::Left,
-                b'>' => Op::Right,
-                b'.' => Op::Out,
-                b',' => Op::In,
-                b'[' => Op::JmpZero,
-                b']' => Op::JmpNonZero,
+            let (op, operand) = match byte {
+                b'+' => (Op::Add, 1),
+                b'-' => (Op::Add, -1),
+                b'<' => (Op::Move, -1),
+                b'>' => (Op::Move, 1),
+                b'.' => (Op::Out, 1),
+                b',' => (Op::In, 1),
+                b'[' => (Op::JmpZero, 0),
+                b']' => (Op::JmpNonZero, 0),
                 _ => unreachable!(),
             };
 
-            return Some(token);
+            return Some(Token {
+                op,
+                operand,
+                span,
+                indent: self.indent,
+            });
         }
         None
     }
 }
 
-pub struct Parser {
-    token_stream: Lexer,
+pub struct Parser<'a> {
+    token_stream: Lexer<'a>,
 }
 
-#[derive(Debug)]
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub struct Cmd {
     pub operator: Op,
-    pub operand: usize,
+    /// Repeat count, jump target, scan step or multiplication factor, depending on
+    /// the operator.
+    pub operand: isize,
+    /// Position of the cell the command works on, relative to the memory pointer.
+    pub offset: isize,
+}
+
+impl Cmd {
+    pub fn new(operator: Op, operand: isize) -> Self {
+        Self {
+            operator,
+            operand,
+            offset: 0,
+        }
+    }
+}
+
+/// Prints as the operator followed by the operand, if it has one, and the offset, if it
+/// is non-zero, e.g. `add -3 @2` or `zero`.
+impl fmt::Display for Cmd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.operator)?;
+        if self.operator != Op::SetZero {
+            write!(f, " {}", self.operand)?;
+        }
+        if self.offset != 0 {
+            write!(f, " @{}", self.offset)?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
 pub enum ParseError {
-    UnclosedBracket(usize),
-    UnopenedBracket(usize),
+    /// A `[` without a matching `]`.
+    UnclosedBracket(Span),
+    /// A `]` without a matching `[`.
+    UnopenedBracket(Span),
+    /// Reading the source failed at the given position.
+    Io(Span, io::Error),
+}
+
+impl ParseError {
+    /// Location of the offending bracket, or of the first byte that couldn't be read.
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnclosedBracket(span)
+            | ParseError::UnopenedBracket(span)
+            | ParseError::Io(span, _) => *span,
+        }
+    }
+
+    /// Short description of the error without its location.
+    pub fn message(&self) -> &'static str {
+        match self {
+            ParseError::UnclosedBracket(_) => "unclosed bracket",
+            ParseError::UnopenedBracket(_) => "unopened bracket",
+            ParseError::Io(..) => "failed to read the source",
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Parse error at {}: {}", self.span(), self.message())?;
+        if let ParseError::Io(_, err) = self {
+            write!(f, ": {err}")?;
+        }
+        Ok(())
+    }
 }
 
-impl Parser {
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Io(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> Parser<'a> {
     pub fn from_file(path: &Path) -> io::Result<Self> {
-        Ok(Self {
-            token_stream: Lexer::from_file(path)?,
-        })
-    }
-    pub fn parse_all(mut self) -> Result<Vec<Cmd>, ParseError> {
-        let mut cmds = Vec::new();
-
-        let mut jmp_stack = Vec::new();
-
-        let mut curr = self.token_stream.next();
-        while let Some(operator) = curr {
-            match operator {
-                Op::Add | Op::Sub | Op::Left | Op::Right | Op::Out | Op::In => {
-                    let mut next = Some(operator);
-                    let mut operand = 0;
-                    while next.is_some_and(|next| next == operator) {
-                        operand += 1;
-                        next = self.token_stream.next();
-                    }
-
-                    curr = next;
-                    cmds.push(Cmd { operator, operand });
-                }
-                Op::JmpZero => {
-                    curr = self.token_stream.next();
-
-                    jmp_stack.push(cmds.len());
-                    cmds.push(Cmd {
-                        operator: Op::JmpZero,
-                        operand: 0,
-                    });
-                }
-                Op::JmpNonZero => {
-                    curr = self.token_stream.next();
+        let file = std::fs::File::open(path)?;
 
-                    let Some(close) = jmp_stack.pop() else {
-                        return Err(ParseError::UnopenedBracket(cmds.len()));
-                    };
+        Ok(Self::from_reader(file))
+    }
 
-                    cmds.push(Cmd {
-                        operator: Op::JmpNonZero,
-                        operand: close + 1,
-                    });
+    /// Copies `src` so the parser does not borrow from the caller.
+    pub fn from_bytes(src: &[u8]) -> Self {
+        Self::from_reader(Cursor::new(src.to_vec()))
+    }
 
-                    cmds[close].operand = cmds.len();
-                }
+    /// Lazily lexes tokens out of `reader`, e.g. stdin or a socket.
+    pub fn from_reader<R: Read + 'a>(reader: R) -> Self {
+        Self {
+            token_stream: Lexer::from_reader(reader),
+        }
+    }
+
+    /// Parses the whole source, folding runs of `+` and `-` into one [`Op::Add`] and runs
+    /// of `<` and `>` into one [`Op::Move`]. Runs that cancel out are dropped entirely, so
+    /// `<>` at the start of the tape does not trip [`TapePolicy::Error`].
+    ///
+    /// [`TapePolicy::Error`]: crate::TapePolicy::Error
+    pub fn parse_all(self) -> Result<Vec<Cmd>, ParseError> {
+        self.parse_all_with_spans().map(|(cmds, _)| cmds)
+    }
+
+    /// Like [`Parser::parse_all`], but also returns where each command starts in the
+    /// source. A folded command starts at its first token.
+    pub fn parse_all_with_spans(mut self) -> Result<(Vec<Cmd>, Vec<Span>), ParseError> {
+        let parsed = parse_tokens(&mut self.token_stream);
+        // A source cut short would otherwise show up as an unclosed bracket
+        self.token_stream.finish()?;
+        parsed
+    }
+
+    /// Like [`Parser::parse_all`], but returns loops as [`Node::Loop`] rather than as
+    /// jumps.
+    pub fn parse_ast(self) -> Result<Vec<Node>, ParseError> {
+        self.parse_all().map(|cmds| lift(&cmds))
+    }
+
+    /// Like [`Parser::parse_all`], but on failure returns every unmatched bracket in the
+    /// source instead of just the first one, ordered by position.
+    ///
+    /// Brackets are paired up by indentation where possible, so a `]` lined up with an
+    /// outer `[` reports the missing `]` of the inner loop rather than of the outer one.
+    pub fn parse_all_recovering(mut self) -> Result<Vec<Cmd>, Vec<ParseError>> {
+        let tokens: Vec<_> = self.token_stream.by_ref().collect();
+        self.token_stream.finish().map_err(|err| vec![err])?;
+
+        parse_tokens(tokens.iter().copied())
+            .map(|(cmds, _)| cmds)
+            .map_err(|_| unmatched_brackets(&tokens))
+    }
+}
+
+/// Returns the commands and where each one starts.
+fn parse_tokens(
+    token_stream: impl Iterator<Item = Token>,
+) -> Result<(Vec<Cmd>, Vec<Span>), ParseError> {
+    let mut cmds = Vec::new();
+    let mut spans = Vec::new();
+
+    let mut jmp_stack = Vec::new();
+
+    for token in token_stream {
+        match token.op {
+            Op::JmpZero => {
+                jmp_stack.push((cmds.len(), token.span));
+                cmds.push(Cmd::new(Op::JmpZero, 0));
+            }
+            Op::JmpNonZero => {
+                let Some((close, _)) = jmp_stack.pop() else {
+                    return Err(ParseError::UnopenedBracket(token.span));
+                };
+
+                cmds.push(Cmd::new(Op::JmpNonZero, close as isize + 1));
+
+                cmds[close].operand = cmds.len() as isize;
+            }
+            Op::Add | Op::Move | Op::Out | Op::In => fold(&mut cmds, token.op, token.operand),
+            Op::SetZero | Op::ScanLeft | Op::ScanRight | Op::MulAdd => {
+                unreachable!("the lexer only produces source commands")
             }
         }
 
-        if let Some(close) = jmp_stack.pop() {
-            return Err(ParseError::UnclosedBracket(close));
+        // Folding may have merged the token into the last command or cancelled it out
+        spans.truncate(cmds.len());
+        if spans.len() < cmds.len() {
+            spans.push(token.span);
+        }
+    }
+
+    if let Some((_, span)) = jmp_stack.pop() {
+        return Err(ParseError::UnclosedBracket(span));
+    }
+
+    Ok((cmds, spans))
+}
+
+/// Appends a command, merging it into the previous one if it has the same operator and
+/// dropping the result if it cancels out, so `+-` and `<>` leave nothing behind.
+fn fold(cmds: &mut Vec<Cmd>, operator: Op, operand: isize) {
+    match cmds.last_mut() {
+        Some(last) if last.operator == operator => {
+            last.operand += operand;
+            if last.operand == 0 {
+                cmds.pop();
+            }
         }
+        _ => cmds.push(Cmd::new(operator, operand)),
+    }
+}
+
+/// Matches brackets, preferring the innermost open `[` at the same indentation as each
+/// `]`, and reports everything left unmatched.
+fn unmatched_brackets(tokens: &[Token]) -> Vec<ParseError> {
+    let mut errors = Vec::new();
+    let mut open: Vec<&Token> = Vec::new();
+
+    for token in tokens {
+        match token.op {
+            Op::JmpZero => open.push(token),
+            Op::JmpNonZero => {
+                if open.is_empty() {
+                    errors.push(ParseError::UnopenedBracket(token.span));
+                    continue;
+                }
+
+                // Loops opened after the one this `]` lines up with are missing their `]`
+                let matched = open
+                    .iter()
+                    .rposition(|open| open.indent == token.indent)
+                    .unwrap_or(open.len() - 1);
+                for unclosed in open.drain(matched + 1..) {
+                    errors.push(ParseError::UnclosedBracket(unclosed.span));
+                }
+                open.pop();
+            }
+            _ => {}
+        }
+    }
+
+    errors.extend(
+        open.iter()
+            .map(|open| ParseError::UnclosedBracket(open.span)),
+    );
+    errors.sort_by_key(|err| err.span().offset);
+    errors
+}
+
+impl FromStr for Parser<'_> {
+    type Err = Infallible;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from_bytes(src.as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(src: &[u8]) -> Vec<(usize, usize, usize)> {
+        let (_, spans) = Parser::from_bytes(src).parse_all_with_spans().unwrap();
+        spans
+            .iter()
+            .map(|span| (span.offset, span.line, span.column))
+            .collect()
+    }
+
+    #[test]
+    fn spans_count_lines_and_columns_from_one() {
+        assert_eq!(spans(b"+ .\n\n  ,"), [(0, 1, 1), (2, 1, 3), (7, 3, 3)]);
+    }
+
+    #[test]
+    fn tabs_take_one_column() {
+        assert_eq!(spans(b"\t\t+"), [(2, 1, 3)]);
+    }
+
+    #[test]
+    fn crlf_starts_one_new_line() {
+        assert_eq!(spans(b"+\r\n.\r\n,"), [(0, 1, 1), (3, 2, 1), (6, 3, 1)]);
+    }
+
+    #[test]
+    fn columns_count_characters_not_bytes() {
+        // `é` is two bytes and `日` three
+        assert_eq!(spans("é日+".as_bytes()), [(5, 1, 3)]);
+    }
+
+    #[test]
+    fn folded_commands_start_at_their_first_token() {
+        assert_eq!(spans(b"+\n+>"), [(0, 1, 1), (3, 2, 2)]);
+    }
+
+    /// Line and column of every bracket `parse_all_recovering` reports, with `[` for an
+    /// unclosed one and `]` for an unopened one.
+    fn unmatched(src: &str) -> Vec<(char, usize, usize)> {
+        Parser::from_bytes(src.as_bytes())
+            .parse_all_recovering()
+            .unwrap_err()
+            .iter()
+            .map(|err| {
+                let bracket = match err {
+                    ParseError::UnclosedBracket(_) => '[',
+                    ParseError::UnopenedBracket(_) => ']',
+                    ParseError::Io(..) => panic!("unexpected I/O error: {err}"),
+                };
+                (bracket, err.span().line, err.span().column)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn balanced_sources_parse_normally() {
+        assert_eq!(
+            Parser::from_bytes(b"[\n  [-]\n]")
+                .parse_all_recovering()
+                .unwrap(),
+            Parser::from_bytes(b"[[-]]").parse_all().unwrap()
+        );
+    }
+
+    #[test]
+    fn closing_brackets_pair_with_the_open_bracket_lined_up_with_them() {
+        // Without indentation the outer `[` would be blamed
+        assert_eq!(unmatched("[\n  [\n  -\n]"), [('[', 2, 3)]);
+    }
+
+    #[test]
+    fn every_skipped_loop_is_reported() {
+        assert_eq!(unmatched("[\n  [\n    [\n]"), [('[', 2, 3), ('[', 3, 5)]);
+    }
+
+    #[test]
+    fn closing_brackets_without_a_lined_up_match_close_the_innermost_loop() {
+        assert_eq!(unmatched("[\n  [\n    ]"), [('[', 1, 1)]);
+    }
+
+    #[test]
+    fn brackets_on_one_line_share_an_indentation() {
+        // Both `[` line up with the `]`, so the inner one is closed
+        assert_eq!(unmatched("[[]"), [('[', 1, 1)]);
+    }
+
+    #[test]
+    fn tabs_and_spaces_indent_by_one_each() {
+        assert_eq!(unmatched("\t[\n\t\t[\n \t]"), [('[', 1, 2)]);
+        assert_eq!(unmatched("\t[\n\t\t[\n ]"), [('[', 2, 3)]);
+    }
 
-        Ok(cmds)
+    #[test]
+    fn unopened_and_unclosed_brackets_are_ordered_by_position() {
+        assert_eq!(
+            unmatched("]\n[\n  [\n]\n]"),
+            [(']', 1, 1), ('[', 3, 3), (']', 5, 1)]
+        );
     }
 }
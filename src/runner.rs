@@ -0,0 +1,264 @@
+use std::{
+    fmt,
+    io::{self, Read, Write},
+    path::Path,
+};
+
+#[cfg(all(target_arch = "x86_64", unix))]
+use crate::jit::Jit;
+use crate::{
+    bytecode::{self, BytecodeError},
+    config::{CellWidth, Config, ConfigError, EofPolicy, FlushPolicy, OutputEncoding, TapePolicy},
+    interpreter::{Interpreter, RuntimeError},
+    optimizer::{OptLevel, PassManager},
+    parser::{Cmd, ParseError, Parser, Span},
+};
+
+/// Which engine a [`Runner`] executes its program with.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum Backend {
+    #[default]
+    Interpreter,
+    #[cfg(all(target_arch = "x86_64", unix))]
+    Jit,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Parse(ParseError),
+    Runtime(RuntimeError),
+    Bytecode(BytecodeError),
+    Config(ConfigError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "I/O error: {err}"),
+            Error::Parse(err) => err.fmt(f),
+            Error::Runtime(err) => err.fmt(f),
+            Error::Bytecode(err) => err.fmt(f),
+            Error::Config(err) => write!(f, "Invalid configuration: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            Error::Parse(err) => Some(err),
+            Error::Runtime(err) => Some(err),
+            Error::Bytecode(err) => Some(err),
+            Error::Config(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(err: RuntimeError) -> Self {
+        Error::Runtime(err)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Error::Parse(err)
+    }
+}
+
+impl From<BytecodeError> for Error {
+    fn from(err: BytecodeError) -> Self {
+        Error::Bytecode(err)
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(err: ConfigError) -> Self {
+        Error::Config(err)
+    }
+}
+
+/// Collects a program source and run options, then parses them into a [`Runner`].
+pub struct RunnerBuilder<'a> {
+    parser: Parser<'a>,
+    backend: Backend,
+    config: Config,
+    passes: PassManager,
+}
+
+impl<'a> RunnerBuilder<'a> {
+    pub fn new(parser: Parser<'a>) -> Self {
+        Self {
+            parser,
+            backend: Backend::default(),
+            config: Config::default(),
+            passes: PassManager::default(),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        Ok(Self::new(Parser::from_file(path)?))
+    }
+
+    pub fn from_bytes(src: &[u8]) -> Self {
+        Self::new(Parser::from_bytes(src))
+    }
+
+    pub fn from_reader<R: Read + 'a>(reader: R) -> Self {
+        Self::new(Parser::from_reader(reader))
+    }
+
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Runs the optimization passes of `level`, [`OptLevel::O2`] by default.
+    pub fn opt_level(mut self, level: OptLevel) -> Self {
+        self.passes = PassManager::new(level);
+        self
+    }
+
+    /// Runs the passes selected by `passes`.
+    pub fn passes(mut self, passes: PassManager) -> Self {
+        self.passes = passes;
+        self
+    }
+
+    pub fn step_limit(mut self, limit: Option<u64>) -> Self {
+        self.config.step_limit = limit;
+        self
+    }
+
+    pub fn tape_len(mut self, len: usize) -> Self {
+        self.config.tape_len = len;
+        self
+    }
+
+    pub fn tape_policy(mut self, policy: TapePolicy) -> Self {
+        self.config.tape_policy = policy;
+        self
+    }
+
+    pub fn cell_width(mut self, width: CellWidth) -> Self {
+        self.config.cell_width = width;
+        self
+    }
+
+    pub fn eof(mut self, eof: EofPolicy) -> Self {
+        self.config.eof = eof;
+        self
+    }
+
+    pub fn output(mut self, encoding: OutputEncoding) -> Self {
+        self.config.output = encoding;
+        self
+    }
+
+    pub fn flush(mut self, flush: FlushPolicy) -> Self {
+        self.config.flush = flush;
+        self
+    }
+
+    pub fn build(mut self) -> Result<Runner, Error> {
+        self.config.validate()?;
+        let (cmds, spans) = self.parser.parse_all_with_spans()?;
+        let (cmds, spans) = self.passes.run_with_spans(cmds, spans);
+
+        Ok(Runner {
+            cmds,
+            spans,
+            backend: self.backend,
+            config: self.config,
+        })
+    }
+}
+
+/// A parsed program ready to be executed, possibly several times.
+pub struct Runner {
+    cmds: Vec<Cmd>,
+    /// Where each command starts in the source, or empty if there is no source.
+    spans: Vec<Span>,
+    backend: Backend,
+    config: Config,
+}
+
+impl Runner {
+    /// Loads a program written by [`bytecode::encode`], to be run on `backend`. The
+    /// program's cell width and tape settings replace those in `config`.
+    pub fn from_bytecode(bytes: &[u8], backend: Backend, config: Config) -> Result<Self, Error> {
+        let program = bytecode::decode(bytes)?;
+        let config = program.config(config);
+        config.validate()?;
+
+        Ok(Runner {
+            config,
+            cmds: program.cmds,
+            spans: Vec::new(),
+            backend,
+        })
+    }
+
+    pub fn cmds(&self) -> &[Cmd] {
+        &self.cmds
+    }
+
+    /// Where each of [`cmds`](Self::cmds) starts in the source, or nothing for a program
+    /// loaded from bytecode.
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.backend
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Runs the program on a fresh tape using stdin and stdout.
+    pub fn run(&mut self) -> Result<(), Error> {
+        self.run_with_io(&mut io::stdin(), &mut io::stdout())
+    }
+
+    /// Runs the program with `input` as its input, returning everything it printed.
+    pub fn run_with_input(&mut self, mut input: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut output = Vec::new();
+        self.run_with_io(&mut input, &mut output)?;
+        Ok(output)
+    }
+
+    /// Runs the program on a fresh tape using the given streams.
+    pub fn run_with_io<R: Read, W: Write>(
+        &mut self,
+        input: &mut R,
+        output: &mut W,
+    ) -> Result<(), Error> {
+        let result = match self.backend {
+            Backend::Interpreter => Interpreter::with_config(self.config.clone())?
+                .run_with_io(&self.cmds, input, output),
+            #[cfg(all(target_arch = "x86_64", unix))]
+            Backend::Jit => Jit::compile_with_config(&self.cmds, self.config.clone())?
+                .run_with_io(input, output),
+        };
+
+        result.map_err(|mut err| {
+            err.span = self.spans.get(err.instr_ptr).copied();
+            Error::Runtime(err)
+        })
+    }
+}
@@ -0,0 +1,146 @@
+use std::fmt;
+
+use crate::config::{CellWidth, TapePolicy};
+
+/// An unsigned integer type a tape can be made of. All arithmetic wraps at the
+/// width of the cell.
+pub(crate) trait Cell: Copy + Default + Eq + fmt::Debug {
+    /// Truncates `n` to the width of the cell.
+    fn truncate(n: u64) -> Self;
+    fn to_u64(self) -> u64;
+    fn wrapping_add(self, rhs: Self) -> Self;
+    fn wrapping_mul(self, rhs: Self) -> Self;
+}
+
+macro_rules! impl_cell {
+    ($($ty:ty),*) => {
+        $(
+            impl Cell for $ty {
+                fn truncate(n: u64) -> Self {
+                    n as $ty
+                }
+
+                fn to_u64(self) -> u64 {
+                    self as u64
+                }
+
+                fn wrapping_add(self, rhs: Self) -> Self {
+                    <$ty>::wrapping_add(self, rhs)
+                }
+
+                fn wrapping_mul(self, rhs: Self) -> Self {
+                    <$ty>::wrapping_mul(self, rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_cell!(u8, u16, u32, u64);
+
+/// A tape of cells whose width is picked at runtime.
+pub(crate) enum Tape {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+    U64(Vec<u64>),
+}
+
+impl Tape {
+    /// A zeroed tape of `len` cells.
+    pub(crate) fn new(width: CellWidth, len: usize) -> Self {
+        match width {
+            CellWidth::U8 => Tape::U8(vec![0; len]),
+            CellWidth::U16 => Tape::U16(vec![0; len]),
+            CellWidth::U32 => Tape::U32(vec![0; len]),
+            CellWidth::U64 => Tape::U64(vec![0; len]),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Tape::U8(mem) => mem.len(),
+            Tape::U16(mem) => mem.len(),
+            Tape::U32(mem) => mem.len(),
+            Tape::U64(mem) => mem.len(),
+        }
+    }
+
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut u8 {
+        match self {
+            Tape::U8(mem) => mem.as_mut_ptr(),
+            Tape::U16(mem) => mem.as_mut_ptr() as *mut u8,
+            Tape::U32(mem) => mem.as_mut_ptr() as *mut u8,
+            Tape::U64(mem) => mem.as_mut_ptr() as *mut u8,
+        }
+    }
+
+    pub(crate) fn seek(&mut self, policy: TapePolicy, from: usize, delta: isize) -> Option<usize> {
+        match self {
+            Tape::U8(mem) => seek(mem, policy, from, delta),
+            Tape::U16(mem) => seek(mem, policy, from, delta),
+            Tape::U32(mem) => seek(mem, policy, from, delta),
+            Tape::U64(mem) => seek(mem, policy, from, delta),
+        }
+    }
+
+    pub(crate) fn locate(
+        &mut self,
+        policy: TapePolicy,
+        mem_ptr: usize,
+        offset: isize,
+    ) -> Option<(usize, usize)> {
+        match self {
+            Tape::U8(mem) => locate(mem, policy, mem_ptr, offset),
+            Tape::U16(mem) => locate(mem, policy, mem_ptr, offset),
+            Tape::U32(mem) => locate(mem, policy, mem_ptr, offset),
+            Tape::U64(mem) => locate(mem, policy, mem_ptr, offset),
+        }
+    }
+}
+
+/// Moves `delta` cells away from `from`, applying `policy` if that leaves the tape.
+/// Returns the new position, which may be shifted if the tape grew to the left, or
+/// `None` if the policy forbids leaving the tape or the position doesn't fit an `isize`.
+pub(crate) fn seek<C: Cell>(
+    tape: &mut Vec<C>,
+    policy: TapePolicy,
+    from: usize,
+    delta: isize,
+) -> Option<usize> {
+    let len = tape.len() as isize;
+    let to = (from as isize).checked_add(delta)?;
+
+    if (0..len).contains(&to) {
+        return Some(to as usize);
+    }
+
+    match policy {
+        TapePolicy::Wrap => Some(to.rem_euclid(len) as usize),
+        TapePolicy::Error => None,
+        TapePolicy::Grow if to >= len => {
+            // Grow geometrically so pointer sweeps stay amortized linear
+            tape.resize((to as usize + 1).max(tape.len() * 2), C::default());
+            Some(to as usize)
+        }
+        TapePolicy::Grow => {
+            let extra = to.unsigned_abs().max(tape.len());
+            tape.splice(0..0, std::iter::repeat_n(C::default(), extra));
+            Some((to + extra as isize) as usize)
+        }
+    }
+}
+
+/// Finds the cell `offset` cells away from `mem_ptr` without moving there, applying
+/// `policy` as [`seek`] would. Returns the cell's position and the memory pointer, which
+/// may have shifted if the tape grew to the left.
+pub(crate) fn locate<C: Cell>(
+    tape: &mut Vec<C>,
+    policy: TapePolicy,
+    mem_ptr: usize,
+    offset: isize,
+) -> Option<(usize, usize)> {
+    let target = seek(tape, policy, mem_ptr, offset)?;
+    let mem_ptr = seek(tape, policy, target, offset.checked_neg()?)?;
+    Some((target, mem_ptr))
+}
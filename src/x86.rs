@@ -0,0 +1,212 @@
+//! An x86-64 machine code encoder shared by the JIT and the ELF backend, along with the
+//! register names the assembly backend prints.
+//!
+//! Generated code keeps the tape's base address in `rbx`, and cells are addressed as
+//! `[rbx + index * width]` with the index in a register.
+
+use crate::config::CellWidth;
+
+/// Register numbers usable as the index of a cell address.
+pub(crate) const RDX: u8 = Reg::Rdx as u8;
+pub(crate) const R12: u8 = Reg::R12 as u8;
+
+/// The general purpose registers generated code uses, numbered as in their encoding.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub(crate) enum Reg {
+    Rax = 0,
+    Rcx = 1,
+    Rdx = 2,
+    Rbx = 3,
+    Rsp = 4,
+    Rsi = 6,
+    Rdi = 7,
+    R12 = 12,
+    R13 = 13,
+    R14 = 14,
+    R15 = 15,
+}
+
+impl Reg {
+    /// The name of the low `size` bytes of the register.
+    pub(crate) fn name(self, size: usize) -> &'static str {
+        let names = match self {
+            Reg::Rax => ["al", "ax", "eax", "rax"],
+            Reg::Rcx => ["cl", "cx", "ecx", "rcx"],
+            Reg::Rdx => ["dl", "dx", "edx", "rdx"],
+            Reg::Rbx => ["bl", "bx", "ebx", "rbx"],
+            Reg::Rsp => ["spl", "sp", "esp", "rsp"],
+            Reg::Rsi => ["sil", "si", "esi", "rsi"],
+            Reg::Rdi => ["dil", "di", "edi", "rdi"],
+            Reg::R12 => ["r12b", "r12w", "r12d", "r12"],
+            Reg::R13 => ["r13b", "r13w", "r13d", "r13"],
+            Reg::R14 => ["r14b", "r14w", "r14d", "r14"],
+            Reg::R15 => ["r15b", "r15w", "r15d", "r15"],
+        };
+        names[size.trailing_zeros() as usize]
+    }
+}
+
+pub(crate) struct Assembler {
+    pub(crate) code: Vec<u8>,
+    pub(crate) width: CellWidth,
+}
+
+impl Assembler {
+    pub(crate) fn new(width: CellWidth) -> Self {
+        Self {
+            code: Vec::new(),
+            width,
+        }
+    }
+
+    /// Points the rel32 operand at `at` to the code offset `target`.
+    pub(crate) fn patch(&mut self, at: usize, target: usize) {
+        let rel = target as i32 - (at + 4) as i32;
+        self.code[at..at + 4].copy_from_slice(&rel.to_le_bytes());
+    }
+
+    pub(crate) fn emit(&mut self, bytes: &[u8]) {
+        self.code.extend_from_slice(bytes);
+    }
+
+    pub(crate) fn emit_u32(&mut self, value: u32) {
+        self.emit(&value.to_le_bytes());
+    }
+
+    pub(crate) fn emit_u64(&mut self, value: u64) {
+        self.emit(&value.to_le_bytes());
+    }
+
+    /// Emits an instruction whose memory operand is the current cell, `[rbx + r12 * width]`.
+    /// `reg` is the register operand or opcode extension; `byte_opcode` is used for 8-bit
+    /// cells and `opcode` for wider ones.
+    pub(crate) fn cell_op(&mut self, byte_opcode: u8, opcode: u8, reg: u8) {
+        self.cell_op_at(R12, byte_opcode, opcode, reg);
+    }
+
+    /// Like [`Assembler::cell_op`], but for the cell at `[rbx + index * width]`.
+    pub(crate) fn cell_op_at(&mut self, index: u8, byte_opcode: u8, opcode: u8, reg: u8) {
+        let (opcode, rex_w, scale) = match self.width {
+            CellWidth::U8 => (byte_opcode, 0, 0),
+            CellWidth::U16 => {
+                // Operand size override
+                self.emit(&[0x66]);
+                (opcode, 0, 1)
+            }
+            CellWidth::U32 => (opcode, 0, 2),
+            CellWidth::U64 => (opcode, 0x08, 3),
+        };
+
+        // ModRM with a SIB byte
+        let (rex_x, sib) = Self::sib(index, scale);
+        self.emit(&[0x40 | rex_w | rex_x, opcode, reg << 3 | 0b100, sib]);
+    }
+
+    /// Returns the REX.X bit and the SIB byte addressing `[rbx + index * (1 << scale)]`.
+    pub(crate) fn sib(index: u8, scale: u8) -> (u8, u8) {
+        ((index >> 3) << 1, scale << 6 | (index & 7) << 3 | 0b011)
+    }
+
+    /// Loads `value`, truncated to the cell width, into `rax`.
+    pub(crate) fn load_imm(&mut self, value: u64) {
+        self.mov_imm(Reg::Rax as u8, (value & self.width.max()) as i64);
+    }
+
+    /// Loads `value` into the 64-bit register `reg`, with the shortest encoding.
+    pub(crate) fn mov_imm(&mut self, reg: u8, value: i64) {
+        let rex_b = reg >> 3;
+
+        if let Ok(value) = u32::try_from(value) {
+            // mov r32, imm32, which zero-extends
+            if rex_b != 0 {
+                self.emit(&[0x41]);
+            }
+            self.emit(&[0xB8 | (reg & 7)]);
+            self.emit_u32(value);
+        } else if let Ok(value) = i32::try_from(value) {
+            // mov r64, imm32, which sign-extends
+            self.emit(&[0x48 | rex_b, 0xC7, 0xC0 | (reg & 7)]);
+            self.emit_u32(value as u32);
+        } else {
+            // mov r64, imm64
+            self.emit(&[0x48 | rex_b, 0xB8 | (reg & 7)]);
+            self.emit_u64(value as u64);
+        }
+    }
+
+    /// Emits a 64-bit instruction with the register operand `reg`, or an opcode
+    /// extension, and the register `rm`.
+    pub(crate) fn reg_op(&mut self, opcode: &[u8], reg: u8, rm: u8) {
+        self.emit(&[0x48 | (reg >> 3) << 2 | rm >> 3]);
+        self.emit(opcode);
+        self.emit(&[0xC0 | (reg & 7) << 3 | (rm & 7)]);
+    }
+
+    /// Emits an instruction with the register operand `reg` and the byte-sized memory
+    /// operand `[base + index]`, or `[base]` without an index. `base` can't be `rbp` or
+    /// `r13`, which need a displacement.
+    pub(crate) fn byte_op(&mut self, opcode: &[u8], reg: u8, base: u8, index: Option<u8>) {
+        debug_assert_ne!(base & 7, 5, "base needs a displacement");
+        // A REX prefix, even an empty one, makes the byte registers sil and dil available
+        let rex_x = index.map_or(0, |index| index >> 3);
+        self.emit(&[0x40 | (reg >> 3) << 2 | rex_x << 1 | base >> 3]);
+        self.emit(opcode);
+
+        match index {
+            Some(index) => {
+                debug_assert_ne!(index, Reg::Rsp as u8, "rsp can't be an index");
+                self.emit(&[0x04 | (reg & 7) << 3, (index & 7) << 3 | (base & 7)]);
+            }
+            // rsp and r12 as a base need a SIB byte with no index
+            None if base & 7 == 4 => self.emit(&[0x04 | (reg & 7) << 3, 0x24]),
+            None => self.emit(&[(reg & 7) << 3 | (base & 7)]),
+        }
+    }
+
+    pub(crate) fn add_cell(&mut self, index: u8, value: u64) {
+        self.load_imm(value);
+        // add [cell], rax
+        self.cell_op_at(index, 0x00, 0x01, 0);
+    }
+
+    pub(crate) fn cmp_cell_zero(&mut self) {
+        // cmp [cell], 0
+        self.cell_op(0x80, 0x83, 7);
+        self.emit(&[0x00]);
+    }
+
+    /// Zero-extends the cell at `[rbx + index * width]` into `rsi`.
+    pub(crate) fn load_cell(&mut self, index: u8) {
+        let scale = self.width.bytes().trailing_zeros() as u8;
+        let (rex_w, opcode): (u8, &[u8]) = match self.width {
+            // movzx esi, byte [cell]
+            CellWidth::U8 => (0, &[0x0F, 0xB6]),
+            // movzx esi, word [cell]
+            CellWidth::U16 => (0, &[0x0F, 0xB7]),
+            // mov esi, dword [cell]
+            CellWidth::U32 => (0, &[0x8B]),
+            // mov rsi, qword [cell]
+            CellWidth::U64 => (0x08, &[0x8B]),
+        };
+
+        let (rex_x, sib) = Self::sib(index, scale);
+        self.emit(&[0x40 | rex_w | rex_x]);
+        self.emit(opcode);
+        self.emit(&[0x34, sib]);
+    }
+
+    pub(crate) fn set_zero(&mut self, index: u8) {
+        // xor eax, eax; mov [cell], rax
+        self.emit(&[0x31, 0xC0]);
+        self.cell_op_at(index, 0x88, 0x89, 0);
+    }
+
+    /// Emits a conditional jump and returns the position of its rel32 operand.
+    pub(crate) fn jcc(&mut self, opcode: u8) -> usize {
+        self.emit(&[0x0F, opcode]);
+        let at = self.code.len();
+        self.emit_u32(0);
+        at
+    }
+}
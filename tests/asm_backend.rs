@@ -0,0 +1,154 @@
+#![cfg(all(target_arch = "x86_64", target_os = "linux"))]
+
+mod common;
+
+use std::{
+    fs,
+    io::Write,
+    process::{Command, Output},
+};
+
+use bf_interpreter::{
+    codegen::asm::{self, Syntax},
+    Config, OptLevel, Parser, RunnerBuilder, TapePolicy,
+};
+
+use common::{build, optimize, spawn, temp_path, Run};
+
+/// Assembles and links the translation of `src` with `as` and `ld`, then runs it on
+/// `input`.
+fn run_asm(src: &[u8], config: &Config, syntax: Syntax, input: &[u8]) -> Output {
+    let asm = asm::emit(&optimize(src), None, config, syntax);
+
+    let binary = temp_path("asm");
+    let source = binary.with_extension("s");
+    let object = binary.with_extension("o");
+    fs::write(&source, &asm).unwrap();
+
+    let built = build(Command::new("as").arg(&source).arg("-o").arg(&object))
+        && build(Command::new("ld").arg(&object).arg("-o").arg(&binary));
+    fs::remove_file(&source).unwrap();
+    let _ = fs::remove_file(&object);
+    assert!(built, "generated assembly failed to build:\n{asm}");
+
+    let mut child = spawn(&mut Command::new(&binary));
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    fs::remove_file(&binary).unwrap();
+
+    output
+}
+
+/// [`run_asm`] in each syntax, for the checks shared with other backends.
+const SYNTAXES: [Run; 2] = [
+    |src, config, input| run_asm(src, config, Syntax::Att, input).stdout,
+    |src, config, input| run_asm(src, config, Syntax::Intel, input).stdout,
+];
+
+#[test]
+fn test_programs_match_the_interpreter() {
+    for run in SYNTAXES {
+        common::test_programs_match_the_interpreter(run);
+    }
+}
+
+#[test]
+fn eof_policies_match_the_interpreter() {
+    for run in SYNTAXES {
+        common::eof_policies_match_the_interpreter(run);
+    }
+}
+
+#[test]
+fn tape_policies_match_the_interpreter() {
+    for run in SYNTAXES {
+        common::tape_policies_match_the_interpreter(run, &[TapePolicy::Wrap, TapePolicy::Error]);
+    }
+}
+
+#[test]
+fn flush_policies_keep_every_byte() {
+    for run in SYNTAXES {
+        common::flush_policies_keep_every_byte(run);
+    }
+}
+
+#[test]
+fn errors_exit_with_a_message() {
+    let config = Config {
+        tape_len: 4,
+        tape_policy: TapePolicy::Error,
+        ..Config::default()
+    };
+    let output = run_asm(b"+++++[->++++++++++++<]>+.<<", &config, Syntax::Att, b"");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(output.stdout, b"=");
+    assert_eq!(output.stderr, b"memory pointer moved off the tape\n");
+}
+
+#[test]
+fn comments_point_back_at_the_source() {
+    let (cmds, spans) = Parser::from_bytes(b"++\n  [-]>.")
+        .parse_all_with_spans()
+        .unwrap();
+    let asm = asm::emit(&cmds, Some(&spans), &Config::default(), Syntax::Att);
+    let comments: Vec<_> = asm
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("# "))
+        .collect();
+
+    assert_eq!(
+        comments,
+        [
+            "0: add 2 at 1:1",
+            "1: jz 4 at 2:3",
+            "2: add -1 at 2:4",
+            "3: jnz 2 at 2:5",
+            "4: move 1 at 2:6",
+            "5: out 1 at 2:7",
+        ]
+    );
+}
+
+#[test]
+fn optimized_commands_keep_their_source() {
+    let runner = RunnerBuilder::from_bytes(b"++\n  [-]>>.<")
+        .opt_level(OptLevel::O2)
+        .build()
+        .unwrap();
+    let asm = asm::emit(
+        runner.cmds(),
+        Some(runner.spans()),
+        runner.config(),
+        Syntax::Att,
+    );
+    let comments: Vec<_> = asm
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("# "))
+        .collect();
+
+    // The clear loop is reported at its `[`, and the moves merged into one at the first
+    assert_eq!(
+        comments,
+        [
+            "0: add 2 at 1:1",
+            "1: zero at 2:3",
+            "2: out 1 @2 at 2:8",
+            "3: move 1 at 2:6",
+        ]
+    );
+}
+
+#[test]
+fn intel_syntax_is_declared() {
+    let cmds = Parser::from_bytes(b"+.").parse_all().unwrap();
+
+    let intel = asm::emit(&cmds, None, &Config::default(), Syntax::Intel);
+    assert!(intel.starts_with("    .intel_syntax noprefix\n"));
+    assert!(intel.contains("add BYTE PTR [rbx+r12], al\n"));
+
+    let att = asm::emit(&cmds, None, &Config::default(), Syntax::Att);
+    assert!(!att.contains(".intel_syntax"));
+    assert!(att.contains("addb %al, (%rbx,%r12,1)\n"));
+}
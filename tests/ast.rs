@@ -0,0 +1,52 @@
+use bf_interpreter::{
+    ast::{lift, lower},
+    optimize, Cmd, Node, Op, Parser,
+};
+
+fn parse(src: &[u8]) -> Vec<Cmd> {
+    Parser::from_bytes(src).parse_all().unwrap()
+}
+
+#[test]
+fn loops_become_nodes() {
+    let add = |n| Node::Cmd(Cmd::new(Op::Add, n));
+    let right = Node::Cmd(Cmd::new(Op::Move, 1));
+
+    assert_eq!(
+        Parser::from_bytes(b"+[>[-]+]-").parse_ast().unwrap(),
+        [
+            add(1),
+            Node::Loop(vec![right, Node::Loop(vec![add(-1)]), add(1)]),
+            add(-1),
+        ]
+    );
+}
+
+#[test]
+fn lowering_undoes_lifting() {
+    for path in ["tests/hello_world.bf", "tests/test.bf"] {
+        let cmds = parse(&std::fs::read(path).unwrap());
+        assert_eq!(lower(&lift(&cmds)), cmds, "{path}");
+
+        let cmds = optimize(cmds);
+        assert_eq!(lower(&lift(&cmds)), cmds, "{path} optimized");
+    }
+}
+
+#[test]
+fn lowering_links_jumps() {
+    let cmds = lower(&[Node::Loop(vec![Node::Loop(vec![])]), Node::Loop(vec![])]);
+
+    let jumps: Vec<_> = cmds.iter().map(|cmd| (cmd.operator, cmd.operand)).collect();
+    assert_eq!(
+        jumps,
+        [
+            (Op::JmpZero, 4),
+            (Op::JmpZero, 3),
+            (Op::JmpNonZero, 2),
+            (Op::JmpNonZero, 1),
+            (Op::JmpZero, 6),
+            (Op::JmpNonZero, 5),
+        ]
+    );
+}
@@ -0,0 +1,282 @@
+use bf_interpreter::{
+    bytecode::{self, BytecodeError, BytecodeErrorKind, Program},
+    Backend, CellWidth, Cmd, Config, Error, Interpreter, Op, OptLevel, Parser, PassManager, Runner,
+    RuntimeErrorKind, TapePolicy,
+};
+
+fn cmds(path: &str, level: OptLevel) -> Vec<Cmd> {
+    let cmds = Parser::from_file(path.as_ref())
+        .unwrap()
+        .parse_all()
+        .unwrap();
+    PassManager::new(level).run(cmds)
+}
+
+/// Replaces the CRC-32 at the end of `bytes` after editing them, so decoding gets past it.
+fn reseal(bytes: &mut Vec<u8>) {
+    bytes.truncate(bytes.len() - 4);
+
+    let mut crc = !0u32;
+    for &byte in bytes.iter() {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    bytes.extend((!crc).to_le_bytes());
+}
+
+#[test]
+fn encoded_programs_decode_back() {
+    let config = Config {
+        cell_width: CellWidth::U32,
+        tape_len: 1 << 20,
+        tape_policy: TapePolicy::Grow,
+        ..Config::default()
+    };
+
+    for path in ["tests/hello_world.bf", "tests/test.bf"] {
+        for level in [OptLevel::O0, OptLevel::O3] {
+            let cmds = cmds(path, level);
+            let program = bytecode::decode(&bytecode::encode(&cmds, &config)).unwrap();
+
+            assert_eq!(
+                program,
+                Program {
+                    cmds,
+                    cell_width: CellWidth::U32,
+                    tape_len: 1 << 20,
+                    tape_policy: TapePolicy::Grow,
+                },
+                "{path}"
+            );
+        }
+    }
+}
+
+#[test]
+fn extreme_operands_survive() {
+    let max = bytecode::MAX_TAPE_LEN as isize;
+    let cmds = vec![
+        Cmd {
+            operator: Op::MulAdd,
+            operand: isize::MIN,
+            offset: max,
+        },
+        Cmd {
+            operator: Op::Add,
+            operand: isize::MAX,
+            offset: -max,
+        },
+        Cmd::new(Op::Move, -max),
+        Cmd::new(Op::ScanLeft, max),
+    ];
+    let program = bytecode::decode(&bytecode::encode(&cmds, &Config::default())).unwrap();
+
+    assert_eq!(program.cmds, cmds);
+}
+
+#[test]
+fn format() {
+    let cmds = Parser::from_bytes(b">[->++<]").parse_all().unwrap();
+    let cmds = PassManager::new(OptLevel::O2).run(cmds);
+    let bytes = bytecode::encode(&cmds, &Config::default());
+
+    // Magic, version, cell width, tape policy, tape length 30000 and command count
+    let header = [b"BFBC".as_slice(), &[1, 8, 0, 0xB0, 0xEA, 0x01, 3]].concat();
+    // `move 1`, `muladd 2 @1` with the offset flag set, and `zero` with no operand
+    let body = [1, 2, 0x89, 4, 2, 6];
+
+    assert_eq!(bytes[..bytes.len() - 4], [header, body.to_vec()].concat());
+}
+
+#[test]
+fn out_of_range_values_are_rejected() {
+    let decode = |cmds: &[Cmd], config: &Config| bytecode::decode(&bytecode::encode(cmds, config));
+    let kind = |cmd: Cmd| decode(&[cmd], &Config::default()).unwrap_err().kind;
+    let max = bytecode::MAX_TAPE_LEN as isize;
+
+    assert_eq!(
+        kind(Cmd::new(Op::Out, -1)),
+        BytecodeErrorKind::InvalidOperand(Op::Out, -1)
+    );
+    assert_eq!(
+        kind(Cmd::new(Op::In, 0)),
+        BytecodeErrorKind::InvalidOperand(Op::In, 0)
+    );
+    assert_eq!(
+        kind(Cmd::new(Op::Out, max + 1)),
+        BytecodeErrorKind::InvalidOperand(Op::Out, max + 1)
+    );
+    // A scan that doesn't move would never stop
+    assert_eq!(
+        kind(Cmd::new(Op::ScanRight, 0)),
+        BytecodeErrorKind::InvalidOperand(Op::ScanRight, 0)
+    );
+    assert_eq!(
+        kind(Cmd::new(Op::Move, isize::MIN)),
+        BytecodeErrorKind::InvalidOperand(Op::Move, isize::MIN)
+    );
+    assert_eq!(
+        kind(Cmd {
+            operator: Op::SetZero,
+            operand: 0,
+            offset: max + 1,
+        }),
+        BytecodeErrorKind::InvalidOffset(max + 1)
+    );
+
+    for tape_len in [0, bytecode::MAX_TAPE_LEN + 1, usize::MAX] {
+        let config = Config {
+            tape_len,
+            ..Config::default()
+        };
+        assert_eq!(
+            decode(&[], &config),
+            Err(BytecodeError {
+                kind: BytecodeErrorKind::InvalidTapeLength(tape_len),
+                offset: 7,
+            })
+        );
+    }
+}
+
+#[test]
+fn errors_point_at_the_operand() {
+    let bytes = bytecode::encode(&[Cmd::new(Op::Out, -3)], &Config::default());
+
+    // Header, then the operator byte
+    assert_eq!(bytecode::decode(&bytes).unwrap_err().offset, 12);
+}
+
+#[test]
+fn runner_loads_the_compiled_settings() {
+    let cmds = Parser::from_bytes(b"+[>+]").parse_all().unwrap();
+    let config = Config {
+        tape_len: 4,
+        tape_policy: TapePolicy::Error,
+        ..Config::default()
+    };
+    let bytes = bytecode::encode(&cmds, &config);
+
+    let mut runner =
+        Runner::from_bytecode(&bytes, Backend::Interpreter, Config::default()).unwrap();
+    let Err(Error::Runtime(err)) = runner.run_with_input(b"") else {
+        panic!("expected a runtime error");
+    };
+    assert!(matches!(err.kind, RuntimeErrorKind::TapeOverflow));
+    assert_eq!(err.mem_ptr, 3);
+}
+
+#[test]
+fn runner_keeps_the_other_settings() {
+    let cmds = cmds("tests/hello_world.bf", OptLevel::O3);
+    let bytes = bytecode::encode(
+        &cmds,
+        &Config {
+            cell_width: CellWidth::U16,
+            ..Config::default()
+        },
+    );
+    let config = Config {
+        step_limit: Some(10),
+        ..Config::default()
+    };
+
+    let mut runner = Runner::from_bytecode(&bytes, Backend::Interpreter, config).unwrap();
+    assert_eq!(runner.cmds(), cmds);
+    assert_eq!(runner.config().cell_width, CellWidth::U16);
+    assert_eq!(runner.config().step_limit, Some(10));
+    assert!(matches!(
+        runner.run_with_input(b""),
+        Err(Error::Runtime(err)) if matches!(err.kind, RuntimeErrorKind::StepLimit(10))
+    ));
+}
+
+#[test]
+fn interpreter_loads_bytecode() {
+    let cmds = cmds("tests/hello_world.bf", OptLevel::O3);
+    let bytes = bytecode::encode(
+        &cmds,
+        &Config {
+            cell_width: CellWidth::U16,
+            ..Config::default()
+        },
+    );
+
+    let (mut interpreter, loaded) = Interpreter::load(&bytes, Config::default()).unwrap();
+    assert_eq!(loaded, cmds);
+    assert_eq!(
+        interpreter.run_with_input(&loaded, b"").unwrap(),
+        b"Hello world!\n"
+    );
+
+    assert!(matches!(
+        Interpreter::load(&bytes[1..], Config::default()),
+        Err(Error::Bytecode(err)) if err.kind == BytecodeErrorKind::NotBytecode
+    ));
+}
+
+#[test]
+fn errors_point_at_the_byte() {
+    let error = |bytes: &[u8]| bytecode::decode(bytes).unwrap_err();
+    let cmds = Parser::from_bytes(b"+[>]").parse_all().unwrap();
+    let valid = bytecode::encode(&cmds, &Config::default());
+
+    assert_eq!(
+        error(b"+[>]"),
+        BytecodeError {
+            kind: BytecodeErrorKind::NotBytecode,
+            offset: 0,
+        }
+    );
+    assert_eq!(
+        error(b"BFBC\x02"),
+        BytecodeError {
+            kind: BytecodeErrorKind::UnsupportedVersion(2),
+            offset: 4,
+        }
+    );
+    assert_eq!(
+        error(&valid[..7]),
+        BytecodeError {
+            kind: BytecodeErrorKind::Truncated,
+            offset: 5,
+        }
+    );
+
+    let mut corrupted = valid.clone();
+    corrupted[11] ^= 1;
+    assert_eq!(
+        error(&corrupted),
+        BytecodeError {
+            kind: BytecodeErrorKind::ChecksumMismatch,
+            offset: valid.len() - 4,
+        }
+    );
+
+    let mut edited = valid.clone();
+    edited[5] = 12;
+    reseal(&mut edited);
+    assert_eq!(
+        error(&edited),
+        BytecodeError {
+            kind: BytecodeErrorKind::UnknownCellWidth(12),
+            offset: 5,
+        }
+    );
+
+    // Drop the `jnz`, the last command, and claim one fewer
+    let mut edited = valid.clone();
+    edited.truncate(valid.len() - 5);
+    edited[10] -= 1;
+    edited.extend([0; 4]);
+    reseal(&mut edited);
+    assert_eq!(
+        error(&edited),
+        BytecodeError {
+            kind: BytecodeErrorKind::UnmatchedJump,
+            offset: 13,
+        }
+    );
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use std::{fs, io::Write, process::Command};
+
+use bf_interpreter::{codegen, ir, Config, TapePolicy};
+
+use common::{build, optimize, spawn, temp_path};
+
+/// Builds the C translation of `src` with the system compiler and runs it on `input`.
+fn run_c(src: &[u8], config: &Config, input: &[u8]) -> Vec<u8> {
+    let c = codegen::c::emit(&optimize(src), config);
+
+    let binary = temp_path("c");
+    let source = binary.with_extension("c");
+    fs::write(&source, c).unwrap();
+
+    let built = build(
+        Command::new("cc")
+            .args(["-std=c99", "-Wall", "-Werror", "-O1", "-o"])
+            .arg(&binary)
+            .arg(&source),
+    );
+    fs::remove_file(&source).unwrap();
+    assert!(built, "generated C failed to compile");
+
+    let mut child = spawn(&mut Command::new(&binary));
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    fs::remove_file(&binary).unwrap();
+
+    output.stdout
+}
+
+#[test]
+fn test_programs_match_the_interpreter() {
+    common::test_programs_match_the_interpreter(run_c);
+}
+
+#[test]
+fn eof_policies_match_the_interpreter() {
+    common::eof_policies_match_the_interpreter(run_c);
+}
+
+#[test]
+fn tape_policies_match_the_interpreter() {
+    common::tape_policies_match_the_interpreter(
+        run_c,
+        &[TapePolicy::Wrap, TapePolicy::Error, TapePolicy::Grow],
+    );
+}
+
+#[test]
+fn flush_policies_keep_every_byte() {
+    common::flush_policies_keep_every_byte(run_c);
+}
+
+#[test]
+fn negative_scan_steps_are_negated_once() {
+    let cmds = ir::parse("add 1\nscanl -2\nscanr -3").unwrap();
+    let c = codegen::c::emit(&cmds, &Config::default());
+
+    assert!(c.contains("while (tape[p]) p = seek(p, 2);\n"), "{c}");
+    assert!(c.contains("while (tape[p]) p = seek(p, -3);\n"), "{c}");
+}
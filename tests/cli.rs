@@ -0,0 +1,65 @@
+use std::process::{Command, Output};
+
+/// Runs the command-line tool with `args`.
+fn bf(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_bf-interpreter"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn programs_run_from_source() {
+    let output = bf(&["tests/hello_world.bf"]);
+
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"Hello world!\n");
+}
+
+#[test]
+fn output_paths_need_a_subcommand_that_writes() {
+    for args in [
+        &["-o", "out", "tests/hello_world.bf"][..],
+        &["run", "-o", "out", "tests/hello_world.bf"],
+    ] {
+        let output = bf(args);
+
+        assert_eq!(output.status.code(), Some(2), "{args:?}");
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "-o only applies to build and compile\n"
+        );
+    }
+}
+
+#[test]
+fn emit_is_rejected_when_writing_a_file() {
+    for subcommand in ["build", "compile"] {
+        let output = bf(&[subcommand, "--emit=c", "-o", "out", "tests/hello_world.bf"]);
+
+        assert_eq!(output.status.code(), Some(2), "{subcommand}");
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "--emit can't be combined with build or compile\n"
+        );
+    }
+}
+
+#[test]
+fn compile_refuses_tapes_too_long_to_load() {
+    let output = bf(&[
+        "compile",
+        "--tape-len",
+        "2000000000",
+        "-o",
+        "out",
+        "tests/hello_world.bf",
+    ]);
+
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "compiled programs can't have a tape longer than 1073741824 cells\n"
+    );
+    assert!(!std::path::Path::new("out").exists());
+}
@@ -0,0 +1,162 @@
+//! Scaffolding shared by the tests checking a compiled backend against the interpreter.
+//!
+//! Each backend's tests pass in a [`Run`] that compiles and runs a program the way that
+//! backend does, and the checks here compare what it printed with the interpreter.
+
+// Not every backend uses every helper
+#![allow(dead_code)]
+
+use std::{
+    env, fs,
+    io::ErrorKind,
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use bf_interpreter::{
+    CellWidth, Cmd, Config, EofPolicy, FlushPolicy, OptLevel, Parser, PassManager, RunnerBuilder,
+    TapePolicy,
+};
+
+pub const WC_INPUT: &[u8] = b"hello world\nthe quick  brown fox\n\tjumps over\n";
+
+/// Compiles `src` with a backend and runs it on `input`, returning what it printed
+/// before it stopped.
+pub type Run = fn(src: &[u8], config: &Config, input: &[u8]) -> Vec<u8>;
+
+/// Parses `src` and optimizes it the way the backends are usually fed.
+pub fn optimize(src: &[u8]) -> Vec<Cmd> {
+    PassManager::new(OptLevel::O3).run(Parser::from_bytes(src).parse_all().unwrap())
+}
+
+/// A fresh path in the temporary directory for `backend` to build into.
+pub fn temp_path(backend: &str) -> PathBuf {
+    static BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+    env::temp_dir().join(format!(
+        "bf-{backend}-backend-{}-{}",
+        std::process::id(),
+        BUILDS.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// Runs a build tool, returning whether it succeeded. Fails the test if the tool isn't
+/// installed rather than letting it pass without checking anything.
+pub fn build(command: &mut Command) -> bool {
+    let program = command.get_program().to_string_lossy().into_owned();
+
+    match command.status() {
+        Ok(status) => status.success(),
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            panic!("`{program}` is needed to test this backend but isn't installed")
+        }
+        Err(err) => panic!("failed to run `{program}`: {err}"),
+    }
+}
+
+/// Spawns `command` with its input and output piped, retrying while another test's child
+/// still holds the binary open for writing, which it inherits if it forks while the
+/// binary is being written.
+pub fn spawn(command: &mut Command) -> Child {
+    loop {
+        match command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+        {
+            Err(err) if err.kind() == ErrorKind::ExecutableFileBusy => continue,
+            child => return child.unwrap(),
+        }
+    }
+}
+
+pub fn run_interpreter(src: &[u8], config: &Config, input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    let mut runner = RunnerBuilder::from_bytes(src)
+        .config(config.clone())
+        .opt_level(OptLevel::O0)
+        .build()
+        .unwrap();
+    // Errors stop both backends, so compare what was printed up to then
+    let _ = runner.run_with_io(&mut &input[..], &mut output);
+    output
+}
+
+pub fn assert_same_output(run: Run, src: &[u8], config: &Config, input: &[u8]) {
+    assert_eq!(
+        run(src, config, input),
+        run_interpreter(src, config, input),
+        "{config:?} on {:?}",
+        String::from_utf8_lossy(src)
+    );
+}
+
+pub fn test_programs_match_the_interpreter(run: Run) {
+    let hello_world = fs::read("tests/hello_world.bf").unwrap();
+    let wc = fs::read("tests/test.bf").unwrap();
+
+    for cell_width in [
+        CellWidth::U8,
+        CellWidth::U16,
+        CellWidth::U32,
+        CellWidth::U64,
+    ] {
+        let config = Config {
+            cell_width,
+            ..Config::default()
+        };
+        assert_same_output(run, &hello_world, &config, b"");
+        assert_same_output(run, &wc, &config, WC_INPUT);
+    }
+}
+
+pub fn eof_policies_match_the_interpreter(run: Run) {
+    // Prints what each read left in the cell, starting from 7
+    let src = b"+++++++,.,.>+++++++,.";
+
+    for eof in [
+        EofPolicy::Zero,
+        EofPolicy::Max,
+        EofPolicy::Unchanged,
+        EofPolicy::Error,
+    ] {
+        let config = Config {
+            eof,
+            ..Config::default()
+        };
+        assert_same_output(run, src, &config, b"a");
+    }
+}
+
+/// Checks `policies`, as not every backend can grow its tape.
+pub fn tape_policies_match_the_interpreter(run: Run, policies: &[TapePolicy]) {
+    let src = b"+++[<+++>-]<.<<+.>>>>>>>>>>+.[-]<[->>+<<]>>.>>";
+
+    for &tape_policy in policies {
+        let config = Config {
+            tape_len: 8,
+            tape_policy,
+            ..Config::default()
+        };
+        assert_same_output(run, src, &config, b"");
+    }
+}
+
+pub fn flush_policies_keep_every_byte(run: Run) {
+    let src = b"++++++++++[>++++++++++<-]>+.+.<.>.,.";
+
+    for flush in [
+        FlushPolicy::Always,
+        FlushPolicy::Newline,
+        FlushPolicy::BeforeRead,
+        FlushPolicy::Exit,
+    ] {
+        let config = Config {
+            flush,
+            ..Config::default()
+        };
+        assert_same_output(run, src, &config, b"z");
+    }
+}
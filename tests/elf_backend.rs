@@ -0,0 +1,77 @@
+#![cfg(all(target_arch = "x86_64", target_os = "linux"))]
+
+mod common;
+
+use std::{
+    fs,
+    io::Write,
+    os::unix::fs::PermissionsExt,
+    process::{Command, Output},
+};
+
+use bf_interpreter::{codegen, Config, TapePolicy};
+
+use common::{optimize, spawn, temp_path};
+
+/// Writes the executable for `src` to a temporary file and runs it on `input`.
+fn run_elf(src: &[u8], config: &Config, input: &[u8]) -> Output {
+    let binary = temp_path("elf");
+    fs::write(&binary, codegen::elf::emit(&optimize(src), config)).unwrap();
+    fs::set_permissions(&binary, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let mut child = spawn(&mut Command::new(&binary));
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    fs::remove_file(&binary).unwrap();
+
+    output
+}
+
+/// The stdout of [`run_elf`], for the checks shared with other backends.
+fn stdout(src: &[u8], config: &Config, input: &[u8]) -> Vec<u8> {
+    run_elf(src, config, input).stdout
+}
+
+#[test]
+fn test_programs_match_the_interpreter() {
+    common::test_programs_match_the_interpreter(stdout);
+}
+
+#[test]
+fn eof_policies_match_the_interpreter() {
+    common::eof_policies_match_the_interpreter(stdout);
+}
+
+#[test]
+fn tape_policies_match_the_interpreter() {
+    common::tape_policies_match_the_interpreter(stdout, &[TapePolicy::Wrap, TapePolicy::Error]);
+}
+
+#[test]
+fn flush_policies_keep_every_byte() {
+    common::flush_policies_keep_every_byte(stdout);
+}
+
+#[test]
+fn long_output_fills_the_buffer_more_than_once() {
+    // Prints 10000 `A`s
+    let src = b"+++++[>+++++++++++++<-]>>++++++++++\
+        [>++++++++++[>++++++++++[>++++++++++[<<<<.>>>>-]<-]<-]<-]";
+    let output = run_elf(src, &Config::default(), b"");
+
+    assert_eq!(output.stdout, vec![b'A'; 10000]);
+}
+
+#[test]
+fn errors_exit_with_a_message() {
+    let config = Config {
+        tape_len: 4,
+        tape_policy: TapePolicy::Error,
+        ..Config::default()
+    };
+    let output = run_elf(b"+++++[->++++++++++++<]>+.<<", &config, b"");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(output.stdout, b"=");
+    assert_eq!(output.stderr, b"memory pointer moved off the tape\n");
+}
@@ -0,0 +1,321 @@
+@tape = internal global [30000 x i8] zeroinitializer
+
+declare i32 @putchar(i32)
+declare i32 @fflush(ptr)
+
+; Moves `delta` cells away from `from`, returning the new position.
+define internal i64 @seek(i64 %from, i64 %delta) {
+entry:
+  %to = add i64 %from, %delta
+  %on_tape = icmp ult i64 %to, 30000
+  br i1 %on_tape, label %done, label %off_tape
+done:
+  ret i64 %to
+off_tape:
+  %rem = srem i64 %to, 30000
+  %negative = icmp slt i64 %rem, 0
+  %wrapped = add i64 %rem, 30000
+  %index = select i1 %negative, i64 %wrapped, i64 %rem
+  ret i64 %index
+}
+
+; Writes the low byte of `value` `count` times.
+define internal void @out(i8 %value, i64 %count) {
+entry:
+  %byte = zext i8 %value to i32
+  br label %loop
+loop:
+  %left = phi i64 [ %count, %entry ], [ %next, %body ]
+  %finished = icmp eq i64 %left, 0
+  br i1 %finished, label %exit, label %body
+body:
+  call i32 @putchar(i32 %byte)
+  %next = sub i64 %left, 1
+  br label %loop
+exit:
+  %newline = icmp eq i32 %byte, 10
+  br i1 %newline, label %flush, label %done
+flush:
+  call i32 @fflush(ptr null)
+  br label %done
+done:
+  ret void
+}
+
+define i32 @main() {
+entry:
+  %p = alloca i64
+  store i64 0, ptr %p
+  ; 0: add 9 @1
+  %i1 = load i64, ptr %p
+  %i2 = call i64 @seek(i64 %i1, i64 1)
+  %a3 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i2
+  %v4 = load i8, ptr %a3
+  %v5 = add i8 %v4, 9
+  store i8 %v5, ptr %a3
+  ; 1: move 1
+  %i6 = load i64, ptr %p
+  %i7 = call i64 @seek(i64 %i6, i64 1)
+  store i64 %i7, ptr %p
+  ; 2: muladd 8 @-1
+  %i8 = load i64, ptr %p
+  %a9 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i8
+  %v10 = load i8, ptr %a9
+  %i11 = load i64, ptr %p
+  %i12 = call i64 @seek(i64 %i11, i64 -1)
+  %a13 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i12
+  %v14 = load i8, ptr %a13
+  %v15 = mul i8 %v10, 8
+  %v16 = add i8 %v14, %v15
+  store i8 %v16, ptr %a13
+  ; 3: zero
+  %i17 = load i64, ptr %p
+  %a18 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i17
+  store i8 0, ptr %a18
+  ; 4: out 1 @-1
+  %i19 = load i64, ptr %p
+  %i20 = call i64 @seek(i64 %i19, i64 -1)
+  %a21 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i20
+  %v22 = load i8, ptr %a21
+  call void @out(i8 %v22, i64 1)
+  ; 5: add 7
+  %i23 = load i64, ptr %p
+  %a24 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i23
+  %v25 = load i8, ptr %a24
+  %v26 = add i8 %v25, 7
+  store i8 %v26, ptr %a24
+  ; 6: muladd 4 @-1
+  %i27 = load i64, ptr %p
+  %a28 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i27
+  %v29 = load i8, ptr %a28
+  %i30 = load i64, ptr %p
+  %i31 = call i64 @seek(i64 %i30, i64 -1)
+  %a32 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i31
+  %v33 = load i8, ptr %a32
+  %v34 = mul i8 %v29, 4
+  %v35 = add i8 %v33, %v34
+  store i8 %v35, ptr %a32
+  ; 7: zero
+  %i36 = load i64, ptr %p
+  %a37 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i36
+  store i8 0, ptr %a37
+  ; 8: add 1 @-1
+  %i38 = load i64, ptr %p
+  %i39 = call i64 @seek(i64 %i38, i64 -1)
+  %a40 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i39
+  %v41 = load i8, ptr %a40
+  %v42 = add i8 %v41, 1
+  store i8 %v42, ptr %a40
+  ; 9: out 1 @-1
+  %i43 = load i64, ptr %p
+  %i44 = call i64 @seek(i64 %i43, i64 -1)
+  %a45 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i44
+  %v46 = load i8, ptr %a45
+  call void @out(i8 %v46, i64 1)
+  ; 10: add 7 @-1
+  %i47 = load i64, ptr %p
+  %i48 = call i64 @seek(i64 %i47, i64 -1)
+  %a49 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i48
+  %v50 = load i8, ptr %a49
+  %v51 = add i8 %v50, 7
+  store i8 %v51, ptr %a49
+  ; 11: out 2 @-1
+  %i52 = load i64, ptr %p
+  %i53 = call i64 @seek(i64 %i52, i64 -1)
+  %a54 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i53
+  %v55 = load i8, ptr %a54
+  call void @out(i8 %v55, i64 2)
+  ; 12: add 3 @-1
+  %i56 = load i64, ptr %p
+  %i57 = call i64 @seek(i64 %i56, i64 -1)
+  %a58 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i57
+  %v59 = load i8, ptr %a58
+  %v60 = add i8 %v59, 3
+  store i8 %v60, ptr %a58
+  ; 13: out 1 @-1
+  %i61 = load i64, ptr %p
+  %i62 = call i64 @seek(i64 %i61, i64 -1)
+  %a63 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i62
+  %v64 = load i8, ptr %a63
+  call void @out(i8 %v64, i64 1)
+  ; 14: zero @-1
+  %i65 = load i64, ptr %p
+  %i66 = call i64 @seek(i64 %i65, i64 -1)
+  %a67 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i66
+  store i8 0, ptr %a67
+  ; 15: add 8
+  %i68 = load i64, ptr %p
+  %a69 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i68
+  %v70 = load i8, ptr %a69
+  %v71 = add i8 %v70, 8
+  store i8 %v71, ptr %a69
+  ; 16: muladd 4 @-1
+  %i72 = load i64, ptr %p
+  %a73 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i72
+  %v74 = load i8, ptr %a73
+  %i75 = load i64, ptr %p
+  %i76 = call i64 @seek(i64 %i75, i64 -1)
+  %a77 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i76
+  %v78 = load i8, ptr %a77
+  %v79 = mul i8 %v74, 4
+  %v80 = add i8 %v78, %v79
+  store i8 %v80, ptr %a77
+  ; 17: zero
+  %i81 = load i64, ptr %p
+  %a82 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i81
+  store i8 0, ptr %a82
+  ; 18: out 1 @-1
+  %i83 = load i64, ptr %p
+  %i84 = call i64 @seek(i64 %i83, i64 -1)
+  %a85 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i84
+  %v86 = load i8, ptr %a85
+  call void @out(i8 %v86, i64 1)
+  ; 19: add 11
+  %i87 = load i64, ptr %p
+  %a88 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i87
+  %v89 = load i8, ptr %a88
+  %v90 = add i8 %v89, 11
+  store i8 %v90, ptr %a88
+  ; 20: muladd 8 @-1
+  %i91 = load i64, ptr %p
+  %a92 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i91
+  %v93 = load i8, ptr %a92
+  %i94 = load i64, ptr %p
+  %i95 = call i64 @seek(i64 %i94, i64 -1)
+  %a96 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i95
+  %v97 = load i8, ptr %a96
+  %v98 = mul i8 %v93, 8
+  %v99 = add i8 %v97, %v98
+  store i8 %v99, ptr %a96
+  ; 21: zero
+  %i100 = load i64, ptr %p
+  %a101 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i100
+  store i8 0, ptr %a101
+  ; 22: add -1 @-1
+  %i102 = load i64, ptr %p
+  %i103 = call i64 @seek(i64 %i102, i64 -1)
+  %a104 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i103
+  %v105 = load i8, ptr %a104
+  %v106 = add i8 %v105, -1
+  store i8 %v106, ptr %a104
+  ; 23: out 1 @-1
+  %i107 = load i64, ptr %p
+  %i108 = call i64 @seek(i64 %i107, i64 -1)
+  %a109 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i108
+  %v110 = load i8, ptr %a109
+  call void @out(i8 %v110, i64 1)
+  ; 24: add -8 @-1
+  %i111 = load i64, ptr %p
+  %i112 = call i64 @seek(i64 %i111, i64 -1)
+  %a113 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i112
+  %v114 = load i8, ptr %a113
+  %v115 = add i8 %v114, -8
+  store i8 %v115, ptr %a113
+  ; 25: out 1 @-1
+  %i116 = load i64, ptr %p
+  %i117 = call i64 @seek(i64 %i116, i64 -1)
+  %a118 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i117
+  %v119 = load i8, ptr %a118
+  call void @out(i8 %v119, i64 1)
+  ; 26: add 3 @-1
+  %i120 = load i64, ptr %p
+  %i121 = call i64 @seek(i64 %i120, i64 -1)
+  %a122 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i121
+  %v123 = load i8, ptr %a122
+  %v124 = add i8 %v123, 3
+  store i8 %v124, ptr %a122
+  ; 27: out 1 @-1
+  %i125 = load i64, ptr %p
+  %i126 = call i64 @seek(i64 %i125, i64 -1)
+  %a127 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i126
+  %v128 = load i8, ptr %a127
+  call void @out(i8 %v128, i64 1)
+  ; 28: add -6 @-1
+  %i129 = load i64, ptr %p
+  %i130 = call i64 @seek(i64 %i129, i64 -1)
+  %a131 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i130
+  %v132 = load i8, ptr %a131
+  %v133 = add i8 %v132, -6
+  store i8 %v133, ptr %a131
+  ; 29: out 1 @-1
+  %i134 = load i64, ptr %p
+  %i135 = call i64 @seek(i64 %i134, i64 -1)
+  %a136 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i135
+  %v137 = load i8, ptr %a136
+  call void @out(i8 %v137, i64 1)
+  ; 30: add -8 @-1
+  %i138 = load i64, ptr %p
+  %i139 = call i64 @seek(i64 %i138, i64 -1)
+  %a140 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i139
+  %v141 = load i8, ptr %a140
+  %v142 = add i8 %v141, -8
+  store i8 %v142, ptr %a140
+  ; 31: out 1 @-1
+  %i143 = load i64, ptr %p
+  %i144 = call i64 @seek(i64 %i143, i64 -1)
+  %a145 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i144
+  %v146 = load i8, ptr %a145
+  call void @out(i8 %v146, i64 1)
+  ; 32: zero @-1
+  %i147 = load i64, ptr %p
+  %i148 = call i64 @seek(i64 %i147, i64 -1)
+  %a149 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i148
+  store i8 0, ptr %a149
+  ; 33: add 8
+  %i150 = load i64, ptr %p
+  %a151 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i150
+  %v152 = load i8, ptr %a151
+  %v153 = add i8 %v152, 8
+  store i8 %v153, ptr %a151
+  ; 34: muladd 4 @-1
+  %i154 = load i64, ptr %p
+  %a155 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i154
+  %v156 = load i8, ptr %a155
+  %i157 = load i64, ptr %p
+  %i158 = call i64 @seek(i64 %i157, i64 -1)
+  %a159 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i158
+  %v160 = load i8, ptr %a159
+  %v161 = mul i8 %v156, 4
+  %v162 = add i8 %v160, %v161
+  store i8 %v162, ptr %a159
+  ; 35: zero
+  %i163 = load i64, ptr %p
+  %a164 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i163
+  store i8 0, ptr %a164
+  ; 36: add 1 @-1
+  %i165 = load i64, ptr %p
+  %i166 = call i64 @seek(i64 %i165, i64 -1)
+  %a167 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i166
+  %v168 = load i8, ptr %a167
+  %v169 = add i8 %v168, 1
+  store i8 %v169, ptr %a167
+  ; 37: out 1 @-1
+  %i170 = load i64, ptr %p
+  %i171 = call i64 @seek(i64 %i170, i64 -1)
+  %a172 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i171
+  %v173 = load i8, ptr %a172
+  call void @out(i8 %v173, i64 1)
+  ; 38: zero @-1
+  %i174 = load i64, ptr %p
+  %i175 = call i64 @seek(i64 %i174, i64 -1)
+  %a176 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i175
+  store i8 0, ptr %a176
+  ; 39: add 10 @-1
+  %i177 = load i64, ptr %p
+  %i178 = call i64 @seek(i64 %i177, i64 -1)
+  %a179 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i178
+  %v180 = load i8, ptr %a179
+  %v181 = add i8 %v180, 10
+  store i8 %v181, ptr %a179
+  ; 40: out 1 @-1
+  %i182 = load i64, ptr %p
+  %i183 = call i64 @seek(i64 %i182, i64 -1)
+  %a184 = getelementptr inbounds [30000 x i8], ptr @tape, i64 0, i64 %i183
+  %v185 = load i8, ptr %a184
+  call void @out(i8 %v185, i64 1)
+  ; 41: move -1
+  %i186 = load i64, ptr %p
+  %i187 = call i64 @seek(i64 %i186, i64 -1)
+  store i64 %i187, ptr %p
+  ret i32 0
+}
@@ -0,0 +1,139 @@
+@tape = internal global [64 x i32] zeroinitializer
+@past_eof = private unnamed_addr constant [36 x i8] c"tried to read past the end of input\0A"
+
+declare i32 @putchar(i32)
+declare i32 @getchar()
+declare i32 @fflush(ptr)
+declare i64 @write(i32, ptr, i64)
+declare void @exit(i32) noreturn
+
+; Flushes, prints the message `len` bytes long at `msg` to stderr and exits with 1.
+define internal void @die(ptr %msg, i64 %len) noreturn {
+entry:
+  call i32 @fflush(ptr null)
+  call i64 @write(i32 2, ptr %msg, i64 %len)
+  call void @exit(i32 1)
+  unreachable
+}
+
+; Moves `delta` cells away from `from`, returning the new position.
+define internal i64 @seek(i64 %from, i64 %delta) {
+entry:
+  %to = add i64 %from, %delta
+  %on_tape = icmp ult i64 %to, 64
+  br i1 %on_tape, label %done, label %off_tape
+done:
+  ret i64 %to
+off_tape:
+  %rem = srem i64 %to, 64
+  %negative = icmp slt i64 %rem, 0
+  %wrapped = add i64 %rem, 64
+  %index = select i1 %negative, i64 %wrapped, i64 %rem
+  ret i64 %index
+}
+
+; Writes the low byte of `value` `count` times.
+define internal void @out(i32 %value, i64 %count) {
+entry:
+  %low = trunc i32 %value to i8
+  %byte = zext i8 %low to i32
+  br label %loop
+loop:
+  %left = phi i64 [ %count, %entry ], [ %next, %body ]
+  %finished = icmp eq i64 %left, 0
+  br i1 %finished, label %exit, label %body
+body:
+  call i32 @putchar(i32 %byte)
+  %next = sub i64 %left, 1
+  br label %loop
+exit:
+  call i32 @fflush(ptr null)
+  ret void
+}
+
+; Reads `count` bytes into `cell`, keeping the last, and applies the EOF policy at
+; the end of input.
+define internal void @in(ptr %cell, i64 %count) {
+entry:
+  br label %loop
+loop:
+  %left = phi i64 [ %count, %entry ], [ %next, %read ]
+  %finished = icmp eq i64 %left, 0
+  br i1 %finished, label %exit, label %body
+body:
+  %byte = call i32 @getchar()
+  %at_eof = icmp slt i32 %byte, 0
+  br i1 %at_eof, label %eof, label %read
+read:
+  store i32 %byte, ptr %cell
+  %next = sub i64 %left, 1
+  br label %loop
+eof:
+  call void @die(ptr @past_eof, i64 36)
+  unreachable
+exit:
+  ret void
+}
+
+define i32 @main() {
+entry:
+  %p = alloca i64
+  store i64 0, ptr %p
+  ; 0: in 1
+  %i1 = load i64, ptr %p
+  %a2 = getelementptr inbounds [64 x i32], ptr @tape, i64 0, i64 %i1
+  call void @in(ptr %a2, i64 1)
+  ; 1: muladd 2 @1
+  %i3 = load i64, ptr %p
+  %a4 = getelementptr inbounds [64 x i32], ptr @tape, i64 0, i64 %i3
+  %v5 = load i32, ptr %a4
+  %i6 = load i64, ptr %p
+  %i7 = call i64 @seek(i64 %i6, i64 1)
+  %a8 = getelementptr inbounds [64 x i32], ptr @tape, i64 0, i64 %i7
+  %v9 = load i32, ptr %a8
+  %v10 = mul i32 %v5, 2
+  %v11 = add i32 %v9, %v10
+  store i32 %v11, ptr %a8
+  ; 2: muladd 1 @-1
+  %i12 = load i64, ptr %p
+  %a13 = getelementptr inbounds [64 x i32], ptr @tape, i64 0, i64 %i12
+  %v14 = load i32, ptr %a13
+  %i15 = load i64, ptr %p
+  %i16 = call i64 @seek(i64 %i15, i64 -1)
+  %a17 = getelementptr inbounds [64 x i32], ptr @tape, i64 0, i64 %i16
+  %v18 = load i32, ptr %a17
+  %v19 = add i32 %v18, %v14
+  store i32 %v19, ptr %a17
+  ; 3: zero
+  %i20 = load i64, ptr %p
+  %a21 = getelementptr inbounds [64 x i32], ptr @tape, i64 0, i64 %i20
+  store i32 0, ptr %a21
+  ; 4: zero @-1
+  %i22 = load i64, ptr %p
+  %i23 = call i64 @seek(i64 %i22, i64 -1)
+  %a24 = getelementptr inbounds [64 x i32], ptr @tape, i64 0, i64 %i23
+  store i32 0, ptr %a24
+  ; 5: move 1
+  %i25 = load i64, ptr %p
+  %i26 = call i64 @seek(i64 %i25, i64 1)
+  store i64 %i26, ptr %p
+  ; 6: scanl 1
+  br label %b6.scan
+b6.scan:
+  %i27 = load i64, ptr %p
+  %a28 = getelementptr inbounds [64 x i32], ptr @tape, i64 0, i64 %i27
+  %v29 = load i32, ptr %a28
+  %z30 = icmp eq i32 %v29, 0
+  br i1 %z30, label %b6.done, label %b6.step
+b6.step:
+  %i31 = call i64 @seek(i64 %i27, i64 -1)
+  store i64 %i31, ptr %p
+  br label %b6.scan
+b6.done:
+  ; 7: out 1
+  %i32 = load i64, ptr %p
+  %a33 = getelementptr inbounds [64 x i32], ptr @tape, i64 0, i64 %i32
+  %v34 = load i32, ptr %a33
+  call void @out(i32 %v34, i64 1)
+  ret i32 0
+}
@@ -0,0 +1,1060 @@
+@tape = internal global [30000 x i16] zeroinitializer
+@off_tape = private unnamed_addr constant [34 x i8] c"memory pointer moved off the tape\0A"
+
+declare i32 @putchar(i32)
+declare i32 @getchar()
+declare i32 @fflush(ptr)
+declare i64 @write(i32, ptr, i64)
+declare void @exit(i32) noreturn
+
+; Flushes, prints the message `len` bytes long at `msg` to stderr and exits with 1.
+define internal void @die(ptr %msg, i64 %len) noreturn {
+entry:
+  call i32 @fflush(ptr null)
+  call i64 @write(i32 2, ptr %msg, i64 %len)
+  call void @exit(i32 1)
+  unreachable
+}
+
+; Moves `delta` cells away from `from`, returning the new position.
+define internal i64 @seek(i64 %from, i64 %delta) {
+entry:
+  %to = add i64 %from, %delta
+  %on_tape = icmp ult i64 %to, 30000
+  br i1 %on_tape, label %done, label %off_tape
+done:
+  ret i64 %to
+off_tape:
+  call void @die(ptr @off_tape, i64 34)
+  unreachable
+}
+
+; Writes the low byte of `value` `count` times.
+define internal void @out(i16 %value, i64 %count) {
+entry:
+  %low = trunc i16 %value to i8
+  %byte = zext i8 %low to i32
+  br label %loop
+loop:
+  %left = phi i64 [ %count, %entry ], [ %next, %body ]
+  %finished = icmp eq i64 %left, 0
+  br i1 %finished, label %exit, label %body
+body:
+  call i32 @putchar(i32 %byte)
+  %next = sub i64 %left, 1
+  br label %loop
+exit:
+  %newline = icmp eq i32 %byte, 10
+  br i1 %newline, label %flush, label %done
+flush:
+  call i32 @fflush(ptr null)
+  br label %done
+done:
+  ret void
+}
+
+; Reads `count` bytes into `cell`, keeping the last, and applies the EOF policy at
+; the end of input.
+define internal void @in(ptr %cell, i64 %count) {
+entry:
+  br label %loop
+loop:
+  %left = phi i64 [ %count, %entry ], [ %next, %read ]
+  %finished = icmp eq i64 %left, 0
+  br i1 %finished, label %exit, label %body
+body:
+  %byte = call i32 @getchar()
+  %at_eof = icmp slt i32 %byte, 0
+  br i1 %at_eof, label %eof, label %read
+read:
+  %value = trunc i32 %byte to i16
+  store i16 %value, ptr %cell
+  %next = sub i64 %left, 1
+  br label %loop
+eof:
+  store i16 -1, ptr %cell
+  br label %exit
+exit:
+  ret void
+}
+
+define i32 @main() {
+entry:
+  %p = alloca i64
+  store i64 0, ptr %p
+  ; 0: add 1 @3
+  %i1 = load i64, ptr %p
+  %i2 = call i64 @seek(i64 %i1, i64 3)
+  %a3 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i2
+  %v4 = load i16, ptr %a3
+  %v5 = add i16 %v4, 1
+  store i16 %v5, ptr %a3
+  ; 1: add 1 @8
+  %i6 = load i64, ptr %p
+  %i7 = call i64 @seek(i64 %i6, i64 8)
+  %a8 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i7
+  %v9 = load i16, ptr %a8
+  %v10 = add i16 %v9, 1
+  store i16 %v10, ptr %a8
+  ; 2: add 1 @10
+  %i11 = load i64, ptr %p
+  %i12 = call i64 @seek(i64 %i11, i64 10)
+  %a13 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i12
+  %v14 = load i16, ptr %a13
+  %v15 = add i16 %v14, 1
+  store i16 %v15, ptr %a13
+  ; 3: add 1 @12
+  %i16 = load i64, ptr %p
+  %i17 = call i64 @seek(i64 %i16, i64 12)
+  %a18 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i17
+  %v19 = load i16, ptr %a18
+  %v20 = add i16 %v19, 1
+  store i16 %v20, ptr %a18
+  ; 4: move 12
+  %i21 = load i64, ptr %p
+  %i22 = call i64 @seek(i64 %i21, i64 12)
+  store i64 %i22, ptr %p
+  ; 5: scanl 2
+  br label %b5.scan
+b5.scan:
+  %i23 = load i64, ptr %p
+  %a24 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i23
+  %v25 = load i16, ptr %a24
+  %z26 = icmp eq i16 %v25, 0
+  br i1 %z26, label %b5.done, label %b5.step
+b5.step:
+  %i27 = call i64 @seek(i64 %i23, i64 -2)
+  store i64 %i27, ptr %p
+  br label %b5.scan
+b5.done:
+  ; 6: in 1
+  %i28 = load i64, ptr %p
+  %a29 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i28
+  call void @in(ptr %a29, i64 1)
+  ; 7: jz 131
+  %i30 = load i64, ptr %p
+  %a31 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i30
+  %v32 = load i16, ptr %a31
+  %z33 = icmp eq i16 %v32, 0
+  br i1 %z33, label %b131, label %b8
+b8:
+  ; 8: add -1
+  %i34 = load i64, ptr %p
+  %a35 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i34
+  %v36 = load i16, ptr %a35
+  %v37 = add i16 %v36, -1
+  store i16 %v37, ptr %a35
+  ; 9: jz 75
+  %i38 = load i64, ptr %p
+  %a39 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i38
+  %v40 = load i16, ptr %a39
+  %z41 = icmp eq i16 %v40, 0
+  br i1 %z41, label %b75, label %b10
+b10:
+  ; 10: add -1
+  %i42 = load i64, ptr %p
+  %a43 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i42
+  %v44 = load i16, ptr %a43
+  %v45 = add i16 %v44, -1
+  store i16 %v45, ptr %a43
+  ; 11: jz 74
+  %i46 = load i64, ptr %p
+  %a47 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i46
+  %v48 = load i16, ptr %a47
+  %z49 = icmp eq i16 %v48, 0
+  br i1 %z49, label %b74, label %b12
+b12:
+  ; 12: add -1
+  %i50 = load i64, ptr %p
+  %a51 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i50
+  %v52 = load i16, ptr %a51
+  %v53 = add i16 %v52, -1
+  store i16 %v53, ptr %a51
+  ; 13: jz 73
+  %i54 = load i64, ptr %p
+  %a55 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i54
+  %v56 = load i16, ptr %a55
+  %z57 = icmp eq i16 %v56, 0
+  br i1 %z57, label %b73, label %b14
+b14:
+  ; 14: add -1
+  %i58 = load i64, ptr %p
+  %a59 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i58
+  %v60 = load i16, ptr %a59
+  %v61 = add i16 %v60, -1
+  store i16 %v61, ptr %a59
+  ; 15: jz 72
+  %i62 = load i64, ptr %p
+  %a63 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i62
+  %v64 = load i16, ptr %a63
+  %z65 = icmp eq i16 %v64, 0
+  br i1 %z65, label %b72, label %b16
+b16:
+  ; 16: add -1
+  %i66 = load i64, ptr %p
+  %a67 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i66
+  %v68 = load i16, ptr %a67
+  %v69 = add i16 %v68, -1
+  store i16 %v69, ptr %a67
+  ; 17: jz 71
+  %i70 = load i64, ptr %p
+  %a71 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i70
+  %v72 = load i16, ptr %a71
+  %z73 = icmp eq i16 %v72, 0
+  br i1 %z73, label %b71, label %b18
+b18:
+  ; 18: add -1
+  %i74 = load i64, ptr %p
+  %a75 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i74
+  %v76 = load i16, ptr %a75
+  %v77 = add i16 %v76, -1
+  store i16 %v77, ptr %a75
+  ; 19: jz 70
+  %i78 = load i64, ptr %p
+  %a79 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i78
+  %v80 = load i16, ptr %a79
+  %z81 = icmp eq i16 %v80, 0
+  br i1 %z81, label %b70, label %b20
+b20:
+  ; 20: add -1
+  %i82 = load i64, ptr %p
+  %a83 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i82
+  %v84 = load i16, ptr %a83
+  %v85 = add i16 %v84, -1
+  store i16 %v85, ptr %a83
+  ; 21: jz 69
+  %i86 = load i64, ptr %p
+  %a87 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i86
+  %v88 = load i16, ptr %a87
+  %z89 = icmp eq i16 %v88, 0
+  br i1 %z89, label %b69, label %b22
+b22:
+  ; 22: add -1
+  %i90 = load i64, ptr %p
+  %a91 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i90
+  %v92 = load i16, ptr %a91
+  %v93 = add i16 %v92, -1
+  store i16 %v93, ptr %a91
+  ; 23: jz 68
+  %i94 = load i64, ptr %p
+  %a95 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i94
+  %v96 = load i16, ptr %a95
+  %z97 = icmp eq i16 %v96, 0
+  br i1 %z97, label %b68, label %b24
+b24:
+  ; 24: add 1 @-1
+  %i98 = load i64, ptr %p
+  %i99 = call i64 @seek(i64 %i98, i64 -1)
+  %a100 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i99
+  %v101 = load i16, ptr %a100
+  %v102 = add i16 %v101, 1
+  store i16 %v102, ptr %a100
+  ; 25: add -1
+  %i103 = load i64, ptr %p
+  %a104 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i103
+  %v105 = load i16, ptr %a104
+  %v106 = add i16 %v105, -1
+  store i16 %v106, ptr %a104
+  ; 26: jz 67
+  %i107 = load i64, ptr %p
+  %a108 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i107
+  %v109 = load i16, ptr %a108
+  %z110 = icmp eq i16 %v109, 0
+  br i1 %z110, label %b67, label %b27
+b27:
+  ; 27: add 1 @1
+  %i111 = load i64, ptr %p
+  %i112 = call i64 @seek(i64 %i111, i64 1)
+  %a113 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i112
+  %v114 = load i16, ptr %a113
+  %v115 = add i16 %v114, 1
+  store i16 %v115, ptr %a113
+  ; 28: add -1
+  %i116 = load i64, ptr %p
+  %a117 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i116
+  %v118 = load i16, ptr %a117
+  %v119 = add i16 %v118, -1
+  store i16 %v119, ptr %a117
+  ; 29: jz 66
+  %i120 = load i64, ptr %p
+  %a121 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i120
+  %v122 = load i16, ptr %a121
+  %z123 = icmp eq i16 %v122, 0
+  br i1 %z123, label %b66, label %b30
+b30:
+  ; 30: add -1 @1
+  %i124 = load i64, ptr %p
+  %i125 = call i64 @seek(i64 %i124, i64 1)
+  %a126 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i125
+  %v127 = load i16, ptr %a126
+  %v128 = add i16 %v127, -1
+  store i16 %v128, ptr %a126
+  ; 31: add -1
+  %i129 = load i64, ptr %p
+  %a130 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i129
+  %v131 = load i16, ptr %a130
+  %v132 = add i16 %v131, -1
+  store i16 %v132, ptr %a130
+  ; 32: jz 65
+  %i133 = load i64, ptr %p
+  %a134 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i133
+  %v135 = load i16, ptr %a134
+  %z136 = icmp eq i16 %v135, 0
+  br i1 %z136, label %b65, label %b33
+b33:
+  ; 33: add -1
+  %i137 = load i64, ptr %p
+  %a138 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i137
+  %v139 = load i16, ptr %a138
+  %v140 = add i16 %v139, -1
+  store i16 %v140, ptr %a138
+  ; 34: jz 64
+  %i141 = load i64, ptr %p
+  %a142 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i141
+  %v143 = load i16, ptr %a142
+  %z144 = icmp eq i16 %v143, 0
+  br i1 %z144, label %b64, label %b35
+b35:
+  ; 35: add -1
+  %i145 = load i64, ptr %p
+  %a146 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i145
+  %v147 = load i16, ptr %a146
+  %v148 = add i16 %v147, -1
+  store i16 %v148, ptr %a146
+  ; 36: jz 63
+  %i149 = load i64, ptr %p
+  %a150 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i149
+  %v151 = load i16, ptr %a150
+  %z152 = icmp eq i16 %v151, 0
+  br i1 %z152, label %b63, label %b37
+b37:
+  ; 37: add 2 @-1
+  %i153 = load i64, ptr %p
+  %i154 = call i64 @seek(i64 %i153, i64 -1)
+  %a155 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i154
+  %v156 = load i16, ptr %a155
+  %v157 = add i16 %v156, 2
+  store i16 %v157, ptr %a155
+  ; 38: move -1
+  %i158 = load i64, ptr %p
+  %i159 = call i64 @seek(i64 %i158, i64 -1)
+  store i64 %i159, ptr %p
+  ; 39: muladd 6 @-1
+  %i160 = load i64, ptr %p
+  %a161 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i160
+  %v162 = load i16, ptr %a161
+  %z163 = icmp eq i16 %v162, 0
+  br i1 %z163, label %b39.done, label %b39.mul
+b39.mul:
+  %i164 = load i64, ptr %p
+  %i165 = call i64 @seek(i64 %i164, i64 -1)
+  %a166 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i165
+  %v167 = load i16, ptr %a166
+  %v168 = mul i16 %v162, 6
+  %v169 = add i16 %v167, %v168
+  store i16 %v169, ptr %a166
+  br label %b39.done
+b39.done:
+  ; 40: zero
+  %i170 = load i64, ptr %p
+  %a171 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i170
+  store i16 0, ptr %a171
+  ; 41: move -1
+  %i172 = load i64, ptr %p
+  %i173 = call i64 @seek(i64 %i172, i64 -1)
+  store i64 %i173, ptr %p
+  ; 42: jz 53
+  %i174 = load i64, ptr %p
+  %a175 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i174
+  %v176 = load i16, ptr %a175
+  %z177 = icmp eq i16 %v176, 0
+  br i1 %z177, label %b53, label %b43
+b43:
+  ; 43: move 2
+  %i178 = load i64, ptr %p
+  %i179 = call i64 @seek(i64 %i178, i64 2)
+  store i64 %i179, ptr %p
+  ; 44: jz 48
+  %i180 = load i64, ptr %p
+  %a181 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i180
+  %v182 = load i16, ptr %a181
+  %z183 = icmp eq i16 %v182, 0
+  br i1 %z183, label %b48, label %b45
+b45:
+  ; 45: add -1
+  %i184 = load i64, ptr %p
+  %a185 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i184
+  %v186 = load i16, ptr %a185
+  %v187 = add i16 %v186, -1
+  store i16 %v187, ptr %a185
+  ; 46: move -1
+  %i188 = load i64, ptr %p
+  %i189 = call i64 @seek(i64 %i188, i64 -1)
+  store i64 %i189, ptr %p
+  ; 47: jnz 45
+  %i190 = load i64, ptr %p
+  %a191 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i190
+  %v192 = load i16, ptr %a191
+  %z193 = icmp ne i16 %v192, 0
+  br i1 %z193, label %b45, label %b48
+b48:
+  ; 48: move -1
+  %i194 = load i64, ptr %p
+  %i195 = call i64 @seek(i64 %i194, i64 -1)
+  store i64 %i195, ptr %p
+  ; 49: scanr 1
+  br label %b49.scan
+b49.scan:
+  %i196 = load i64, ptr %p
+  %a197 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i196
+  %v198 = load i16, ptr %a197
+  %z199 = icmp eq i16 %v198, 0
+  br i1 %z199, label %b49.done, label %b49.step
+b49.step:
+  %i200 = call i64 @seek(i64 %i196, i64 1)
+  store i64 %i200, ptr %p
+  br label %b49.scan
+b49.done:
+  ; 50: add -1 @-1
+  %i201 = load i64, ptr %p
+  %i202 = call i64 @seek(i64 %i201, i64 -1)
+  %a203 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i202
+  %v204 = load i16, ptr %a203
+  %v205 = add i16 %v204, -1
+  store i16 %v205, ptr %a203
+  ; 51: move -1
+  %i206 = load i64, ptr %p
+  %i207 = call i64 @seek(i64 %i206, i64 -1)
+  store i64 %i207, ptr %p
+  ; 52: jnz 43
+  %i208 = load i64, ptr %p
+  %a209 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i208
+  %v210 = load i16, ptr %a209
+  %z211 = icmp ne i16 %v210, 0
+  br i1 %z211, label %b43, label %b53
+b53:
+  ; 53: move 2
+  %i212 = load i64, ptr %p
+  %i213 = call i64 @seek(i64 %i212, i64 2)
+  store i64 %i213, ptr %p
+  ; 54: jz 62
+  %i214 = load i64, ptr %p
+  %a215 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i214
+  %v216 = load i16, ptr %a215
+  %z217 = icmp eq i16 %v216, 0
+  br i1 %z217, label %b62, label %b55
+b55:
+  ; 55: add 1 @-1
+  %i218 = load i64, ptr %p
+  %i219 = call i64 @seek(i64 %i218, i64 -1)
+  %a220 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i219
+  %v221 = load i16, ptr %a220
+  %v222 = add i16 %v221, 1
+  store i16 %v222, ptr %a220
+  ; 56: add -1
+  %i223 = load i64, ptr %p
+  %a224 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i223
+  %v225 = load i16, ptr %a224
+  %v226 = add i16 %v225, -1
+  store i16 %v226, ptr %a224
+  ; 57: jz 61
+  %i227 = load i64, ptr %p
+  %a228 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i227
+  %v229 = load i16, ptr %a228
+  %z230 = icmp eq i16 %v229, 0
+  br i1 %z230, label %b61, label %b58
+b58:
+  ; 58: add -1 @-1
+  %i231 = load i64, ptr %p
+  %i232 = call i64 @seek(i64 %i231, i64 -1)
+  %a233 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i232
+  %v234 = load i16, ptr %a233
+  %v235 = add i16 %v234, -1
+  store i16 %v235, ptr %a233
+  ; 59: zero
+  %i236 = load i64, ptr %p
+  %a237 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i236
+  store i16 0, ptr %a237
+  ; 60: jnz 58
+  %i238 = load i64, ptr %p
+  %a239 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i238
+  %v240 = load i16, ptr %a239
+  %z241 = icmp ne i16 %v240, 0
+  br i1 %z241, label %b58, label %b61
+b61:
+  ; 61: jnz 55
+  %i242 = load i64, ptr %p
+  %a243 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i242
+  %v244 = load i16, ptr %a243
+  %z245 = icmp ne i16 %v244, 0
+  br i1 %z245, label %b55, label %b62
+b62:
+  ; 62: jnz 37
+  %i246 = load i64, ptr %p
+  %a247 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i246
+  %v248 = load i16, ptr %a247
+  %z249 = icmp ne i16 %v248, 0
+  br i1 %z249, label %b37, label %b63
+b63:
+  ; 63: jnz 35
+  %i250 = load i64, ptr %p
+  %a251 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i250
+  %v252 = load i16, ptr %a251
+  %z253 = icmp ne i16 %v252, 0
+  br i1 %z253, label %b35, label %b64
+b64:
+  ; 64: jnz 33
+  %i254 = load i64, ptr %p
+  %a255 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i254
+  %v256 = load i16, ptr %a255
+  %z257 = icmp ne i16 %v256, 0
+  br i1 %z257, label %b33, label %b65
+b65:
+  ; 65: jnz 30
+  %i258 = load i64, ptr %p
+  %a259 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i258
+  %v260 = load i16, ptr %a259
+  %z261 = icmp ne i16 %v260, 0
+  br i1 %z261, label %b30, label %b66
+b66:
+  ; 66: jnz 27
+  %i262 = load i64, ptr %p
+  %a263 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i262
+  %v264 = load i16, ptr %a263
+  %z265 = icmp ne i16 %v264, 0
+  br i1 %z265, label %b27, label %b67
+b67:
+  ; 67: jnz 24
+  %i266 = load i64, ptr %p
+  %a267 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i266
+  %v268 = load i16, ptr %a267
+  %z269 = icmp ne i16 %v268, 0
+  br i1 %z269, label %b24, label %b68
+b68:
+  ; 68: jnz 22
+  %i270 = load i64, ptr %p
+  %a271 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i270
+  %v272 = load i16, ptr %a271
+  %z273 = icmp ne i16 %v272, 0
+  br i1 %z273, label %b22, label %b69
+b69:
+  ; 69: jnz 20
+  %i274 = load i64, ptr %p
+  %a275 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i274
+  %v276 = load i16, ptr %a275
+  %z277 = icmp ne i16 %v276, 0
+  br i1 %z277, label %b20, label %b70
+b70:
+  ; 70: jnz 18
+  %i278 = load i64, ptr %p
+  %a279 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i278
+  %v280 = load i16, ptr %a279
+  %z281 = icmp ne i16 %v280, 0
+  br i1 %z281, label %b18, label %b71
+b71:
+  ; 71: jnz 16
+  %i282 = load i64, ptr %p
+  %a283 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i282
+  %v284 = load i16, ptr %a283
+  %z285 = icmp ne i16 %v284, 0
+  br i1 %z285, label %b16, label %b72
+b72:
+  ; 72: jnz 14
+  %i286 = load i64, ptr %p
+  %a287 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i286
+  %v288 = load i16, ptr %a287
+  %z289 = icmp ne i16 %v288, 0
+  br i1 %z289, label %b14, label %b73
+b73:
+  ; 73: jnz 12
+  %i290 = load i64, ptr %p
+  %a291 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i290
+  %v292 = load i16, ptr %a291
+  %z293 = icmp ne i16 %v292, 0
+  br i1 %z293, label %b12, label %b74
+b74:
+  ; 74: jnz 10
+  %i294 = load i64, ptr %p
+  %a295 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i294
+  %v296 = load i16, ptr %a295
+  %z297 = icmp ne i16 %v296, 0
+  br i1 %z297, label %b10, label %b75
+b75:
+  ; 75: move -1
+  %i298 = load i64, ptr %p
+  %i299 = call i64 @seek(i64 %i298, i64 -1)
+  store i64 %i299, ptr %p
+  ; 76: jz 82
+  %i300 = load i64, ptr %p
+  %a301 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i300
+  %v302 = load i16, ptr %a301
+  %z303 = icmp eq i16 %v302, 0
+  br i1 %z303, label %b82, label %b77
+b77:
+  ; 77: add -1
+  %i304 = load i64, ptr %p
+  %a305 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i304
+  %v306 = load i16, ptr %a305
+  %v307 = add i16 %v306, -1
+  store i16 %v307, ptr %a305
+  ; 78: zero @-2
+  %i308 = load i64, ptr %p
+  %i309 = call i64 @seek(i64 %i308, i64 -2)
+  %a310 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i309
+  store i16 0, ptr %a310
+  ; 79: add 1 @-2
+  %i311 = load i64, ptr %p
+  %i312 = call i64 @seek(i64 %i311, i64 -2)
+  %a313 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i312
+  %v314 = load i16, ptr %a313
+  %v315 = add i16 %v314, 1
+  store i16 %v315, ptr %a313
+  ; 80: move -1
+  %i316 = load i64, ptr %p
+  %i317 = call i64 @seek(i64 %i316, i64 -1)
+  store i64 %i317, ptr %p
+  ; 81: jnz 77
+  %i318 = load i64, ptr %p
+  %a319 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i318
+  %v320 = load i16, ptr %a319
+  %z321 = icmp ne i16 %v320, 0
+  br i1 %z321, label %b77, label %b82
+b82:
+  ; 82: move -2
+  %i322 = load i64, ptr %p
+  %i323 = call i64 @seek(i64 %i322, i64 -2)
+  store i64 %i323, ptr %p
+  ; 83: muladd 1 @6
+  %i324 = load i64, ptr %p
+  %a325 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i324
+  %v326 = load i16, ptr %a325
+  %z327 = icmp eq i16 %v326, 0
+  br i1 %z327, label %b83.done, label %b83.mul
+b83.mul:
+  %i328 = load i64, ptr %p
+  %i329 = call i64 @seek(i64 %i328, i64 6)
+  %a330 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i329
+  %v331 = load i16, ptr %a330
+  %v332 = add i16 %v331, %v326
+  store i16 %v332, ptr %a330
+  br label %b83.done
+b83.done:
+  ; 84: zero
+  %i333 = load i64, ptr %p
+  %a334 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i333
+  store i16 0, ptr %a334
+  ; 85: move 1
+  %i335 = load i64, ptr %p
+  %i336 = call i64 @seek(i64 %i335, i64 1)
+  store i64 %i336, ptr %p
+  ; 86: scanr 1
+  br label %b86.scan
+b86.scan:
+  %i337 = load i64, ptr %p
+  %a338 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i337
+  %v339 = load i16, ptr %a338
+  %z340 = icmp eq i16 %v339, 0
+  br i1 %z340, label %b86.done, label %b86.step
+b86.step:
+  %i341 = call i64 @seek(i64 %i337, i64 1)
+  store i64 %i341, ptr %p
+  br label %b86.scan
+b86.done:
+  ; 87: add 1 @7
+  %i342 = load i64, ptr %p
+  %i343 = call i64 @seek(i64 %i342, i64 7)
+  %a344 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i343
+  %v345 = load i16, ptr %a344
+  %v346 = add i16 %v345, 1
+  store i16 %v346, ptr %a344
+  ; 88: move 8
+  %i347 = load i64, ptr %p
+  %i348 = call i64 @seek(i64 %i347, i64 8)
+  store i64 %i348, ptr %p
+  ; 89: jz 129
+  %i349 = load i64, ptr %p
+  %a350 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i349
+  %v351 = load i16, ptr %a350
+  %z352 = icmp eq i16 %v351, 0
+  br i1 %z352, label %b129, label %b90
+b90:
+  ; 90: add 1 @-1
+  %i353 = load i64, ptr %p
+  %i354 = call i64 @seek(i64 %i353, i64 -1)
+  %a355 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i354
+  %v356 = load i16, ptr %a355
+  %v357 = add i16 %v356, 1
+  store i16 %v357, ptr %a355
+  ; 91: move -1
+  %i358 = load i64, ptr %p
+  %i359 = call i64 @seek(i64 %i358, i64 -1)
+  store i64 %i359, ptr %p
+  ; 92: jz 122
+  %i360 = load i64, ptr %p
+  %a361 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i360
+  %v362 = load i16, ptr %a361
+  %z363 = icmp eq i16 %v362, 0
+  br i1 %z363, label %b122, label %b93
+b93:
+  ; 93: add 9 @1
+  %i364 = load i64, ptr %p
+  %i365 = call i64 @seek(i64 %i364, i64 1)
+  %a366 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i365
+  %v367 = load i16, ptr %a366
+  %v368 = add i16 %v367, 9
+  store i16 %v368, ptr %a366
+  ; 94: add -1
+  %i369 = load i64, ptr %p
+  %a370 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i369
+  %v371 = load i16, ptr %a370
+  %v372 = add i16 %v371, -1
+  store i16 %v372, ptr %a370
+  ; 95: muladd -1 @1
+  %i373 = load i64, ptr %p
+  %a374 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i373
+  %v375 = load i16, ptr %a374
+  %z376 = icmp eq i16 %v375, 0
+  br i1 %z376, label %b95.done, label %b95.mul
+b95.mul:
+  %i377 = load i64, ptr %p
+  %i378 = call i64 @seek(i64 %i377, i64 1)
+  %a379 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i378
+  %v380 = load i16, ptr %a379
+  %v381 = mul i16 %v375, -1
+  %v382 = add i16 %v380, %v381
+  store i16 %v382, ptr %a379
+  br label %b95.done
+b95.done:
+  ; 96: zero
+  %i383 = load i64, ptr %p
+  %a384 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i383
+  store i16 0, ptr %a384
+  ; 97: add 2
+  %i385 = load i64, ptr %p
+  %a386 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i385
+  %v387 = load i16, ptr %a386
+  %v388 = add i16 %v387, 2
+  store i16 %v388, ptr %a386
+  ; 98: move 1
+  %i389 = load i64, ptr %p
+  %i390 = call i64 @seek(i64 %i389, i64 1)
+  store i64 %i390, ptr %p
+  ; 99: jz 110
+  %i391 = load i64, ptr %p
+  %a392 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i391
+  %v393 = load i16, ptr %a392
+  %z394 = icmp eq i16 %v393, 0
+  br i1 %z394, label %b110, label %b100
+b100:
+  ; 100: add 7 @-1
+  %i395 = load i64, ptr %p
+  %i396 = call i64 @seek(i64 %i395, i64 -1)
+  %a397 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i396
+  %v398 = load i16, ptr %a397
+  %v399 = add i16 %v398, 7
+  store i16 %v399, ptr %a397
+  ; 101: add -1
+  %i400 = load i64, ptr %p
+  %a401 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i400
+  %v402 = load i16, ptr %a401
+  %v403 = add i16 %v402, -1
+  store i16 %v403, ptr %a401
+  ; 102: muladd -1 @-1
+  %i404 = load i64, ptr %p
+  %a405 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i404
+  %v406 = load i16, ptr %a405
+  %z407 = icmp eq i16 %v406, 0
+  br i1 %z407, label %b102.done, label %b102.mul
+b102.mul:
+  %i408 = load i64, ptr %p
+  %i409 = call i64 @seek(i64 %i408, i64 -1)
+  %a410 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i409
+  %v411 = load i16, ptr %a410
+  %v412 = mul i16 %v406, -1
+  %v413 = add i16 %v411, %v412
+  store i16 %v413, ptr %a410
+  br label %b102.done
+b102.done:
+  ; 103: zero
+  %i414 = load i64, ptr %p
+  %a415 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i414
+  store i16 0, ptr %a415
+  ; 104: add 1
+  %i416 = load i64, ptr %p
+  %a417 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i416
+  %v418 = load i16, ptr %a417
+  %v419 = add i16 %v418, 1
+  store i16 %v419, ptr %a417
+  ; 105: jz 109
+  %i420 = load i64, ptr %p
+  %a421 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i420
+  %v422 = load i16, ptr %a421
+  %z423 = icmp eq i16 %v422, 0
+  br i1 %z423, label %b109, label %b106
+b106:
+  ; 106: add 1
+  %i424 = load i64, ptr %p
+  %a425 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i424
+  %v426 = load i16, ptr %a425
+  %v427 = add i16 %v426, 1
+  store i16 %v427, ptr %a425
+  ; 107: move 6
+  %i428 = load i64, ptr %p
+  %i429 = call i64 @seek(i64 %i428, i64 6)
+  store i64 %i429, ptr %p
+  ; 108: jnz 106
+  %i430 = load i64, ptr %p
+  %a431 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i430
+  %v432 = load i16, ptr %a431
+  %z433 = icmp ne i16 %v432, 0
+  br i1 %z433, label %b106, label %b109
+b109:
+  ; 109: jnz 100
+  %i434 = load i64, ptr %p
+  %a435 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i434
+  %v436 = load i16, ptr %a435
+  %z437 = icmp ne i16 %v436, 0
+  br i1 %z437, label %b100, label %b110
+b110:
+  ; 110: move -1
+  %i438 = load i64, ptr %p
+  %i439 = call i64 @seek(i64 %i438, i64 -1)
+  store i64 %i439, ptr %p
+  ; 111: muladd 1 @1
+  %i440 = load i64, ptr %p
+  %a441 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i440
+  %v442 = load i16, ptr %a441
+  %z443 = icmp eq i16 %v442, 0
+  br i1 %z443, label %b111.done, label %b111.mul
+b111.mul:
+  %i444 = load i64, ptr %p
+  %i445 = call i64 @seek(i64 %i444, i64 1)
+  %a446 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i445
+  %v447 = load i16, ptr %a446
+  %v448 = add i16 %v447, %v442
+  store i16 %v448, ptr %a446
+  br label %b111.done
+b111.done:
+  ; 112: zero
+  %i449 = load i64, ptr %p
+  %a450 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i449
+  store i16 0, ptr %a450
+  ; 113: move 1
+  %i451 = load i64, ptr %p
+  %i452 = call i64 @seek(i64 %i451, i64 1)
+  store i64 %i452, ptr %p
+  ; 114: jz 119
+  %i453 = load i64, ptr %p
+  %a454 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i453
+  %v455 = load i16, ptr %a454
+  %z456 = icmp eq i16 %v455, 0
+  br i1 %z456, label %b119, label %b115
+b115:
+  ; 115: add 2 @5
+  %i457 = load i64, ptr %p
+  %i458 = call i64 @seek(i64 %i457, i64 5)
+  %a459 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i458
+  %v460 = load i16, ptr %a459
+  %v461 = add i16 %v460, 2
+  store i16 %v461, ptr %a459
+  ; 116: zero @6
+  %i462 = load i64, ptr %p
+  %i463 = call i64 @seek(i64 %i462, i64 6)
+  %a464 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i463
+  store i16 0, ptr %a464
+  ; 117: move 6
+  %i465 = load i64, ptr %p
+  %i466 = call i64 @seek(i64 %i465, i64 6)
+  store i64 %i466, ptr %p
+  ; 118: jnz 115
+  %i467 = load i64, ptr %p
+  %a468 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i467
+  %v469 = load i16, ptr %a468
+  %z470 = icmp ne i16 %v469, 0
+  br i1 %z470, label %b115, label %b119
+b119:
+  ; 119: add 1
+  %i471 = load i64, ptr %p
+  %a472 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i471
+  %v473 = load i16, ptr %a472
+  %v474 = add i16 %v473, 1
+  store i16 %v474, ptr %a472
+  ; 120: move -1
+  %i475 = load i64, ptr %p
+  %i476 = call i64 @seek(i64 %i475, i64 -1)
+  store i64 %i476, ptr %p
+  ; 121: jnz 93
+  %i477 = load i64, ptr %p
+  %a478 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i477
+  %v479 = load i16, ptr %a478
+  %z480 = icmp ne i16 %v479, 0
+  br i1 %z480, label %b93, label %b122
+b122:
+  ; 122: move 1
+  %i481 = load i64, ptr %p
+  %i482 = call i64 @seek(i64 %i481, i64 1)
+  store i64 %i482, ptr %p
+  ; 123: jz 127
+  %i483 = load i64, ptr %p
+  %a484 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i483
+  %v485 = load i16, ptr %a484
+  %z486 = icmp eq i16 %v485, 0
+  br i1 %z486, label %b127, label %b124
+b124:
+  ; 124: add -1
+  %i487 = load i64, ptr %p
+  %a488 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i487
+  %v489 = load i16, ptr %a488
+  %v490 = add i16 %v489, -1
+  store i16 %v490, ptr %a488
+  ; 125: move -6
+  %i491 = load i64, ptr %p
+  %i492 = call i64 @seek(i64 %i491, i64 -6)
+  store i64 %i492, ptr %p
+  ; 126: jnz 124
+  %i493 = load i64, ptr %p
+  %a494 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i493
+  %v495 = load i16, ptr %a494
+  %z496 = icmp ne i16 %v495, 0
+  br i1 %z496, label %b124, label %b127
+b127:
+  ; 127: move 4
+  %i497 = load i64, ptr %p
+  %i498 = call i64 @seek(i64 %i497, i64 4)
+  store i64 %i498, ptr %p
+  ; 128: jnz 90
+  %i499 = load i64, ptr %p
+  %a500 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i499
+  %v501 = load i16, ptr %a500
+  %z502 = icmp ne i16 %v501, 0
+  br i1 %z502, label %b90, label %b129
+b129:
+  ; 129: in 1
+  %i503 = load i64, ptr %p
+  %a504 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i503
+  call void @in(ptr %a504, i64 1)
+  ; 130: jnz 8
+  %i505 = load i64, ptr %p
+  %a506 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i505
+  %v507 = load i16, ptr %a506
+  %z508 = icmp ne i16 %v507, 0
+  br i1 %z508, label %b8, label %b131
+b131:
+  ; 131: add 1
+  %i509 = load i64, ptr %p
+  %a510 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i509
+  %v511 = load i16, ptr %a510
+  %v512 = add i16 %v511, 1
+  store i16 %v512, ptr %a510
+  ; 132: add 2 @-1
+  %i513 = load i64, ptr %p
+  %i514 = call i64 @seek(i64 %i513, i64 -1)
+  %a515 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i514
+  %v516 = load i16, ptr %a515
+  %v517 = add i16 %v516, 2
+  store i16 %v517, ptr %a515
+  ; 133: move 2
+  %i518 = load i64, ptr %p
+  %i519 = call i64 @seek(i64 %i518, i64 2)
+  store i64 %i519, ptr %p
+  ; 134: jz 149
+  %i520 = load i64, ptr %p
+  %a521 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i520
+  %v522 = load i16, ptr %a521
+  %z523 = icmp eq i16 %v522, 0
+  br i1 %z523, label %b149, label %b135
+b135:
+  ; 135: jz 139
+  %i524 = load i64, ptr %p
+  %a525 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i524
+  %v526 = load i16, ptr %a525
+  %z527 = icmp eq i16 %v526, 0
+  br i1 %z527, label %b139, label %b136
+b136:
+  ; 136: add 5
+  %i528 = load i64, ptr %p
+  %a529 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i528
+  %v530 = load i16, ptr %a529
+  %v531 = add i16 %v530, 5
+  store i16 %v531, ptr %a529
+  ; 137: move 6
+  %i532 = load i64, ptr %p
+  %i533 = call i64 @seek(i64 %i532, i64 6)
+  store i64 %i533, ptr %p
+  ; 138: jnz 136
+  %i534 = load i64, ptr %p
+  %a535 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i534
+  %v536 = load i16, ptr %a535
+  %z537 = icmp ne i16 %v536, 0
+  br i1 %z537, label %b136, label %b139
+b139:
+  ; 139: add 1 @-1
+  %i538 = load i64, ptr %p
+  %i539 = call i64 @seek(i64 %i538, i64 -1)
+  %a540 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i539
+  %v541 = load i16, ptr %a540
+  %v542 = add i16 %v541, 1
+  store i16 %v542, ptr %a540
+  ; 140: add 1
+  %i543 = load i64, ptr %p
+  %a544 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i543
+  %v545 = load i16, ptr %a544
+  %v546 = add i16 %v545, 1
+  store i16 %v546, ptr %a544
+  ; 141: jz 147
+  %i547 = load i64, ptr %p
+  %a548 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i547
+  %v549 = load i16, ptr %a548
+  %z550 = icmp eq i16 %v549, 0
+  br i1 %z550, label %b147, label %b142
+b142:
+  ; 142: muladd 8 @-1
+  %i551 = load i64, ptr %p
+  %a552 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i551
+  %v553 = load i16, ptr %a552
+  %z554 = icmp eq i16 %v553, 0
+  br i1 %z554, label %b142.done, label %b142.mul
+b142.mul:
+  %i555 = load i64, ptr %p
+  %i556 = call i64 @seek(i64 %i555, i64 -1)
+  %a557 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i556
+  %v558 = load i16, ptr %a557
+  %v559 = mul i16 %v553, 8
+  %v560 = add i16 %v558, %v559
+  store i16 %v560, ptr %a557
+  br label %b142.done
+b142.done:
+  ; 143: zero
+  %i561 = load i64, ptr %p
+  %a562 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i561
+  store i16 0, ptr %a562
+  ; 144: out 1 @-1
+  %i563 = load i64, ptr %p
+  %i564 = call i64 @seek(i64 %i563, i64 -1)
+  %a565 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i564
+  %v566 = load i16, ptr %a565
+  call void @out(i16 %v566, i64 1)
+  ; 145: move -6
+  %i567 = load i64, ptr %p
+  %i568 = call i64 @seek(i64 %i567, i64 -6)
+  store i64 %i568, ptr %p
+  ; 146: jnz 142
+  %i569 = load i64, ptr %p
+  %a570 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i569
+  %v571 = load i16, ptr %a570
+  %z572 = icmp ne i16 %v571, 0
+  br i1 %z572, label %b142, label %b147
+b147:
+  ; 147: move 8
+  %i573 = load i64, ptr %p
+  %i574 = call i64 @seek(i64 %i573, i64 8)
+  store i64 %i574, ptr %p
+  ; 148: jnz 135
+  %i575 = load i64, ptr %p
+  %a576 = getelementptr inbounds [30000 x i16], ptr @tape, i64 0, i64 %i575
+  %v577 = load i16, ptr %a576
+  %z578 = icmp ne i16 %v577, 0
+  br i1 %z578, label %b135, label %b149
+b149:
+  ret i32 0
+}
@@ -0,0 +1,242 @@
+use std::io::{self, Write};
+
+use bf_interpreter::{
+    Backend, Config, ConfigError, EofPolicy, Error, Interpreter, OptLevel, OutputEncoding, Parser,
+    RunnerBuilder, RuntimeError, RuntimeErrorKind, Span, TapePolicy,
+};
+
+fn parse(src: &str) -> Vec<bf_interpreter::Cmd> {
+    src.parse::<Parser>().unwrap().parse_all().unwrap()
+}
+
+/// Records everything written to it along with how often it was flushed.
+#[derive(Default)]
+struct Recorder {
+    written: Vec<u8>,
+    flushes: usize,
+}
+
+impl Write for Recorder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flushes += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn output_is_captured() {
+    let cmds = parse("++++++++[>++++++++<-]>+.+.+.");
+
+    assert_eq!(
+        Interpreter::new().run_with_input(&cmds, b"").unwrap(),
+        b"ABC"
+    );
+}
+
+#[test]
+fn input_is_scripted() {
+    // Echoes its input up to a zero byte
+    let cmds = parse(",[.,]");
+
+    assert_eq!(
+        Interpreter::new().run_with_input(&cmds, b"echo").unwrap(),
+        b"echo"
+    );
+}
+
+#[test]
+fn any_reader_and_writer_can_be_used() {
+    let cmds = parse(",+.,+.");
+    let mut input = io::Cursor::new(b"ab".to_vec());
+    let mut output = Recorder::default();
+
+    Interpreter::new()
+        .run_with_io(&cmds, &mut input, &mut output)
+        .unwrap();
+
+    assert_eq!(output.written, b"bc");
+    assert_eq!(input.position(), 2);
+    assert!(
+        output.flushes > 0,
+        "output is flushed once the program stops"
+    );
+}
+
+#[test]
+fn runners_capture_output_too() {
+    let mut runner = RunnerBuilder::from_bytes(b",[.,]").build().unwrap();
+
+    assert_eq!(runner.run_with_input(b"one").unwrap(), b"one");
+    // Every run starts from a fresh tape and reads its own input
+    assert_eq!(runner.run_with_input(b"two").unwrap(), b"two");
+}
+
+#[cfg(all(target_arch = "x86_64", unix))]
+#[test]
+fn jit_output_is_captured() {
+    let cmds = parse(",[.,]");
+    let mut jit = bf_interpreter::jit::Jit::compile(&cmds).unwrap();
+
+    assert_eq!(jit.run_with_input(b"jit").unwrap(), b"jit");
+}
+
+/// Runs `src` at `level` and returns the memory pointer its runtime error reports.
+fn run_failing(src: &str, level: OptLevel, backend: Backend, config: Config) -> RuntimeError {
+    let err = RunnerBuilder::from_bytes(src.as_bytes())
+        .backend(backend)
+        .opt_level(level)
+        .config(config)
+        .build()
+        .unwrap()
+        .run_with_input(b"")
+        .unwrap_err();
+
+    match err {
+        Error::Runtime(err) => err,
+        err => panic!("expected a runtime error, got {err}"),
+    }
+}
+
+#[test]
+fn errors_report_the_same_cell_at_every_level() {
+    let cases = [
+        (
+            ">,",
+            Config {
+                eof: EofPolicy::Error,
+                ..Config::default()
+            },
+        ),
+        (
+            ">-.",
+            Config {
+                output: OutputEncoding::Ascii,
+                ..Config::default()
+            },
+        ),
+    ];
+
+    let mut backends = vec![Backend::Interpreter];
+    #[cfg(all(target_arch = "x86_64", unix))]
+    backends.push(Backend::Jit);
+
+    for (src, config) in cases {
+        for &backend in &backends {
+            let unoptimized = run_failing(src, OptLevel::O0, backend, config.clone()).mem_ptr;
+            let optimized = run_failing(src, OptLevel::O2, backend, config.clone()).mem_ptr;
+
+            assert_eq!(unoptimized, 1, "{src} on {backend:?}");
+            assert_eq!(optimized, unoptimized, "{src} on {backend:?}");
+        }
+    }
+}
+
+#[test]
+fn step_limits_stop_endless_scans_at_every_level() {
+    // The scan loop wraps around a one-cell tape forever
+    let config = Config {
+        tape_len: 1,
+        step_limit: Some(100),
+        ..Config::default()
+    };
+
+    let mut backends = vec![Backend::Interpreter];
+    #[cfg(all(target_arch = "x86_64", unix))]
+    backends.push(Backend::Jit);
+
+    for backend in backends {
+        for level in [OptLevel::O0, OptLevel::O1, OptLevel::O2, OptLevel::O3] {
+            let err = run_failing("+[>]", level, backend, config.clone());
+
+            assert!(
+                matches!(err.kind, RuntimeErrorKind::StepLimit(100)),
+                "{level:?} on {backend:?}: {err}"
+            );
+        }
+    }
+}
+
+#[test]
+fn errors_point_at_their_source_at_every_level() {
+    let cases = [
+        (
+            "+\n >,",
+            Config {
+                eof: EofPolicy::Error,
+                ..Config::default()
+            },
+            (2, 3),
+        ),
+        // The moves are merged into one from -O2, which keeps the span of the first
+        (
+            "+[-]\n<<",
+            Config {
+                tape_policy: TapePolicy::Error,
+                ..Config::default()
+            },
+            (2, 1),
+        ),
+    ];
+
+    let mut backends = vec![Backend::Interpreter];
+    #[cfg(all(target_arch = "x86_64", unix))]
+    backends.push(Backend::Jit);
+
+    for (src, config, (line, column)) in cases {
+        for &backend in &backends {
+            for level in [OptLevel::O0, OptLevel::O1, OptLevel::O2, OptLevel::O3] {
+                let err = run_failing(src, level, backend, config.clone());
+                let span = err.span.expect("built from source");
+
+                assert_eq!(
+                    (span.line, span.column),
+                    (line, column),
+                    "{src:?} at {level:?} on {backend:?}"
+                );
+                assert!(err
+                    .to_string()
+                    .starts_with(&format!("Runtime error at {line}:{column} ")));
+            }
+        }
+    }
+}
+
+#[test]
+fn errors_without_a_source_report_the_command() {
+    let err = Interpreter::with_config(Config {
+        eof: EofPolicy::Error,
+        ..Config::default()
+    })
+    .unwrap()
+    .run_with_input(&parse("+,"), b"")
+    .unwrap_err();
+
+    assert_eq!(err.span, None::<Span>);
+    assert!(err.to_string().starts_with("Runtime error at command 1 "));
+}
+
+#[test]
+fn empty_tapes_are_rejected() {
+    let built = RunnerBuilder::from_bytes(b"+").tape_len(0).build();
+
+    assert!(matches!(built, Err(Error::Config(ConfigError::EmptyTape))));
+
+    let config = Config {
+        tape_len: 0,
+        ..Config::default()
+    };
+    assert!(matches!(
+        Interpreter::with_config(config.clone()),
+        Err(ConfigError::EmptyTape)
+    ));
+    #[cfg(all(target_arch = "x86_64", unix))]
+    assert!(matches!(
+        bf_interpreter::jit::Jit::compile_with_config(&parse("+"), config),
+        Err(Error::Config(ConfigError::EmptyTape))
+    ));
+}
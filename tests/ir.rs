@@ -0,0 +1,109 @@
+use bf_interpreter::{
+    ir::{self, IrError, IrErrorKind},
+    Cmd, Interpreter, Op, OptLevel, Parser, PassManager,
+};
+
+fn cmds(path: &str, level: OptLevel) -> Vec<Cmd> {
+    let cmds = Parser::from_file(path.as_ref())
+        .unwrap()
+        .parse_all()
+        .unwrap();
+    PassManager::new(level).run(cmds)
+}
+
+#[test]
+fn printed_ir_parses_back() {
+    for path in ["tests/hello_world.bf", "tests/test.bf"] {
+        for level in [OptLevel::O0, OptLevel::O3] {
+            let cmds = cmds(path, level);
+            assert_eq!(ir::parse(&ir::print(&cmds)).unwrap(), cmds, "{path}");
+        }
+    }
+}
+
+#[test]
+fn print_format() {
+    let cmds = Parser::from_bytes(b">[->++<]").parse_all().unwrap();
+    let cmds = PassManager::new(OptLevel::O2).run(cmds);
+
+    assert_eq!(
+        ir::print(&cmds),
+        "   0  move 1\n   1  muladd 2 @1\n   2  zero\n"
+    );
+}
+
+#[test]
+fn fixtures_can_leave_out_indices_and_jump_targets() {
+    let cmds = ir::parse(
+        "; prints the input until a zero byte
+         in 1
+         jz
+           out 1 ; echo
+           in 1
+         jnz",
+    )
+    .unwrap();
+
+    assert_eq!(cmds[1], Cmd::new(Op::JmpZero, 5));
+    assert_eq!(cmds[4], Cmd::new(Op::JmpNonZero, 2));
+
+    let output = Interpreter::new().run_with_input(&cmds, b"abc").unwrap();
+    assert_eq!(output, b"abc");
+}
+
+#[test]
+fn errors_point_at_the_line() {
+    let error = |src: &str| ir::parse(src).unwrap_err();
+
+    assert_eq!(
+        error("add 1\nfrob 2"),
+        IrError {
+            kind: IrErrorKind::UnknownOperator("frob".to_string()),
+            line: 2,
+        }
+    );
+    assert_eq!(error("out").kind, IrErrorKind::MissingOperand);
+    assert_eq!(
+        error("zero 3").kind,
+        IrErrorKind::Unexpected("3".to_string())
+    );
+    assert_eq!(error("0 add 1\n2 add 1").kind, IrErrorKind::WrongIndex(2));
+    assert_eq!(error("jz\nadd 1").kind, IrErrorKind::UnmatchedJump);
+    assert_eq!(error("jz 1\njnz 1").kind, IrErrorKind::WrongTarget(2));
+    assert_eq!(error("jz 2\njnz 3").kind, IrErrorKind::WrongTarget(1));
+    assert_eq!(
+        error("jz @1\njnz").kind,
+        IrErrorKind::Unexpected("@1".to_string())
+    );
+    assert_eq!(
+        error("jz 2\njnz 1 @-1").kind,
+        IrErrorKind::Unexpected("@-1".to_string())
+    );
+    assert_eq!(error("scanr 0").kind, IrErrorKind::OperandOutOfRange(0));
+    assert_eq!(
+        error(&format!("scanl {}", isize::MIN)).kind,
+        IrErrorKind::OperandOutOfRange(isize::MIN)
+    );
+    assert_eq!(
+        error(&format!("add 1 @{}", isize::MIN)).kind,
+        IrErrorKind::OffsetOutOfRange(isize::MIN)
+    );
+    assert_eq!(
+        error(&format!("move 1\nadd 1 @{}", isize::MAX)),
+        IrError {
+            kind: IrErrorKind::OffsetOutOfRange(isize::MAX),
+            line: 2,
+        }
+    );
+}
+
+#[test]
+fn offsets_within_the_longest_tape_are_accepted() {
+    let max = bf_interpreter::bytecode::MAX_TAPE_LEN as isize;
+
+    assert!(ir::parse(&format!("add 1 @{max}\nout 1 @-{max}")).is_ok());
+    assert_eq!(
+        ir::parse(&format!("add 1 @{}", max + 1)).unwrap_err().kind,
+        IrErrorKind::OffsetOutOfRange(max + 1)
+    );
+}
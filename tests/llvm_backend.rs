@@ -0,0 +1,115 @@
+mod common;
+
+use std::{env, fs, io::Write, process::Command};
+
+use bf_interpreter::{codegen, CellWidth, Config, EofPolicy, FlushPolicy, TapePolicy};
+
+use common::{optimize, spawn, temp_path};
+
+fn compile(src: &[u8], config: &Config) -> String {
+    codegen::llvm::emit(&optimize(src), config)
+}
+
+/// Runs the LLVM translation of `src` on `input` with `lli`.
+fn run_llvm(src: &[u8], config: &Config, input: &[u8]) -> Vec<u8> {
+    let ll = compile(src, config);
+    let module = temp_path("llvm").with_extension("ll");
+    fs::write(&module, &ll).unwrap();
+
+    let mut lli = Command::new("lli");
+    // Opaque pointers are only the default from LLVM 15 on
+    if lli_version() < 15 {
+        lli.arg("-opaque-pointers");
+    }
+    let mut child = spawn(lli.arg(&module));
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    fs::remove_file(&module).unwrap();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        !stderr.contains("lli:"),
+        "generated LLVM IR failed to load: {stderr}\n{ll}"
+    );
+    output.stdout
+}
+
+/// The major version of the installed `lli`.
+fn lli_version() -> u32 {
+    let output = Command::new("lli")
+        .arg("--version")
+        .output()
+        .unwrap_or_else(|err| panic!("`lli` is needed to test this backend: {err}"));
+    let version = String::from_utf8_lossy(&output.stdout);
+    version
+        .split_once("LLVM version ")
+        .and_then(|(_, rest)| rest.split('.').next()?.parse().ok())
+        .unwrap_or_else(|| panic!("unexpected `lli --version` output: {version}"))
+}
+
+/// Compares the translation of `src` with `tests/golden/{name}.ll`, or rewrites the file
+/// if `UPDATE_GOLDEN` is set.
+fn assert_golden(name: &str, src: &[u8], config: &Config) {
+    let ll = compile(src, config);
+    let path = format!("tests/golden/{name}.ll");
+
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&path, &ll).unwrap();
+    }
+    let golden = fs::read_to_string(&path).unwrap();
+    assert!(
+        ll == golden,
+        "{path} is out of date, rerun with UPDATE_GOLDEN=1 to rewrite it\n{ll}"
+    );
+}
+
+#[test]
+fn test_programs_match_the_interpreter() {
+    common::test_programs_match_the_interpreter(run_llvm);
+}
+
+#[test]
+fn eof_policies_match_the_interpreter() {
+    common::eof_policies_match_the_interpreter(run_llvm);
+}
+
+#[test]
+fn tape_policies_match_the_interpreter() {
+    common::tape_policies_match_the_interpreter(run_llvm, &[TapePolicy::Wrap, TapePolicy::Error]);
+}
+
+#[test]
+fn flush_policies_keep_every_byte() {
+    common::flush_policies_keep_every_byte(run_llvm);
+}
+
+#[test]
+fn output_matches_golden_files() {
+    assert_golden(
+        "hello_world",
+        &fs::read("tests/hello_world.bf").unwrap(),
+        &Config::default(),
+    );
+    assert_golden(
+        "wc",
+        &fs::read("tests/test.bf").unwrap(),
+        &Config {
+            cell_width: CellWidth::U16,
+            tape_policy: TapePolicy::Error,
+            eof: EofPolicy::Max,
+            flush: FlushPolicy::Newline,
+            ..Config::default()
+        },
+    );
+    assert_golden(
+        "idioms",
+        b",[->++<<+>]<[-]>>[<].",
+        &Config {
+            cell_width: CellWidth::U32,
+            tape_len: 64,
+            eof: EofPolicy::Error,
+            flush: FlushPolicy::Always,
+            ..Config::default()
+        },
+    );
+}
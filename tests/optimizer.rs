@@ -0,0 +1,213 @@
+use std::{cell::RefCell, rc::Rc};
+
+use bf_interpreter::{
+    optimize, Backend, Cmd, Op, OptLevel, Parser, PassManager, RunnerBuilder, TapePolicy,
+};
+
+const LEVELS: [OptLevel; 4] = [OptLevel::O0, OptLevel::O1, OptLevel::O2, OptLevel::O3];
+
+const WC_INPUT: &[u8] = b"hello world\nthe quick  brown fox\n\tjumps over\n";
+
+fn backends() -> Vec<Backend> {
+    vec![
+        Backend::Interpreter,
+        #[cfg(all(target_arch = "x86_64", unix))]
+        Backend::Jit,
+    ]
+}
+
+fn run(path: &str, input: &[u8], backend: Backend, level: OptLevel) -> Vec<u8> {
+    RunnerBuilder::from_file(path.as_ref())
+        .unwrap()
+        .backend(backend)
+        .opt_level(level)
+        .build()
+        .unwrap()
+        .run_with_input(input)
+        .unwrap()
+}
+
+fn optimized(src: &str) -> Vec<Cmd> {
+    optimize(src.parse::<Parser>().unwrap().parse_all().unwrap())
+}
+
+#[test]
+fn hello_world_output_is_unchanged() {
+    for backend in backends() {
+        for level in LEVELS {
+            let output = run("tests/hello_world.bf", b"", backend, level);
+            assert_eq!(output, b"Hello world!\n", "{backend:?} at {level:?}");
+        }
+    }
+}
+
+#[test]
+fn wc_output_is_unchanged() {
+    for backend in backends() {
+        let expected = run("tests/test.bf", WC_INPUT, backend, OptLevel::O0);
+        for level in LEVELS {
+            let output = run("tests/test.bf", WC_INPUT, backend, level);
+            assert_eq!(output, expected, "{backend:?} at {level:?}");
+        }
+    }
+}
+
+#[test]
+fn clear_loops_become_set_zero() {
+    for src in ["[-]", "[+]", "[---]"] {
+        assert_eq!(optimized(src), [Cmd::new(Op::SetZero, 0)]);
+    }
+
+    // Never terminates on odd values, so it must stay a loop
+    assert_eq!(optimized("[--]").len(), 3);
+}
+
+#[test]
+fn scan_loops_become_scans() {
+    assert_eq!(optimized("[>]"), [Cmd::new(Op::ScanRight, 1)]);
+    assert_eq!(optimized("[<<]"), [Cmd::new(Op::ScanLeft, 2)]);
+}
+
+#[test]
+fn copy_loops_become_mul_add() {
+    let mul_add = |offset, factor| Cmd {
+        operator: Op::MulAdd,
+        operand: factor,
+        offset,
+    };
+
+    assert_eq!(
+        optimized("[->+<]"),
+        [mul_add(1, 1), Cmd::new(Op::SetZero, 0)]
+    );
+    assert_eq!(
+        optimized("[<<+++>>->---<]"),
+        [mul_add(-2, 3), mul_add(1, -3), Cmd::new(Op::SetZero, 0)]
+    );
+    // Counting up runs the loop `-cell` times
+    assert_eq!(
+        optimized("[+>++<]"),
+        [mul_add(1, -2), Cmd::new(Op::SetZero, 0)]
+    );
+}
+
+#[test]
+fn jumps_are_relinked() {
+    let cmds = optimized("+[>[-]<-]");
+    let ops: Vec<_> = cmds.iter().map(|cmd| cmd.operator).collect();
+
+    assert_eq!(
+        ops,
+        [Op::Add, Op::JmpZero, Op::SetZero, Op::Add, Op::JmpNonZero]
+    );
+    assert_eq!(cmds[1].operand, 5);
+    assert_eq!(cmds[4].operand, 2);
+}
+
+#[test]
+fn moves_are_folded_into_offsets() {
+    let add = |offset, operand| Cmd {
+        operator: Op::Add,
+        operand,
+        offset,
+    };
+
+    assert_eq!(optimized(">+>++<<-"), [add(1, 1), add(2, 2), add(0, -1)]);
+    assert_eq!(
+        optimized(">+>[-<]"),
+        [
+            add(1, 1),
+            Cmd::new(Op::Move, 2),
+            Cmd::new(Op::JmpZero, 6),
+            add(0, -1),
+            Cmd::new(Op::Move, -1),
+            Cmd::new(Op::JmpNonZero, 3),
+        ]
+    );
+    // The pointer still ends up where the program left it
+    assert_eq!(
+        optimized(".>>"),
+        [Cmd::new(Op::Out, 1), Cmd::new(Op::Move, 2)]
+    );
+}
+
+#[test]
+fn offsets_follow_the_tape_policy() {
+    // Reaches left of the start of the tape and back without moving there
+    let src = b"<+<++>>+++<<<++++>>>[<<<[->+<]>>>-]<<<.>.>.>.";
+
+    for policy in [TapePolicy::Wrap, TapePolicy::Grow, TapePolicy::Error] {
+        for backend in backends() {
+            let run = |level| {
+                RunnerBuilder::from_bytes(src)
+                    .backend(backend)
+                    .tape_len(8)
+                    .tape_policy(policy)
+                    .opt_level(level)
+                    .build()
+                    .unwrap()
+                    .run_with_input(b"")
+                    .map_err(|err| err.to_string())
+            };
+
+            match (run(OptLevel::O0), run(OptLevel::O3)) {
+                (Ok(expected), Ok(output)) => assert_eq!(output, expected),
+                (Err(_), Err(_)) => assert_eq!(policy, TapePolicy::Error),
+                (expected, output) => panic!("{expected:?} != {output:?}"),
+            }
+        }
+    }
+}
+
+#[test]
+fn dead_loops_are_removed() {
+    let mut passes = PassManager::new(OptLevel::O3);
+    let cmds = passes.run(
+        Parser::from_bytes(b"[comment]+[->+<][.]>[-]")
+            .parse_all()
+            .unwrap(),
+    );
+
+    let ops: Vec<_> = cmds.iter().map(|cmd| cmd.operator).collect();
+    assert_eq!(
+        ops,
+        [Op::Add, Op::MulAdd, Op::SetZero, Op::SetZero, Op::Move]
+    );
+}
+
+#[test]
+fn passes_can_be_toggled_by_name() {
+    let names = |passes: &PassManager| passes.passes().map(|pass| pass.name).collect::<Vec<_>>();
+
+    assert!(names(&PassManager::new(OptLevel::O0)).is_empty());
+    assert_eq!(
+        names(&PassManager::new(OptLevel::O3)),
+        ["loops", "dead-loops", "offsets"]
+    );
+
+    let mut passes = PassManager::new(OptLevel::O1);
+    passes.enable("offsets").unwrap();
+    passes.disable("loops").unwrap();
+    assert_eq!(names(&passes), ["offsets"]);
+
+    assert!(passes.enable("inline").is_err());
+}
+
+#[test]
+fn after_pass_sees_every_pass() {
+    let seen = Rc::new(RefCell::new(Vec::new()));
+
+    let mut passes = PassManager::new(OptLevel::O2);
+    let log = Rc::clone(&seen);
+    passes.after_pass(move |name, cmds| log.borrow_mut().push((name.to_string(), cmds.len())));
+    passes.run(Parser::from_bytes(b">[-]<").parse_all().unwrap());
+
+    assert_eq!(
+        *seen.borrow(),
+        [
+            ("parse".to_string(), 5),
+            ("loops".to_string(), 3),
+            ("offsets".to_string(), 1),
+        ]
+    );
+}
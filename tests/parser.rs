@@ -0,0 +1,82 @@
+use std::io::{self, Read};
+
+use bf_interpreter::{Cmd, Op, ParseError, Parser};
+
+fn parse(src: &str) -> Vec<Cmd> {
+    src.parse::<Parser>().unwrap().parse_all().unwrap()
+}
+
+#[test]
+fn opposing_ops_fold_into_signed_commands() {
+    assert_eq!(parse("++-+"), [Cmd::new(Op::Add, 2)]);
+    assert_eq!(parse("--+-"), [Cmd::new(Op::Add, -2)]);
+    assert_eq!(parse("><<<"), [Cmd::new(Op::Move, -2)]);
+    assert_eq!(parse("> > comment >"), [Cmd::new(Op::Move, 3)]);
+}
+
+#[test]
+fn cancelled_ops_are_dropped() {
+    assert_eq!(parse("+-"), []);
+    assert_eq!(parse("<>-+"), []);
+
+    // Dropping the move lets the adds either side of it merge
+    assert_eq!(parse("+<>+"), [Cmd::new(Op::Add, 2)]);
+    assert_eq!(parse(".<>."), [Cmd::new(Op::Out, 2)]);
+}
+
+#[test]
+fn folding_stops_at_brackets() {
+    assert_eq!(
+        parse("+[-+]+"),
+        [
+            Cmd::new(Op::Add, 1),
+            Cmd::new(Op::JmpZero, 3),
+            Cmd::new(Op::JmpNonZero, 2),
+            Cmd::new(Op::Add, 1),
+        ]
+    );
+}
+
+/// Yields its bytes, then fails.
+struct Failing<'a>(&'a [u8]);
+
+impl Read for Failing<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.0.is_empty() {
+            return Err(io::Error::other("connection reset"));
+        }
+        self.0.read(buf)
+    }
+}
+
+#[test]
+fn read_errors_are_not_end_of_input() {
+    let err = Parser::from_reader(Failing(b"+\n[-"))
+        .parse_all()
+        .unwrap_err();
+
+    let ParseError::Io(span, err) = err else {
+        panic!("expected an I/O error, got {err:?}");
+    };
+    assert_eq!((span.line, span.column), (2, 3));
+    assert_eq!(err.to_string(), "connection reset");
+
+    let errors = Parser::from_reader(Failing(b"[["))
+        .parse_all_recovering()
+        .unwrap_err();
+    assert!(matches!(errors[..], [ParseError::Io(..)]));
+}
+
+#[test]
+fn readers_can_be_borrowed() {
+    let src = String::from("+>.");
+
+    assert_eq!(
+        Parser::from_reader(src.as_bytes()).parse_all().unwrap(),
+        [
+            Cmd::new(Op::Add, 1),
+            Cmd::new(Op::Move, 1),
+            Cmd::new(Op::Out, 1)
+        ]
+    );
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use std::{
+    fs,
+    io::Write,
+    process::{Command, Output},
+};
+
+use bf_interpreter::{codegen, ir, Cmd, Config, OptLevel, Parser, PassManager, TapePolicy};
+
+use common::{build, optimize, spawn, temp_path};
+
+/// Builds the Rust translation of `src` with `rustc` and runs it on `input`.
+fn run_rust(src: &[u8], config: &Config, input: &[u8]) -> Vec<u8> {
+    run_cmds(&optimize(src), config, input).stdout
+}
+
+/// Like [`run_rust`], but for commands as given.
+fn run_cmds(cmds: &[Cmd], config: &Config, input: &[u8]) -> Output {
+    let rust = codegen::rust::emit(cmds, config);
+
+    let binary = temp_path("rust");
+    let source = binary.with_extension("rs");
+    fs::write(&source, &rust).unwrap();
+
+    let built = build(
+        Command::new("rustc")
+            .args(["--edition", "2021", "-D", "warnings", "-o"])
+            .arg(&binary)
+            .arg(&source),
+    );
+    fs::remove_file(&source).unwrap();
+    assert!(built, "generated Rust failed to compile:\n{rust}");
+
+    let mut child = spawn(&mut Command::new(&binary));
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    let output = child.wait_with_output().unwrap();
+    fs::remove_file(&binary).unwrap();
+
+    output
+}
+
+#[test]
+fn test_programs_match_the_interpreter() {
+    common::test_programs_match_the_interpreter(run_rust);
+}
+
+#[test]
+fn eof_policies_match_the_interpreter() {
+    common::eof_policies_match_the_interpreter(run_rust);
+}
+
+#[test]
+fn tape_policies_match_the_interpreter() {
+    common::tape_policies_match_the_interpreter(
+        run_rust,
+        &[TapePolicy::Wrap, TapePolicy::Error, TapePolicy::Grow],
+    );
+}
+
+#[test]
+fn flush_policies_keep_every_byte() {
+    common::flush_policies_keep_every_byte(run_rust);
+}
+
+#[test]
+fn idioms_become_assignments() {
+    let cmds = PassManager::new(OptLevel::O3)
+        .run(Parser::from_bytes(b",[->++<<+>]<[-].").parse_all().unwrap());
+    let rust = codegen::rust::emit(&cmds, &Config::default());
+
+    let body = rust
+        .split_once("let p = 0;\n\n")
+        .and_then(|(_, rest)| rest.split_once("\n    output.flush()"))
+        .unwrap()
+        .0;
+    assert_eq!(
+        body,
+        "    get(input, &mut tape[p], 1)?;
+    let c = (p + 1) % LEN;
+    tape[c] = tape[c].wrapping_add(tape[p].wrapping_mul(2));
+    let c = (p + LEN - 1) % LEN;
+    tape[c] = tape[c].wrapping_add(tape[p]);
+    tape[p] = 0;
+    let c = (p + LEN - 1) % LEN;
+    tape[c] = 0;
+    let c = (p + LEN - 1) % LEN;
+    put(output, tape[c], 1)?;
+"
+    );
+}
+
+#[test]
+fn trailing_moves_start_where_the_last_one_ended() {
+    // Neither move leaves a three-cell tape on its own, but together they do
+    let cmds = ir::parse("add 1\nmove 2\nmove 2").unwrap();
+    let config = |tape_len| Config {
+        tape_len,
+        tape_policy: TapePolicy::Error,
+        ..Config::default()
+    };
+
+    let short = run_cmds(&cmds, &config(3), b"");
+    assert!(!short.status.success());
+
+    let long = run_cmds(&cmds, &config(5), b"");
+    assert!(long.status.success());
+}
@@ -0,0 +1,439 @@
+mod common;
+
+use bf_interpreter::{codegen, CellWidth, Config, Parser, TapePolicy};
+
+use common::optimize;
+
+/// Reads the parts of a binary module the backend writes.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> u8 {
+        let byte = self.bytes[self.pos];
+        self.pos += 1;
+        byte
+    }
+
+    fn unsigned(&mut self) -> u64 {
+        let mut value = 0;
+        for shift in (0..).step_by(7) {
+            let byte = self.byte();
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        value
+    }
+
+    fn signed(&mut self) -> i64 {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte();
+            value |= i64::from(byte & 0x7F) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                if shift < 64 && byte & 0x40 != 0 {
+                    value |= -1 << shift;
+                }
+                return value;
+            }
+        }
+    }
+
+    fn name(&mut self) -> String {
+        let len = self.unsigned() as usize;
+        self.pos += len;
+        String::from_utf8(self.bytes[self.pos - len..self.pos].to_vec()).unwrap()
+    }
+
+    fn vec<T>(&mut self, mut item: impl FnMut(&mut Self) -> T) -> Vec<T> {
+        (0..self.unsigned()).map(|_| item(self)).collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Instr {
+    Unreachable,
+    Block,
+    Loop,
+    If,
+    End,
+    Br(usize),
+    BrIf(usize),
+    Call(u64),
+    LocalGet(usize),
+    LocalSet(usize),
+    LocalTee(usize),
+    I32Const(i32),
+    I64Const(i64),
+    /// Any instruction without immediates.
+    Numeric(u8),
+    /// Loads or stores, with their opcode and offset.
+    Memory(u8, u32),
+}
+
+/// A decoded module: its memory size in pages and the body of its one function.
+struct Module {
+    pages: u64,
+    locals: usize,
+    body: Vec<Instr>,
+}
+
+type FuncType = (Vec<u8>, Vec<u8>);
+
+fn decode(bytes: &[u8]) -> Module {
+    assert_eq!(&bytes[..8], b"\0asm\x01\0\0\0", "magic and version");
+    let mut reader = Reader { bytes, pos: 8 };
+
+    let mut types: Vec<FuncType> = Vec::new();
+    let mut imports = Vec::new();
+    let mut funcs = Vec::new();
+    let mut pages = None;
+    let mut exports = Vec::new();
+    let mut code = None;
+    let mut last_id = 0;
+
+    while reader.pos < bytes.len() {
+        let id = reader.byte();
+        assert!(id > last_id, "section {id} out of order");
+        last_id = id;
+        let size = reader.unsigned() as usize;
+        let end = reader.pos + size;
+
+        match id {
+            1 => {
+                types = reader.vec(|r| {
+                    assert_eq!(r.byte(), 0x60);
+                    (r.vec(Reader::byte), r.vec(Reader::byte))
+                })
+            }
+            2 => {
+                imports = reader.vec(|r| {
+                    let name = (r.name(), r.name());
+                    assert_eq!(r.byte(), 0x00, "only functions are imported");
+                    (name, r.unsigned() as usize)
+                })
+            }
+            3 => funcs = reader.vec(|r| r.unsigned() as usize),
+            5 => {
+                assert_eq!(reader.unsigned(), 1, "one memory");
+                assert_eq!(reader.byte(), 0x01, "with a maximum");
+                let min = reader.unsigned();
+                assert_eq!(reader.unsigned(), min, "of a fixed size");
+                pages = Some(min);
+            }
+            7 => exports = reader.vec(|r| (r.name(), r.byte(), r.unsigned())),
+            10 => {
+                assert_eq!(reader.unsigned(), 1, "one function body");
+                let body_end = reader.unsigned() as usize + reader.pos;
+                let locals = reader
+                    .vec(|r| (r.unsigned() as usize, r.byte()))
+                    .iter()
+                    .map(|&(count, _)| count)
+                    .sum();
+                code = Some((locals, instructions(&mut reader, body_end)));
+            }
+            _ => panic!("unexpected section {id}"),
+        }
+        assert_eq!(reader.pos, end, "size of section {id}");
+    }
+
+    let func_type = |index: usize| types[index].clone();
+    let i32_out: FuncType = (vec![0x7F], vec![]);
+    let i32_in: FuncType = (vec![], vec![0x7F]);
+    assert_eq!(imports.len(), 2);
+    assert_eq!(imports[0].0, ("env".to_string(), "out".to_string()));
+    assert_eq!(func_type(imports[0].1), i32_out);
+    assert_eq!(imports[1].0, ("env".to_string(), "in".to_string()));
+    assert_eq!(func_type(imports[1].1), i32_in);
+    assert_eq!(funcs.len(), 1);
+    assert_eq!(func_type(funcs[0]), (vec![], vec![]));
+    assert!(exports.contains(&("memory".to_string(), 0x02, 0)));
+    assert!(exports.contains(&("run".to_string(), 0x00, 2)));
+
+    let (locals, body) = code.expect("a code section");
+    Module {
+        pages: pages.expect("a memory section"),
+        locals,
+        body,
+    }
+}
+
+/// Decodes instructions up to `end`, which must close the function body.
+fn instructions(reader: &mut Reader, end: usize) -> Vec<Instr> {
+    let mut body = Vec::new();
+    let mut depth = 0;
+
+    while reader.pos < end {
+        let opcode = reader.byte();
+        let instr = match opcode {
+            0x00 => Instr::Unreachable,
+            0x02..=0x04 => {
+                assert_eq!(reader.byte(), 0x40, "blocks have no type");
+                depth += 1;
+                [Instr::Block, Instr::Loop, Instr::If][opcode as usize - 2]
+            }
+            0x0B => {
+                depth -= 1;
+                Instr::End
+            }
+            0x0C => Instr::Br(reader.unsigned() as usize),
+            0x0D => Instr::BrIf(reader.unsigned() as usize),
+            0x10 => Instr::Call(reader.unsigned()),
+            0x20 => Instr::LocalGet(reader.unsigned() as usize),
+            0x21 => Instr::LocalSet(reader.unsigned() as usize),
+            0x22 => Instr::LocalTee(reader.unsigned() as usize),
+            0x41 => Instr::I32Const(reader.signed() as i32),
+            0x42 => Instr::I64Const(reader.signed()),
+            0x29 | 0x31 | 0x33 | 0x35 | 0x37 | 0x3C..=0x3E => {
+                let align = reader.unsigned();
+                let offset = reader.unsigned() as u32;
+                let bytes = match opcode {
+                    0x31 | 0x3C => 1,
+                    0x33 | 0x3D => 2,
+                    0x35 | 0x3E => 4,
+                    _ => 8,
+                };
+                assert!(1 << align <= bytes, "alignment larger than the access");
+                body.push(Instr::Memory(opcode, offset));
+                continue;
+            }
+            0x45 | 0x48 | 0x4F | 0x50 | 0x6A | 0x70 | 0x71 | 0x74 | 0x7C | 0x7E | 0xA7 | 0xAD => {
+                Instr::Numeric(opcode)
+            }
+            _ => panic!("unexpected opcode {opcode:#04x}"),
+        };
+        body.push(instr);
+    }
+
+    assert_eq!(depth, -1, "the body ends with its own `end`");
+    body
+}
+
+/// Runs the module's function, returning its memory and output, or the output up to a
+/// trap.
+fn execute(module: &Module, mut input: &[u8]) -> Result<(Vec<u8>, Vec<u8>), Vec<u8>> {
+    let body = &module.body;
+    // Where each block, loop and if ends
+    let mut ends = vec![0; body.len()];
+    let mut open = Vec::new();
+    for (pc, instr) in body.iter().enumerate() {
+        match instr {
+            Instr::Block | Instr::Loop | Instr::If => open.push(pc),
+            Instr::End => {
+                if let Some(start) = open.pop() {
+                    ends[start] = pc;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut memory = vec![0u8; module.pages as usize * 0x10000];
+    let mut output = Vec::new();
+    let mut locals = vec![0u64; module.locals];
+    let mut stack: Vec<u64> = Vec::new();
+    // Where a branch to each enclosing label continues
+    let mut labels = Vec::new();
+    let mut pc = 0;
+
+    macro_rules! pop {
+        () => {
+            stack.pop().expect("operand on the stack")
+        };
+    }
+
+    loop {
+        let instr = body[pc];
+        pc += 1;
+
+        match instr {
+            Instr::Unreachable => return Err(output),
+            Instr::Block => labels.push(ends[pc - 1] + 1),
+            Instr::Loop => labels.push(pc - 1),
+            Instr::If => match pop!() as u32 {
+                0 => pc = ends[pc - 1] + 1,
+                _ => labels.push(ends[pc - 1] + 1),
+            },
+            Instr::End => {
+                if labels.pop().is_none() {
+                    return Ok((memory, output));
+                }
+            }
+            Instr::Br(depth) => pc = branch(&mut labels, depth),
+            Instr::BrIf(depth) => {
+                if pop!() as u32 != 0 {
+                    pc = branch(&mut labels, depth);
+                }
+            }
+            Instr::Call(0) => output.push(pop!() as u8),
+            Instr::Call(1) => {
+                let byte = match input.split_first() {
+                    Some((&byte, rest)) => {
+                        input = rest;
+                        byte as u32
+                    }
+                    None => u32::MAX,
+                };
+                stack.push(byte.into());
+            }
+            Instr::Call(func) => panic!("call to function {func}"),
+            Instr::LocalGet(local) => stack.push(locals[local]),
+            Instr::LocalSet(local) => locals[local] = pop!(),
+            Instr::LocalTee(local) => locals[local] = *stack.last().unwrap(),
+            Instr::I32Const(value) => stack.push(u64::from(value as u32)),
+            Instr::I64Const(value) => stack.push(value as u64),
+            Instr::Numeric(opcode) => {
+                let value = match opcode {
+                    0x45 => u64::from(pop!() as u32 == 0),
+                    0x50 => u64::from(pop!() == 0),
+                    0xA7 => u64::from(pop!() as u32),
+                    0xAD => pop!(),
+                    _ => {
+                        let (b, a) = (pop!(), pop!());
+                        let (a32, b32) = (a as u32, b as u32);
+                        match opcode {
+                            0x48 => u64::from((a32 as i32) < (b32 as i32)),
+                            0x4F => u64::from(a32 >= b32),
+                            0x6A => a32.wrapping_add(b32).into(),
+                            0x70 => a32.checked_rem(b32).ok_or_else(|| output.clone())?.into(),
+                            0x71 => (a32 & b32).into(),
+                            0x74 => a32.wrapping_shl(b32).into(),
+                            0x7C => a.wrapping_add(b),
+                            0x7E => a.wrapping_mul(b),
+                            _ => unreachable!(),
+                        }
+                    }
+                };
+                stack.push(value);
+            }
+            Instr::Memory(opcode, offset) => {
+                let size = match opcode {
+                    0x31 | 0x3C => 1,
+                    0x33 | 0x3D => 2,
+                    0x35 | 0x3E => 4,
+                    _ => 8,
+                };
+                let store = matches!(opcode, 0x37 | 0x3C..=0x3E);
+                let value = if store { pop!() } else { 0 };
+                let addr = (pop!() as u32 + offset) as usize;
+                let Some(bytes) = memory.get_mut(addr..addr + size) else {
+                    return Err(output);
+                };
+
+                if store {
+                    bytes.copy_from_slice(&value.to_le_bytes()[..size]);
+                } else {
+                    let mut value = [0; 8];
+                    value[..size].copy_from_slice(bytes);
+                    stack.push(u64::from_le_bytes(value));
+                }
+            }
+        }
+    }
+}
+
+/// Leaves the label `depth` levels out and returns where execution continues.
+fn branch(labels: &mut Vec<usize>, depth: usize) -> usize {
+    let index = labels.len() - 1 - depth;
+    let target = labels[index];
+    labels.truncate(index);
+    target
+}
+
+fn compile(src: &[u8], config: &Config) -> Module {
+    decode(&codegen::wasm::emit(&optimize(src), config))
+}
+
+/// Runs the translation of `src` on `input`, returning its output up to any trap.
+fn run_wasm(src: &[u8], config: &Config, input: &[u8]) -> Vec<u8> {
+    match execute(&compile(src, config), input) {
+        Ok((_, output)) | Err(output) => output,
+    }
+}
+
+#[test]
+fn test_programs_match_the_interpreter() {
+    common::test_programs_match_the_interpreter(run_wasm);
+}
+
+#[test]
+fn eof_policies_match_the_interpreter() {
+    common::eof_policies_match_the_interpreter(run_wasm);
+}
+
+#[test]
+fn tape_policies_match_the_interpreter() {
+    common::tape_policies_match_the_interpreter(run_wasm, &[TapePolicy::Wrap, TapePolicy::Error]);
+}
+
+#[test]
+fn flush_policies_keep_every_byte() {
+    common::flush_policies_keep_every_byte(run_wasm);
+}
+
+#[test]
+fn moving_off_the_tape_traps() {
+    let config = Config {
+        tape_len: 4,
+        tape_policy: TapePolicy::Error,
+        ..Config::default()
+    };
+    let result = execute(&compile(b"+++++[->++++++++++++<]>+.<<", &config), b"");
+
+    assert_eq!(result, Err(b"=".to_vec()));
+}
+
+#[test]
+fn memory_holds_the_tape() {
+    let config = Config {
+        tape_len: 100_000,
+        cell_width: CellWidth::U16,
+        ..Config::default()
+    };
+    let module = compile(b"+>-->>+++", &config);
+    assert_eq!(module.pages, 4);
+
+    let (memory, _) = execute(&module, b"").unwrap();
+    assert_eq!(&memory[..8], &[1, 0, 0xFE, 0xFF, 0, 0, 3, 0]);
+}
+
+#[test]
+fn text_lists_each_command_with_its_instructions() {
+    let cmds = Parser::from_bytes(b"+.").parse_all().unwrap();
+
+    assert_eq!(
+        codegen::wasm::emit_wat(&cmds, &Config::default()),
+        r#"(module
+  (import "env" "out" (func $out (param i32)))
+  (import "env" "in" (func $in (result i32)))
+  (memory (export "memory") 1 1)
+  (func (export "run")
+    (local $p i32) (local $t i32) (local $a i32) (local $b i32) (local $v i64)
+    ;; add 1
+    local.get $p
+    local.tee $a
+    local.get $a
+    i64.load8_u
+    i64.const 1
+    i64.add
+    i64.store8
+    ;; out 1
+    local.get $p
+    i64.load8_u
+    i32.wrap_i64
+    i32.const 255
+    i32.and
+    local.set $b
+    local.get $b
+    call $out
+  )
+)
+"#
+    );
+}